@@ -0,0 +1,32 @@
+#![feature(async_await, await_macro, futures_api, test)]
+extern crate test;
+
+use std::net::TcpStream;
+
+use futures::executor;
+use romio::TcpListener;
+use test::Bencher;
+
+const CONNECTIONS: usize = 64;
+
+#[bench]
+fn accept(b: &mut Bencher) {
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    b.iter(|| {
+        // The connections complete in the kernel before any of them is
+        // accepted, so this measures the accept path alone.
+        let clients: Vec<_> = (0..CONNECTIONS)
+            .map(|_| TcpStream::connect(&addr).unwrap())
+            .collect();
+
+        executor::block_on(async {
+            for _ in 0..CONNECTIONS {
+                await!(server.accept()).unwrap();
+            }
+        });
+
+        clients
+    });
+}
@@ -3,31 +3,28 @@
 use std::io;
 
 use futures::StreamExt;
-use futures::executor::{self, ThreadPool};
 use futures::io::AsyncReadExt;
-use futures::task::{SpawnExt};
 
 use romio::{TcpListener, TcpStream};
 
 fn main() -> io::Result<()> {
-    executor::block_on(async {
-        let mut threadpool = ThreadPool::new()?;
-
-        let mut listener = TcpListener::bind(&"127.0.0.1:7878".parse().unwrap())?;
+    romio::run(async {
+        let listener = TcpListener::bind(&"127.0.0.1:7878".parse().unwrap())?;
 
         println!("Listening on 127.0.0.1:7878");
 
-        while let Some(stream) = await!(listener.next()) {
-            let stream = stream?;
-            let addr = stream.peer_addr()?;
+        let mut incoming = listener.incoming_with_addr();
+
+        while let Some(accepted) = await!(incoming.next()) {
+            let (stream, addr) = accepted?;
 
-            threadpool.spawn(async move {
+            romio::spawn(async move {
                 println!("Accepting stream from: {}", addr);
 
                 await!(echo_on(stream)).unwrap();
 
                 println!("Closing stream from: {}", addr);
-            }).unwrap();
+            }).detach();
         }
 
         Ok(())
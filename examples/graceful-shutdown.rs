@@ -0,0 +1,43 @@
+#![feature(async_await, await_macro, futures_api)]
+
+use std::io;
+use std::sync::Mutex;
+
+use futures::channel::oneshot;
+use futures::executor::{self, ThreadPool};
+use futures::io::AsyncReadExt;
+use futures::FutureExt;
+
+use romio::TcpListener;
+
+fn main() -> io::Result<()> {
+    let (stop, stopped) = oneshot::channel();
+    let stop = Mutex::new(Some(stop));
+
+    ctrlc::set_handler(move || {
+        if let Some(stop) = stop.lock().unwrap().take() {
+            println!("Shutting down, waiting for open connections to close");
+            let _ = stop.send(());
+        }
+    }).expect("failed to install Ctrl-C handler");
+
+    executor::block_on(async {
+        let threadpool = ThreadPool::new()?;
+
+        let listener = TcpListener::bind(&"127.0.0.1:7878".parse().unwrap())?;
+
+        println!("Listening on 127.0.0.1:7878, press Ctrl-C to stop");
+
+        await!(listener.serve(threadpool, stopped.map(|_| ()), |stream, addr| async move {
+            println!("Accepting stream from: {}", addr);
+
+            let (mut reader, mut writer) = stream.split();
+            let _ = await!(reader.copy_into(&mut writer));
+
+            println!("Closing stream from: {}", addr);
+        }))?;
+
+        println!("All connections closed");
+        Ok(())
+    })
+}
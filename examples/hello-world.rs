@@ -0,0 +1,35 @@
+#![feature(async_await, await_macro, futures_api)]
+
+use std::io;
+
+use futures::io::{AsyncReadExt, AsyncWriteExt};
+use futures::FutureExt;
+
+use romio::{TcpListener, TcpStream};
+
+fn main() -> io::Result<()> {
+    // romio::run drives the reactor and the future on this thread, no executor
+    // needed.
+    romio::run(async {
+        let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap())?;
+        let addr = listener.local_addr()?;
+
+        let server = async {
+            let (mut stream, _) = await!(listener.accept())?;
+            await!(stream.write_all(b"Hello, world!\n"))?;
+            Ok::<(), io::Error>(())
+        };
+
+        let client = async {
+            let mut stream = await!(TcpStream::connect(&addr))?;
+            let mut greeting = String::new();
+            await!(stream.read_to_string(&mut greeting))?;
+            print!("{}", greeting);
+            Ok::<(), io::Error>(())
+        };
+
+        let (server, client) = await!(server.join(client));
+        server?;
+        client
+    })
+}
@@ -0,0 +1,248 @@
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+
+use bytes::BytesMut;
+use futures::io::{AsyncRead, AsyncWrite};
+use futures::sink::Sink;
+use futures::stream::Stream;
+use futures::task::LocalWaker;
+use futures::{ready, Poll};
+
+use super::{Decoder, Encoder};
+
+/// The number of bytes the read buffer grows by when more data is needed.
+const READ_CHUNK: usize = 8 * 1024;
+
+/// The default maximum size of the read buffer.
+const DEFAULT_MAX_BUFFER_SIZE: usize = 8 * 1024 * 1024;
+
+/// The size of the write buffer past which `poll_ready` flushes first.
+const BACKPRESSURE_BOUNDARY: usize = 128 * 1024;
+
+/// A unified `Stream` and `Sink` of frames, using a codec to decode and
+/// encode them to and from an underlying I/O object.
+///
+/// Frames are read by reading bytes from the I/O object into a buffer and
+/// calling [`Decoder::decode`] until a frame is produced. Frames are written
+/// by calling [`Encoder::encode`] into a write buffer, which is written to the
+/// I/O object when the sink is flushed.
+///
+/// # Buffer growth
+///
+/// The read buffer starts out empty and grows by 8 KiB whenever the codec
+/// needs more bytes to complete a frame. To protect against peers sending
+/// frames that never complete, the read buffer is capped: if a frame still
+/// can't be decoded once the buffer holds [`max_buffer_size`] bytes (8 MiB by
+/// default), the stream yields an error of kind `InvalidData`.
+///
+/// [`Decoder::decode`]: trait.Decoder.html#tymethod.decode
+/// [`Encoder::encode`]: trait.Encoder.html#tymethod.encode
+/// [`max_buffer_size`]: #method.max_buffer_size
+pub struct Framed<T, C> {
+    io: T,
+    codec: C,
+    read_buf: BytesMut,
+    write_buf: BytesMut,
+    max_buffer_size: usize,
+    is_readable: bool,
+    eof: bool,
+}
+
+impl<T, C> Framed<T, C> {
+    /// Creates a new `Framed` over the I/O object `io`, using `codec` to
+    /// decode and encode frames.
+    pub fn new(io: T, codec: C) -> Framed<T, C> {
+        Framed {
+            io,
+            codec,
+            read_buf: BytesMut::new(),
+            write_buf: BytesMut::new(),
+            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
+            is_readable: false,
+            eof: false,
+        }
+    }
+
+    /// Returns the maximum number of bytes buffered while decoding a frame.
+    ///
+    /// For more information, see [`set_max_buffer_size`].
+    ///
+    /// [`set_max_buffer_size`]: #method.set_max_buffer_size
+    pub fn max_buffer_size(&self) -> usize {
+        self.max_buffer_size
+    }
+
+    /// Sets the maximum number of bytes buffered while decoding a frame.
+    ///
+    /// If the codec can't decode a frame from a read buffer holding this many
+    /// bytes, the stream yields an error of kind `InvalidData`.
+    pub fn set_max_buffer_size(&mut self, size: usize) {
+        self.max_buffer_size = size;
+    }
+
+    /// Returns a shared reference to the underlying I/O object.
+    pub fn get_ref(&self) -> &T {
+        &self.io
+    }
+
+    /// Returns a mutable reference to the underlying I/O object.
+    ///
+    /// Care should be taken not to read or write through this reference, as
+    /// that would corrupt the stream of frames.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.io
+    }
+
+    /// Returns a shared reference to the codec.
+    pub fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    /// Returns a mutable reference to the codec.
+    pub fn codec_mut(&mut self) -> &mut C {
+        &mut self.codec
+    }
+
+    /// Consumes the `Framed`, returning the underlying I/O object.
+    ///
+    /// Any data remaining in the read and write buffers is lost.
+    pub fn into_inner(self) -> T {
+        self.io
+    }
+}
+
+impl<T: AsyncRead, C> Framed<T, C> {
+    fn poll_fill_read_buf(&mut self, lw: &LocalWaker) -> Poll<io::Result<usize>> {
+        if self.read_buf.len() >= self.max_buffer_size {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "frame exceeds the maximum buffer size",
+            )));
+        }
+
+        let len = self.read_buf.len();
+        let chunk = READ_CHUNK.min(self.max_buffer_size - len);
+        self.read_buf.reserve(chunk);
+
+        unsafe {
+            self.read_buf.set_len(len + chunk);
+            self.io.initializer().initialize(&mut self.read_buf[len..]);
+
+            let res = self.io.poll_read(lw, &mut self.read_buf[len..]);
+
+            let n = match res {
+                Poll::Ready(Ok(n)) => n,
+                _ => 0,
+            };
+            self.read_buf.set_len(len + n);
+
+            res
+        }
+    }
+}
+
+impl<T: AsyncWrite, C> Framed<T, C> {
+    fn poll_flush_write_buf(&mut self, lw: &LocalWaker) -> Poll<io::Result<()>> {
+        while !self.write_buf.is_empty() {
+            let n = ready!(self.io.poll_write(lw, &self.write_buf))?;
+
+            if n == 0 {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write frame to transport",
+                )));
+            }
+
+            self.write_buf.split_to(n);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+// `Framed` never pins its fields, so it can be moved freely.
+impl<T, C> Unpin for Framed<T, C> {}
+
+impl<T, C> Stream for Framed<T, C>
+where
+    T: AsyncRead,
+    C: Decoder,
+{
+    type Item = io::Result<C::Item>;
+
+    fn poll_next(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            // Try to decode a frame from the buffered data before reading
+            // more, as a single read can contain several frames.
+            if this.is_readable {
+                if this.eof {
+                    return match this.codec.decode_eof(&mut this.read_buf) {
+                        Ok(Some(frame)) => Poll::Ready(Some(Ok(frame))),
+                        Ok(None) => Poll::Ready(None),
+                        Err(e) => Poll::Ready(Some(Err(e))),
+                    };
+                }
+
+                match this.codec.decode(&mut this.read_buf) {
+                    Ok(Some(frame)) => return Poll::Ready(Some(Ok(frame))),
+                    Ok(None) => this.is_readable = false,
+                    Err(e) => return Poll::Ready(Some(Err(e))),
+                }
+            }
+
+            match ready!(this.poll_fill_read_buf(lw)) {
+                Ok(0) => this.eof = true,
+                Ok(_) => {}
+                Err(e) => return Poll::Ready(Some(Err(e))),
+            }
+
+            this.is_readable = true;
+        }
+    }
+}
+
+impl<T, C> Sink for Framed<T, C>
+where
+    T: AsyncWrite,
+    C: Encoder,
+{
+    type SinkItem = C::Item;
+    type SinkError = io::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<()>> {
+        // Only apply backpressure once a sizable amount of data is buffered,
+        // so that several small frames can be coalesced into a single write.
+        if self.write_buf.len() >= BACKPRESSURE_BOUNDARY {
+            ready!(self.poll_flush_write_buf(lw))?;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: C::Item) -> io::Result<()> {
+        let this = &mut *self;
+        this.codec.encode(item, &mut this.write_buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<()>> {
+        ready!(self.poll_flush_write_buf(lw))?;
+        self.io.poll_flush(lw)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<()>> {
+        ready!(self.poll_flush_write_buf(lw))?;
+        self.io.poll_close(lw)
+    }
+}
+
+impl<T: fmt::Debug, C: fmt::Debug> fmt::Debug for Framed<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Framed")
+            .field("io", &self.io)
+            .field("codec", &self.codec)
+            .finish()
+    }
+}
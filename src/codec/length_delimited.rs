@@ -0,0 +1,95 @@
+use std::io;
+
+use bytes::{Bytes, BytesMut};
+
+use super::{Decoder, Encoder};
+
+/// The number of bytes in the length prefix.
+const HEADER_LEN: usize = 4;
+
+/// A codec for frames prefixed with their length.
+///
+/// Each frame is preceded by its length in bytes, encoded as a 32-bit
+/// big-endian integer that doesn't include the length of the prefix itself.
+///
+/// Frames longer than the maximum frame length (8 MiB by default) are
+/// rejected with an error of kind `InvalidData`, both when decoding and when
+/// encoding.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LengthDelimitedCodec {
+    max_frame_length: usize,
+}
+
+impl LengthDelimitedCodec {
+    /// Creates a new `LengthDelimitedCodec` with the default maximum frame
+    /// length.
+    pub fn new() -> LengthDelimitedCodec {
+        LengthDelimitedCodec {
+            max_frame_length: 8 * 1024 * 1024,
+        }
+    }
+
+    /// Returns the maximum frame length.
+    pub fn max_frame_length(&self) -> usize {
+        self.max_frame_length
+    }
+
+    /// Sets the maximum frame length.
+    pub fn set_max_frame_length(&mut self, max_frame_length: usize) {
+        self.max_frame_length = max_frame_length;
+    }
+
+    fn check_length(&self, len: usize) -> io::Result<()> {
+        if len > self.max_frame_length {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "frame exceeds the maximum frame length",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for LengthDelimitedCodec {
+    fn default() -> LengthDelimitedCodec {
+        LengthDelimitedCodec::new()
+    }
+}
+
+impl Decoder for LengthDelimitedCodec {
+    type Item = BytesMut;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> io::Result<Option<BytesMut>> {
+        if buf.len() < HEADER_LEN {
+            return Ok(None);
+        }
+
+        let mut header = [0; HEADER_LEN];
+        header.copy_from_slice(&buf[..HEADER_LEN]);
+        let len = u32::from_be_bytes(header) as usize;
+        self.check_length(len)?;
+
+        if buf.len() < HEADER_LEN + len {
+            // Make room for the rest of the frame up front.
+            buf.reserve(HEADER_LEN + len - buf.len());
+            return Ok(None);
+        }
+
+        buf.split_to(HEADER_LEN);
+        Ok(Some(buf.split_to(len)))
+    }
+}
+
+impl Encoder for LengthDelimitedCodec {
+    type Item = Bytes;
+
+    fn encode(&mut self, frame: Bytes, buf: &mut BytesMut) -> io::Result<()> {
+        self.check_length(frame.len())?;
+
+        buf.reserve(HEADER_LEN + frame.len());
+        buf.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+        buf.extend_from_slice(&frame);
+        Ok(())
+    }
+}
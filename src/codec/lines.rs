@@ -0,0 +1,123 @@
+use std::io;
+use std::str;
+
+use bytes::BytesMut;
+
+use super::{Decoder, Encoder};
+
+/// A codec for frames delimited by a newline.
+///
+/// Decoded lines have their trailing `\n` (or `\r\n`) removed; encoded lines
+/// have a `\n` appended. Lines must be valid UTF-8.
+///
+/// By default there is no limit on the length of a line. A peer can then make
+/// the read buffer grow until the [`Framed`] buffer cap is reached; use
+/// [`with_max_length`] to reject long lines earlier.
+///
+/// [`Framed`]: struct.Framed.html
+/// [`with_max_length`]: #method.with_max_length
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct LinesCodec {
+    /// The index in the buffer up to which it has already been searched for
+    /// a newline, so that successive calls to `decode` don't rescan it.
+    next_index: usize,
+
+    /// The maximum length of a line, excluding the delimiter.
+    max_length: Option<usize>,
+}
+
+impl LinesCodec {
+    /// Creates a new `LinesCodec` without a maximum line length.
+    pub fn new() -> LinesCodec {
+        LinesCodec::default()
+    }
+
+    /// Creates a new `LinesCodec` which rejects lines longer than
+    /// `max_length` bytes, not counting the delimiter.
+    ///
+    /// Decoding a longer line returns an error of kind `InvalidData`.
+    pub fn with_max_length(max_length: usize) -> LinesCodec {
+        LinesCodec {
+            next_index: 0,
+            max_length: Some(max_length),
+        }
+    }
+
+    /// Returns the maximum line length, if any.
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    fn check_length(&self, len: usize) -> io::Result<()> {
+        match self.max_length {
+            Some(max) if len > max => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "line exceeds the maximum length",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Decoder for LinesCodec {
+    type Item = String;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> io::Result<Option<String>> {
+        let newline = buf[self.next_index..].iter().position(|b| *b == b'\n');
+
+        match newline {
+            Some(offset) => {
+                let index = self.next_index + offset;
+                self.next_index = 0;
+
+                let line = buf.split_to(index + 1);
+                let line = without_delimiter(&line[..line.len() - 1]);
+                self.check_length(line.len())?;
+
+                Ok(Some(utf8(line)?.to_string()))
+            }
+            None => {
+                self.next_index = buf.len();
+                self.check_length(buf.len())?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> io::Result<Option<String>> {
+        match self.decode(buf)? {
+            Some(line) => Ok(Some(line)),
+            None if buf.is_empty() => Ok(None),
+            None => {
+                // The last line doesn't need to be terminated by a newline.
+                self.next_index = 0;
+                let line = buf.take();
+                Ok(Some(utf8(without_delimiter(&line))?.to_string()))
+            }
+        }
+    }
+}
+
+impl Encoder for LinesCodec {
+    type Item = String;
+
+    fn encode(&mut self, line: String, buf: &mut BytesMut) -> io::Result<()> {
+        buf.reserve(line.len() + 1);
+        buf.extend_from_slice(line.as_bytes());
+        buf.extend_from_slice(b"\n");
+        Ok(())
+    }
+}
+
+fn without_delimiter(line: &[u8]) -> &[u8] {
+    if let Some(&b'\r') = line.last() {
+        &line[..line.len() - 1]
+    } else {
+        line
+    }
+}
+
+fn utf8(line: &[u8]) -> io::Result<&str> {
+    str::from_utf8(line)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "line is not valid UTF-8"))
+}
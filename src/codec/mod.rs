@@ -0,0 +1,99 @@
+//! Utilities for encoding and decoding frames.
+//!
+//! Network protocols usually exchange discrete messages ("frames") rather than
+//! raw bytes. This module contains the [`Encoder`] and [`Decoder`] traits,
+//! which describe how frames are converted to and from bytes, and the
+//! [`Framed`] adapter, which uses them to turn any `AsyncRead + AsyncWrite`
+//! type (such as a [`TcpStream`]) into a `Stream` and `Sink` of frames.
+//!
+//! Two codecs are provided out of the box: [`LinesCodec`] for newline
+//! delimited text and [`LengthDelimitedCodec`] for frames prefixed with their
+//! length.
+//!
+//! [`Encoder`]: trait.Encoder.html
+//! [`Decoder`]: trait.Decoder.html
+//! [`Framed`]: struct.Framed.html
+//! [`TcpStream`]: ../tcp/struct.TcpStream.html
+//! [`LinesCodec`]: struct.LinesCodec.html
+//! [`LengthDelimitedCodec`]: struct.LengthDelimitedCodec.html
+//!
+//! # Example
+//!
+//! ```no_run
+//! #![feature(async_await, await_macro, futures_api)]
+//! use romio::codec::{Framed, LinesCodec};
+//! use romio::tcp::TcpStream;
+//! use futures::prelude::*;
+//!
+//! async fn echo_lines(stream: TcpStream) -> std::io::Result<()> {
+//!     let mut lines = Framed::new(stream, LinesCodec::new());
+//!
+//!     while let Some(line) = await!(lines.next()) {
+//!         await!(lines.send(line?))?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+mod framed;
+mod length_delimited;
+mod lines;
+
+pub use self::framed::Framed;
+pub use self::length_delimited::LengthDelimitedCodec;
+pub use self::lines::LinesCodec;
+
+use std::io;
+
+use bytes::BytesMut;
+
+/// Decoding of frames from a buffer of bytes.
+///
+/// Used by [`Framed`] to turn the bytes read from an I/O object into a stream
+/// of frames.
+///
+/// [`Framed`]: struct.Framed.html
+pub trait Decoder {
+    /// The type of decoded frames.
+    type Item;
+
+    /// Attempts to decode a frame from the provided buffer of bytes.
+    ///
+    /// If the buffer contains a complete frame, the bytes making up the frame
+    /// should be removed from the buffer and the frame returned as
+    /// `Ok(Some(frame))`. If more bytes are required to decode a frame,
+    /// `Ok(None)` should be returned and the buffer left as is; the caller
+    /// will read more data and call `decode` again.
+    ///
+    /// Returning an error indicates that the bytes are not a valid frame.
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Self::Item>>;
+
+    /// Attempts to decode a frame after the underlying I/O object has reached
+    /// EOF.
+    ///
+    /// The default implementation calls `decode` and returns an error if
+    /// bytes remain in the buffer that don't make up a complete frame.
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> io::Result<Option<Self::Item>> {
+        match self.decode(buf)? {
+            Some(frame) => Ok(Some(frame)),
+            None if buf.is_empty() => Ok(None),
+            None => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "bytes remaining on stream",
+            )),
+        }
+    }
+}
+
+/// Encoding of frames into a buffer of bytes.
+///
+/// Used by [`Framed`] to turn frames into bytes written to an I/O object.
+///
+/// [`Framed`]: struct.Framed.html
+pub trait Encoder {
+    /// The type of frames to encode.
+    type Item;
+
+    /// Encodes a frame into the provided buffer of bytes.
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> io::Result<()>;
+}
@@ -0,0 +1,143 @@
+//! Binding sockets to a network interface, with `SO_BINDTODEVICE` on Linux and
+//! `IP_BOUND_IF` on macOS.
+
+use std::ffi::CString;
+use std::io;
+#[cfg(target_os = "macos")]
+use std::mem;
+use std::os::unix::io::AsRawFd;
+
+#[cfg(target_os = "macos")]
+use crate::sockopt;
+
+/// Returns the name of the interface `socket` is bound to, if any.
+#[cfg(target_os = "linux")]
+pub(crate) fn device(socket: &impl AsRawFd) -> io::Result<Option<String>> {
+    let mut buf = [0 as libc::c_char; libc::IFNAMSIZ];
+    let mut len = buf.len() as libc::socklen_t;
+    let r = unsafe {
+        libc::getsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_BINDTODEVICE,
+            buf.as_mut_ptr() as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if r < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if len == 0 || buf[0] == 0 {
+        return Ok(None);
+    }
+    Ok(Some(name(&buf)))
+}
+
+/// Binds `socket` to the interface named `device`, or unbinds it if `None`.
+#[cfg(target_os = "linux")]
+pub(crate) fn set_device(socket: &impl AsRawFd, device: Option<&str>) -> io::Result<()> {
+    let device = match device {
+        Some(device) => Some(to_cstring(device)?),
+        None => None,
+    };
+    let (ptr, len) = match &device {
+        Some(device) => (device.as_ptr(), device.as_bytes().len()),
+        None => (std::ptr::null(), 0),
+    };
+    let r = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_BINDTODEVICE,
+            ptr as *const libc::c_void,
+            len as libc::socklen_t,
+        )
+    };
+    if r < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Returns the name of the interface `socket` is bound to, if any.
+#[cfg(target_os = "macos")]
+pub(crate) fn device(socket: &impl AsRawFd) -> io::Result<Option<String>> {
+    let (level, name) = bound_if(socket)?;
+    let index = sockopt::getsockopt(socket, level, name)?;
+    if index == 0 {
+        return Ok(None);
+    }
+
+    let mut buf = [0 as libc::c_char; libc::IFNAMSIZ];
+    if unsafe { libc::if_indextoname(index as libc::c_uint, buf.as_mut_ptr()) }.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(Some(self::name(&buf)))
+}
+
+/// Binds `socket` to the interface named `device`, or unbinds it if `None`.
+#[cfg(target_os = "macos")]
+pub(crate) fn set_device(socket: &impl AsRawFd, device: Option<&str>) -> io::Result<()> {
+    let index = match device {
+        Some(device) => {
+            let device = to_cstring(device)?;
+            match unsafe { libc::if_nametoindex(device.as_ptr()) } {
+                0 => return Err(io::Error::last_os_error()),
+                index => index as libc::c_int,
+            }
+        }
+        None => 0,
+    };
+    let (level, name) = bound_if(socket)?;
+    sockopt::setsockopt(socket, level, name, index)
+}
+
+/// Returns the level and name of the `IP_BOUND_IF` option matching the
+/// address family of `socket`.
+#[cfg(target_os = "macos")]
+fn bound_if(socket: &impl AsRawFd) -> io::Result<(libc::c_int, libc::c_int)> {
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    let r = unsafe {
+        libc::getsockname(
+            socket.as_raw_fd(),
+            &mut storage as *mut _ as *mut libc::sockaddr,
+            &mut len,
+        )
+    };
+    if r < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if libc::c_int::from(storage.ss_family) == libc::AF_INET6 {
+        Ok((libc::IPPROTO_IPV6, libc::IPV6_BOUND_IF))
+    } else {
+        Ok((libc::IPPROTO_IP, libc::IP_BOUND_IF))
+    }
+}
+
+/// Checks that `device` is a valid interface name, rather than letting the
+/// kernel truncate it.
+fn to_cstring(device: &str) -> io::Result<CString> {
+    if device.len() >= libc::IFNAMSIZ {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "interface name is too long",
+        ));
+    }
+    CString::new(device).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "interface name contains a nul byte",
+        )
+    })
+}
+
+/// Reads the nul terminated interface name out of `buf`.
+fn name(buf: &[libc::c_char]) -> String {
+    let name: Vec<u8> = buf
+        .iter()
+        .take_while(|&&c| c != 0)
+        .map(|&c| c as u8)
+        .collect();
+    String::from_utf8_lossy(&name).into_owned()
+}
@@ -0,0 +1,194 @@
+use std::fmt;
+use std::io;
+
+use futures::io::{AsyncRead, AsyncWrite, Initializer};
+use futures::task::LocalWaker;
+use futures::{ready, Poll};
+use iovec::IoVec;
+
+/// The size of the buffer used by `BufWriter::new`.
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// A writer which collects small writes in a buffer, writing them to the
+/// wrapped writer in larger batches.
+///
+/// Every write to a plain `TcpStream` is a system call, and likely a packet of
+/// its own, which dominates the cost of sending many small messages. A
+/// `BufWriter` only writes to the wrapped writer once its buffer is full, or
+/// when it is flushed or closed. Writes at least as large as the buffer bypass
+/// it once it has been written out.
+///
+/// Flushing and closing first write out everything in the buffer, however
+/// many writes that takes, and only then flush or close the wrapped writer. If
+/// writing out the buffer fails, the data which wasn't written yet stays in
+/// the buffer, and is written by the next write, flush or close.
+///
+/// Data still in the buffer when the `BufWriter` is dropped is lost, so make
+/// sure to flush or close it when done.
+///
+/// Reads go to the wrapped reader directly.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// #![feature(async_await, await_macro, futures_api)]
+/// use futures::prelude::*;
+/// use romio::io::BufWriter;
+/// use romio::TcpStream;
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let addr = "127.0.0.1:8080".parse().unwrap();
+/// let stream = await!(TcpStream::connect(&addr))?;
+/// let mut stream = BufWriter::new(stream);
+///
+/// for i in 0..100 {
+///     await!(stream.write_all(format!("PING {}\r\n", i).as_bytes()))?;
+/// }
+/// await!(stream.flush())?;
+/// # Ok(()) }
+/// ```
+pub struct BufWriter<W> {
+    inner: W,
+    buf: Vec<u8>,
+    /// The number of bytes at the start of `buf` which were written already.
+    written: usize,
+}
+
+impl<W> BufWriter<W> {
+    /// Wraps `inner`, with a buffer of 8 KiB.
+    pub fn new(inner: W) -> BufWriter<W> {
+        BufWriter::with_capacity(inner, DEFAULT_CAPACITY)
+    }
+
+    /// Wraps `inner`, with a buffer of `capacity` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_capacity(inner: W, capacity: usize) -> BufWriter<W> {
+        assert!(capacity > 0, "write buffer capacity must be non-zero");
+
+        BufWriter {
+            inner,
+            buf: Vec::with_capacity(capacity),
+            written: 0,
+        }
+    }
+
+    /// Returns a reference to the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped writer.
+    ///
+    /// Writing to the wrapped writer directly skips ahead of the data held in
+    /// the buffer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Consumes the `BufWriter`, returning the wrapped writer.
+    ///
+    /// The data still in the buffer is lost, flush the `BufWriter` first to
+    /// keep it.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Returns the data which hasn't been written to the wrapped writer yet.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.written..]
+    }
+
+    /// Returns the capacity of the internal buffer.
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+}
+
+impl<W: AsyncWrite> BufWriter<W> {
+    /// Writes out the buffer, retrying partial writes until it is empty.
+    fn poll_flush_buf(&mut self, lw: &LocalWaker) -> Poll<io::Result<()>> {
+        while self.written < self.buf.len() {
+            let n = ready!(self.inner.poll_write(lw, &self.buf[self.written..]))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write the buffered data",
+                )));
+            }
+            self.written += n;
+        }
+        self.buf.clear();
+        self.written = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite> AsyncWrite for BufWriter<W> {
+    fn poll_write(&mut self, lw: &LocalWaker, buf: &[u8]) -> Poll<io::Result<usize>> {
+        if self.buf.len() + buf.len() > self.buf.capacity() {
+            ready!(self.poll_flush_buf(lw))?;
+        }
+        // Copying into the buffer gains nothing if the data would fill it on
+        // its own.
+        if buf.len() >= self.buf.capacity() {
+            return self.inner.poll_write(lw, buf);
+        }
+        self.buf.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_vectored_write(&mut self, lw: &LocalWaker, vec: &[&IoVec]) -> Poll<io::Result<usize>> {
+        let len = vec.iter().map(|buf| buf.len()).sum::<usize>();
+        if self.buf.len() + len > self.buf.capacity() {
+            ready!(self.poll_flush_buf(lw))?;
+        }
+        if len >= self.buf.capacity() {
+            return self.inner.poll_vectored_write(lw, vec);
+        }
+        for buf in vec {
+            self.buf.extend_from_slice(buf);
+        }
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(&mut self, lw: &LocalWaker) -> Poll<io::Result<()>> {
+        ready!(self.poll_flush_buf(lw))?;
+        self.inner.poll_flush(lw)
+    }
+
+    fn poll_close(&mut self, lw: &LocalWaker) -> Poll<io::Result<()>> {
+        ready!(self.poll_flush_buf(lw))?;
+        self.inner.poll_close(lw)
+    }
+}
+
+impl<W: AsyncRead> AsyncRead for BufWriter<W> {
+    unsafe fn initializer(&self) -> Initializer {
+        self.inner.initializer()
+    }
+
+    fn poll_read(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        self.inner.poll_read(lw, buf)
+    }
+
+    fn poll_vectored_read(
+        &mut self,
+        lw: &LocalWaker,
+        vec: &mut [&mut IoVec],
+    ) -> Poll<io::Result<usize>> {
+        self.inner.poll_vectored_read(lw, vec)
+    }
+}
+
+impl<W: fmt::Debug> fmt::Debug for BufWriter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufWriter")
+            .field("inner", &self.inner)
+            .field("buffered", &(self.buf.len() - self.written))
+            .field("capacity", &self.buf.capacity())
+            .finish()
+    }
+}
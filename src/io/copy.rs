@@ -0,0 +1,212 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+
+use futures::io::{AsyncRead, AsyncWrite};
+use futures::task::LocalWaker;
+use futures::{ready, Future, Poll};
+
+/// The size of the buffer used by `copy`.
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// Copies all data from `reader` into `writer` until `reader` reaches end of
+/// stream, then flushes `writer`.
+///
+/// The returned future resolves to the number of bytes copied. If the copy
+/// fails partway, the error reports how many bytes were written to `writer`
+/// before the failure, so that a transfer can be resumed.
+///
+/// Data is only read once everything read before has been written, so a
+/// slow writer applies backpressure to the reader.
+///
+/// A writer which accepts no data at all, by returning `Ok(0)`, fails the
+/// copy with an `ErrorKind::WriteZero` error, rather than making it retry
+/// forever.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// #![feature(async_await, await_macro, futures_api)]
+/// use romio::TcpStream;
+///
+/// # async fn proxy(mut client: TcpStream) -> std::io::Result<()> {
+/// let addr = "127.0.0.1:8080".parse().unwrap();
+/// let mut server = await!(TcpStream::connect(&addr))?;
+///
+/// match await!(romio::io::copy(&mut client, &mut server)) {
+///     Ok(n) => println!("copied {} bytes", n),
+///     Err(e) => println!("copy failed after {} bytes: {}", e.transferred(), e),
+/// }
+/// # Ok(()) }
+/// ```
+pub fn copy<'a, R, W>(reader: &'a mut R, writer: &'a mut W) -> Copy<'a, R, W>
+where
+    R: AsyncRead + ?Sized,
+    W: AsyncWrite + ?Sized,
+{
+    copy_with_capacity(reader, writer, DEFAULT_CAPACITY)
+}
+
+/// Like `copy`, but with a buffer of `capacity` bytes.
+///
+/// A larger buffer means fewer system calls per byte copied, at the cost of
+/// memory held for the duration of the copy.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+pub fn copy_with_capacity<'a, R, W>(
+    reader: &'a mut R,
+    writer: &'a mut W,
+    capacity: usize,
+) -> Copy<'a, R, W>
+where
+    R: AsyncRead + ?Sized,
+    W: AsyncWrite + ?Sized,
+{
+    assert!(capacity > 0, "copy buffer capacity must be non-zero");
+
+    Copy {
+        reader,
+        writer,
+        read_done: false,
+        amt: 0,
+        pos: 0,
+        cap: 0,
+        buf: vec![0; capacity].into_boxed_slice(),
+    }
+}
+
+/// The future returned by `copy` and `copy_with_capacity`.
+#[must_use = "futures do nothing unless polled"]
+pub struct Copy<'a, R: ?Sized, W: ?Sized> {
+    reader: &'a mut R,
+    writer: &'a mut W,
+    read_done: bool,
+    amt: u64,
+    pos: usize,
+    cap: usize,
+    buf: Box<[u8]>,
+}
+
+/// The error returned by `copy` when the copy fails partway.
+#[derive(Debug)]
+pub struct CopyError {
+    transferred: u64,
+    source: io::Error,
+}
+
+impl<'a, R: ?Sized, W: ?Sized> Copy<'a, R, W>
+where
+    R: AsyncRead,
+    W: AsyncWrite,
+{
+    fn fail(&self, source: io::Error) -> Poll<Result<u64, CopyError>> {
+        Poll::Ready(Err(CopyError {
+            transferred: self.amt,
+            source,
+        }))
+    }
+}
+
+impl<'a, R: ?Sized, W: ?Sized> Future for Copy<'a, R, W>
+where
+    R: AsyncRead,
+    W: AsyncWrite,
+{
+    type Output = Result<u64, CopyError>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        loop {
+            // If our buffer is empty, then we need to read some data to
+            // continue.
+            if this.pos == this.cap && !this.read_done {
+                match ready!(this.reader.poll_read(lw, &mut this.buf)) {
+                    Ok(0) => this.read_done = true,
+                    Ok(n) => {
+                        this.pos = 0;
+                        this.cap = n;
+                    }
+                    Err(e) => return this.fail(e),
+                }
+            }
+
+            // If our buffer has some data, let's write it out!
+            while this.pos < this.cap {
+                match ready!(this.writer.poll_write(lw, &this.buf[this.pos..this.cap])) {
+                    Ok(0) => {
+                        return this.fail(io::Error::new(
+                            io::ErrorKind::WriteZero,
+                            "write zero byte into writer",
+                        ));
+                    }
+                    Ok(n) => {
+                        this.pos += n;
+                        this.amt += n as u64;
+                    }
+                    Err(e) => return this.fail(e),
+                }
+            }
+
+            // If we've written all the data and we've seen EOF, flush out the
+            // data and finish the transfer.
+            if this.pos == this.cap && this.read_done {
+                if let Err(e) = ready!(this.writer.poll_flush(lw)) {
+                    return this.fail(e);
+                }
+                return Poll::Ready(Ok(this.amt));
+            }
+        }
+    }
+}
+
+impl<'a, R: ?Sized, W: ?Sized> fmt::Debug for Copy<'a, R, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Copy")
+            .field("amt", &self.amt)
+            .field("read_done", &self.read_done)
+            .field("capacity", &self.buf.len())
+            .finish()
+    }
+}
+
+// ===== impl CopyError =====
+
+impl CopyError {
+    /// Returns the number of bytes written before the copy failed.
+    pub fn transferred(&self) -> u64 {
+        self.transferred
+    }
+
+    /// Returns the I/O error which made the copy fail.
+    pub fn get_ref(&self) -> &io::Error {
+        &self.source
+    }
+
+    /// Consumes the `CopyError`, returning the I/O error which made the copy
+    /// fail.
+    pub fn into_inner(self) -> io::Error {
+        self.source
+    }
+}
+
+impl fmt::Display for CopyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl Error for CopyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl From<CopyError> for io::Error {
+    fn from(e: CopyError) -> io::Error {
+        e.source
+    }
+}
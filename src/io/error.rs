@@ -0,0 +1,159 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+/// The error connecting to or binding to an address, along with the address.
+///
+/// An `io::Error` alone, like "Connection refused", doesn't tell which of a
+/// service's many addresses it is about. Connecting and binding in this crate
+/// fail with an `AddrError` instead, whose `Display` names the operation and
+/// the address, for example
+/// "connect to 10.0.0.5:443 failed: Connection refused (os error 111)".
+///
+/// The error reported by the OS is kept as it is, [`kind`] and
+/// [`raw_os_error`] return its kind and OS error code. An `AddrError`
+/// converts into an `io::Error` of the same kind, so `?` works in functions
+/// returning an `io::Result`, and [`from_io`] gets the `AddrError` back out
+/// of it. Errors accepting connections are passed on as they are, the
+/// listener's address is known to the caller anyway.
+///
+/// [`kind`]: #method.kind
+/// [`raw_os_error`]: #method.raw_os_error
+/// [`from_io`]: #method.from_io
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// #![feature(async_await, await_macro, futures_api)]
+/// use romio::TcpStream;
+///
+/// # async fn run() {
+/// let addr = "10.0.0.5:443".parse().unwrap();
+/// if let Err(e) = await!(TcpStream::connect(&addr)) {
+///     println!("{}", e);
+///
+///     assert_eq!(e.socket_addr(), Some(addr));
+///     println!("os error {:?}", e.raw_os_error());
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AddrError {
+    op: Operation,
+    addr: Addr,
+    source: io::Error,
+}
+
+/// The operation an [`AddrError`] failed at.
+///
+/// [`AddrError`]: struct.AddrError.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// Connecting to a remote address.
+    Connect,
+    /// Binding to a local address.
+    Bind,
+}
+
+#[derive(Debug)]
+enum Addr {
+    Inet(SocketAddr),
+    Unix(PathBuf),
+}
+
+// ===== impl AddrError =====
+
+impl AddrError {
+    /// Creates the error of `op` on `addr`.
+    pub(crate) fn new(op: Operation, addr: &SocketAddr, source: io::Error) -> AddrError {
+        AddrError {
+            op,
+            addr: Addr::Inet(*addr),
+            source,
+        }
+    }
+
+    /// Like `new`, for Unix sockets.
+    pub(crate) fn with_path(op: Operation, path: &Path, source: io::Error) -> AddrError {
+        AddrError {
+            op,
+            addr: Addr::Unix(path.to_path_buf()),
+            source,
+        }
+    }
+
+    /// Returns the `AddrError` carried by `e`, if it was converted from one.
+    pub fn from_io(e: &io::Error) -> Option<&AddrError> {
+        e.get_ref()?.downcast_ref()
+    }
+
+    /// Returns the operation which failed.
+    pub fn op(&self) -> Operation {
+        self.op
+    }
+
+    /// Returns the address the operation failed on, unless it was a Unix
+    /// socket path.
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        match self.addr {
+            Addr::Inet(addr) => Some(addr),
+            Addr::Unix(_) => None,
+        }
+    }
+
+    /// Returns the Unix socket path the operation failed on, if it was one.
+    pub fn path(&self) -> Option<&Path> {
+        match &self.addr {
+            Addr::Inet(_) => None,
+            Addr::Unix(path) => Some(path),
+        }
+    }
+
+    /// Returns the kind of the original error.
+    pub fn kind(&self) -> io::ErrorKind {
+        self.source.kind()
+    }
+
+    /// Returns the OS error code of the original error, if it has one.
+    pub fn raw_os_error(&self) -> Option<i32> {
+        self.source.raw_os_error()
+    }
+
+    /// Returns the original error.
+    pub fn get_ref(&self) -> &io::Error {
+        &self.source
+    }
+
+    /// Consumes the `AddrError`, returning the original error.
+    pub fn into_inner(self) -> io::Error {
+        self.source
+    }
+}
+
+impl fmt::Display for AddrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.op {
+            Operation::Connect => f.write_str("connect to ")?,
+            Operation::Bind => f.write_str("bind to ")?,
+        }
+        match &self.addr {
+            Addr::Inet(addr) => write!(f, "{}", addr)?,
+            Addr::Unix(path) => write!(f, "{}", path.display())?,
+        }
+        write!(f, " failed: {}", self.source)
+    }
+}
+
+impl From<AddrError> for io::Error {
+    fn from(e: AddrError) -> io::Error {
+        io::Error::new(e.kind(), e)
+    }
+}
+
+impl Error for AddrError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
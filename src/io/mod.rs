@@ -0,0 +1,79 @@
+//! Async I/O utilities.
+//!
+//! This module contains helpers which complement the extension traits in
+//! `futures::io` for working with the streams in this crate.
+
+mod buf_writer;
+mod copy;
+mod error;
+mod ready;
+mod timeout;
+
+pub use self::buf_writer::BufWriter;
+pub use self::copy::{copy, copy_with_capacity, Copy, CopyError};
+pub use self::error::{AddrError, Operation};
+pub use self::ready::{Interest, Readiness};
+pub use self::timeout::TimeoutStream;
+pub use crate::reactor::ReactorMetrics;
+
+/// Returns the number of I/O operations a socket performs in a row before
+/// yielding, or `None` if it never yields.
+///
+/// See [`set_poll_budget`] for details.
+///
+/// [`set_poll_budget`]: fn.set_poll_budget.html
+pub fn poll_budget() -> Option<usize> {
+    crate::reactor::budget()
+}
+
+/// Returns a snapshot of the counters of the reactor driving the sockets
+/// created on the current thread.
+///
+/// Inside of `romio::run` that is the reactor of `run`, elsewhere it is the
+/// global reactor. The counters are cheap to maintain, so they are always
+/// collected. Fails if the reactor can't be started or has shut down.
+///
+/// # Examples
+///
+/// ```rust
+/// let metrics = romio::io::reactor_metrics()?;
+/// println!(
+///     "{} of {} wakeups were spurious",
+///     metrics.spurious_wakeups, metrics.wakeups
+/// );
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn reactor_metrics() -> std::io::Result<ReactorMetrics> {
+    crate::reactor::metrics()
+}
+
+/// Sets the number of I/O operations a socket performs in a row before
+/// yielding to other tasks.
+///
+/// A socket which always has data available, or a listener with an endless
+/// supply of connections, would let a task loop over it within a single poll
+/// forever, starving the other tasks on the same executor thread. Instead,
+/// once a socket was ready for `budget` reads (or writes, or accepts) in a
+/// row, the next attempt returns `Poll::Pending` after waking the task, which
+/// then runs again after the others had their turn. The count starts over
+/// whenever an operation would block, and whenever the reactor wakes the task
+/// up for the socket. Accepting a batch of connections counts as a single
+/// accept.
+///
+/// The budget applies to all sockets of this crate and defaults to 32. `None`
+/// disables it.
+///
+/// # Panics
+///
+/// Panics if the budget is `Some(0)`.
+///
+/// # Examples
+///
+/// ```rust
+/// romio::io::set_poll_budget(Some(128));
+/// assert_eq!(romio::io::poll_budget(), Some(128));
+/// ```
+pub fn set_poll_budget(budget: Option<usize>) {
+    assert!(budget != Some(0), "poll budget must be non-zero");
+    crate::reactor::set_budget(budget);
+}
@@ -0,0 +1,148 @@
+use std::ops;
+
+use crate::reactor::platform;
+
+/// The readiness events to wait for, passed to `TcpStream::ready`.
+///
+/// Interests can be combined with `|`, like `Interest::READABLE |
+/// Interest::WRITABLE`. Closed readiness doesn't need to be asked for, it is
+/// always reported along with the requested events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interest(u8);
+
+const READABLE: u8 = 0b0001;
+const WRITABLE: u8 = 0b0010;
+const READ_CLOSED: u8 = 0b0100;
+const WRITE_CLOSED: u8 = 0b1000;
+
+impl Interest {
+    /// Interest in readable readiness.
+    pub const READABLE: Interest = Interest(READABLE);
+
+    /// Interest in writable readiness.
+    pub const WRITABLE: Interest = Interest(WRITABLE);
+
+    /// Returns whether the interest includes readable readiness.
+    pub fn is_readable(self) -> bool {
+        self.0 & READABLE != 0
+    }
+
+    /// Returns whether the interest includes writable readiness.
+    pub fn is_writable(self) -> bool {
+        self.0 & WRITABLE != 0
+    }
+
+    pub(crate) fn to_mio(self) -> mio::Ready {
+        let mut ready = mio::Ready::empty();
+        if self.is_readable() {
+            ready |= mio::Ready::readable();
+        }
+        if self.is_writable() {
+            ready |= mio::Ready::writable();
+        }
+        ready
+    }
+}
+
+impl ops::BitOr for Interest {
+    type Output = Interest;
+
+    fn bitor(self, other: Interest) -> Interest {
+        Interest(self.0 | other.0)
+    }
+}
+
+impl From<mio::Ready> for Interest {
+    /// Converts the readable and writable bits of `ready`, ignoring the
+    /// others.
+    fn from(ready: mio::Ready) -> Interest {
+        let mut interest = Interest(0);
+        if ready.is_readable() {
+            interest = interest | Interest::READABLE;
+        }
+        if ready.is_writable() {
+            interest = interest | Interest::WRITABLE;
+        }
+        interest
+    }
+}
+
+/// The readiness of a socket, as returned by `TcpStream::ready`.
+///
+/// Readiness is only a hint: an operation may still fail with `WouldBlock`,
+/// and should be retried once the socket becomes ready again.
+///
+/// Closed readiness is derived from the HUP and error events of the
+/// platform. A HUP means that both directions of the connection are closed,
+/// and an error that writing fails, so on their own reads report a closed
+/// read direction by returning `Ok(0)`, rather than through readiness. On
+/// Windows, closed readiness is never reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Readiness(u8);
+
+impl Readiness {
+    /// No readiness.
+    pub const EMPTY: Readiness = Readiness(0);
+
+    /// Readable readiness.
+    pub const READABLE: Readiness = Readiness(READABLE);
+
+    /// Writable readiness.
+    pub const WRITABLE: Readiness = Readiness(WRITABLE);
+
+    /// The read direction is closed.
+    pub const READ_CLOSED: Readiness = Readiness(READ_CLOSED);
+
+    /// The write direction is closed.
+    pub const WRITE_CLOSED: Readiness = Readiness(WRITE_CLOSED);
+
+    /// Returns whether no readiness is set.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns whether the readiness includes readable readiness.
+    pub fn is_readable(self) -> bool {
+        self.0 & READABLE != 0
+    }
+
+    /// Returns whether the readiness includes writable readiness.
+    pub fn is_writable(self) -> bool {
+        self.0 & WRITABLE != 0
+    }
+
+    /// Returns whether the read direction is closed.
+    pub fn is_read_closed(self) -> bool {
+        self.0 & READ_CLOSED != 0
+    }
+
+    /// Returns whether the write direction is closed.
+    pub fn is_write_closed(self) -> bool {
+        self.0 & WRITE_CLOSED != 0
+    }
+
+    pub(crate) fn from_mio(ready: mio::Ready) -> Readiness {
+        let mut readiness = Readiness::EMPTY;
+        if ready.is_readable() {
+            readiness = readiness | Readiness::READABLE;
+        }
+        if ready.is_writable() {
+            readiness = readiness | Readiness::WRITABLE;
+        }
+        if platform::is_hup(&ready) {
+            readiness = readiness | Readiness::READ_CLOSED | Readiness::WRITE_CLOSED;
+        }
+        if platform::is_error(&ready) {
+            readiness = readiness | Readiness::WRITE_CLOSED;
+        }
+        readiness
+    }
+}
+
+impl ops::BitOr for Readiness {
+    type Output = Readiness;
+
+    fn bitor(self, other: Readiness) -> Readiness {
+        Readiness(self.0 | other.0)
+    }
+}
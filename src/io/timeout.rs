@@ -0,0 +1,184 @@
+use std::io;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+use futures::io::{AsyncRead, AsyncWrite, Initializer};
+use futures::task::LocalWaker;
+use futures::{Future, Poll};
+use iovec::IoVec;
+
+use crate::reactor::Delay;
+
+/// A wrapper around a stream which fails reads and writes with an
+/// `ErrorKind::TimedOut` error once they made no progress for too long.
+///
+/// The read and write timeouts are independent, and are both disabled by
+/// default. The time starts running when a read (or a write) has to wait, and
+/// starts over whenever one makes progress, so a slow peer which keeps sending
+/// data never times out, while one which stalls does. Flushing and closing
+/// count as writing.
+///
+/// A timed out operation can be retried, which waits for the full timeout
+/// again.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// #![feature(async_await, await_macro, futures_api)]
+/// use futures::prelude::*;
+/// use romio::io::TimeoutStream;
+/// use romio::TcpStream;
+/// use std::time::Duration;
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let addr = "127.0.0.1:8080".parse().unwrap();
+/// let stream = await!(TcpStream::connect(&addr))?;
+///
+/// let mut stream = TimeoutStream::new(stream);
+/// stream.set_read_timeout(Some(Duration::from_secs(30)));
+///
+/// let mut buf = vec![0; 1024];
+/// let n = await!(stream.read(&mut buf))?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug)]
+pub struct TimeoutStream<S> {
+    stream: S,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    /// Running while a read waits.
+    read_delay: Option<Delay>,
+    /// Running while a write, flush or close waits.
+    write_delay: Option<Delay>,
+}
+
+impl<S> TimeoutStream<S> {
+    /// Wraps `stream`, without any timeouts.
+    pub fn new(stream: S) -> TimeoutStream<S> {
+        TimeoutStream {
+            stream,
+            read_timeout: None,
+            write_timeout: None,
+            read_delay: None,
+            write_delay: None,
+        }
+    }
+
+    /// Returns the read timeout, or `None` if reads never time out.
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout
+    }
+
+    /// Sets how long a read may wait without receiving any data before it
+    /// fails, or `None` to wait forever.
+    ///
+    /// A read which is waiting already starts over with the new timeout.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+        self.read_delay = None;
+    }
+
+    /// Returns the write timeout, or `None` if writes never time out.
+    pub fn write_timeout(&self) -> Option<Duration> {
+        self.write_timeout
+    }
+
+    /// Sets how long a write may wait without sending any data before it
+    /// fails, or `None` to wait forever.
+    ///
+    /// A write which is waiting already starts over with the new timeout.
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) {
+        self.write_timeout = timeout;
+        self.write_delay = None;
+    }
+
+    /// Returns a reference to the wrapped stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Returns a mutable reference to the wrapped stream.
+    ///
+    /// Reading or writing through it bypasses the timeouts.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Consumes the `TimeoutStream`, returning the wrapped stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+/// Passes on the result of an operation, or fails it once it waited for
+/// `timeout` without making progress.
+fn poll_timeout<T>(
+    res: Poll<io::Result<T>>,
+    timeout: Option<Duration>,
+    delay: &mut Option<Delay>,
+    lw: &LocalWaker,
+) -> Poll<io::Result<T>> {
+    let timeout = match (res, timeout) {
+        (Poll::Pending, Some(timeout)) => timeout,
+        (res, _) => {
+            *delay = None;
+            return res;
+        }
+    };
+
+    let timer = delay.get_or_insert_with(|| Delay::new(Instant::now() + timeout));
+    let ready = Pin::new(timer).poll(lw);
+    match ready {
+        Poll::Ready(res) => {
+            *delay = None;
+            res?;
+            Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "operation timed out",
+            )))
+        }
+        Poll::Pending => Poll::Pending,
+    }
+}
+
+impl<S: AsyncRead> AsyncRead for TimeoutStream<S> {
+    unsafe fn initializer(&self) -> Initializer {
+        self.stream.initializer()
+    }
+
+    fn poll_read(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let res = self.stream.poll_read(lw, buf);
+        poll_timeout(res, self.read_timeout, &mut self.read_delay, lw)
+    }
+
+    fn poll_vectored_read(
+        &mut self,
+        lw: &LocalWaker,
+        vec: &mut [&mut IoVec],
+    ) -> Poll<io::Result<usize>> {
+        let res = self.stream.poll_vectored_read(lw, vec);
+        poll_timeout(res, self.read_timeout, &mut self.read_delay, lw)
+    }
+}
+
+impl<S: AsyncWrite> AsyncWrite for TimeoutStream<S> {
+    fn poll_write(&mut self, lw: &LocalWaker, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let res = self.stream.poll_write(lw, buf);
+        poll_timeout(res, self.write_timeout, &mut self.write_delay, lw)
+    }
+
+    fn poll_vectored_write(&mut self, lw: &LocalWaker, vec: &[&IoVec]) -> Poll<io::Result<usize>> {
+        let res = self.stream.poll_vectored_write(lw, vec);
+        poll_timeout(res, self.write_timeout, &mut self.write_delay, lw)
+    }
+
+    fn poll_flush(&mut self, lw: &LocalWaker) -> Poll<io::Result<()>> {
+        let res = self.stream.poll_flush(lw);
+        poll_timeout(res, self.write_timeout, &mut self.write_delay, lw)
+    }
+
+    fn poll_close(&mut self, lw: &LocalWaker) -> Poll<io::Result<()>> {
+        let res = self.stream.poll_close(lw);
+        poll_timeout(res, self.write_timeout, &mut self.write_delay, lw)
+    }
+}
@@ -59,15 +59,34 @@
 #![deny(missing_docs, missing_debug_implementations)]
 #![cfg_attr(test, deny(warnings))]
 
+pub mod codec;
+pub mod io;
+pub mod resolver;
 pub mod tcp;
+pub mod timer;
+pub mod trigger;
 pub mod udp;
 
 #[cfg(unix)]
 pub mod uds;
 
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod device;
+#[cfg(target_os = "linux")]
+mod mark;
+mod priority;
 mod reactor;
+#[cfg(unix)]
+mod sigpipe;
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod sockaddr;
+#[cfg(unix)]
+mod sockopt;
 
+#[doc(inline)]
+pub use crate::reactor::{run, spawn, JoinHandle};
 #[doc(inline)]
 pub use crate::tcp::{TcpListener, TcpStream};
+pub use crate::trigger::trigger;
 #[doc(inline)]
 pub use crate::udp::UdpSocket;
@@ -0,0 +1,17 @@
+//! Marking the packets sent from a socket with `SO_MARK`, for policy routing
+//! and filtering on Linux.
+
+use std::io;
+use std::os::unix::io::AsRawFd;
+
+use crate::sockopt::{getsockopt, setsockopt};
+
+/// Returns the mark set on `socket`, `0` if none is set.
+pub(crate) fn mark(socket: &impl AsRawFd) -> io::Result<u32> {
+    Ok(getsockopt(socket, libc::SOL_SOCKET, libc::SO_MARK)? as u32)
+}
+
+/// Sets the mark on `socket`, which requires the `CAP_NET_ADMIN` capability.
+pub(crate) fn set_mark(socket: &impl AsRawFd, mark: u32) -> io::Result<()> {
+    setsockopt(socket, libc::SOL_SOCKET, libc::SO_MARK, mark as libc::c_int)
+}
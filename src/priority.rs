@@ -0,0 +1,48 @@
+//! Setting the priority of the packets sent from a socket with `SO_PRIORITY`,
+//! for queueing disciplines on Linux.
+//!
+//! Other platforms don't have the option, there both functions fail with an
+//! error of kind `Other`.
+
+use std::io;
+#[cfg(target_os = "linux")]
+use std::os::unix::io::AsRawFd;
+
+#[cfg(target_os = "linux")]
+use crate::sockopt::{getsockopt, setsockopt};
+
+/// Returns the priority of `socket`, `0` by default.
+#[cfg(target_os = "linux")]
+pub(crate) fn priority(socket: &impl AsRawFd) -> io::Result<u32> {
+    Ok(getsockopt(socket, libc::SOL_SOCKET, libc::SO_PRIORITY)? as u32)
+}
+
+/// Sets the priority of `socket`. Priorities above 6 require the
+/// `CAP_NET_ADMIN` capability.
+#[cfg(target_os = "linux")]
+pub(crate) fn set_priority(socket: &impl AsRawFd, priority: u32) -> io::Result<()> {
+    setsockopt(
+        socket,
+        libc::SOL_SOCKET,
+        libc::SO_PRIORITY,
+        priority as libc::c_int,
+    )
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn priority<T>(_: &T) -> io::Result<u32> {
+    Err(unsupported())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn set_priority<T>(_: &T, _: u32) -> io::Result<()> {
+    Err(unsupported())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Other,
+        "SO_PRIORITY is only supported on Linux",
+    )
+}
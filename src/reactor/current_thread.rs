@@ -0,0 +1,364 @@
+use super::{HandlePriv, Reactor, CURRENT_REACTOR};
+
+use futures::task::{local_waker_from_nonlocal, LocalWaker, Wake, Waker};
+use futures::{Future, Poll};
+use slab::Slab;
+
+use std::cell::RefCell;
+use std::fmt;
+use std::mem;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::usize;
+
+/// A task spawned onto `run`.
+type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+/// The key the future passed to `run` is scheduled with.
+const MAIN: usize = usize::MAX;
+
+/// Tasks spawned onto the `run` call of the current thread, which haven't
+/// been polled yet.
+thread_local!(static SPAWNED: RefCell<Option<Vec<Task>>> = RefCell::new(None));
+
+/// Runs a future to completion on the current thread, driving the I/O
+/// resources it uses along the way.
+///
+/// This starts a reactor on the current thread and makes it the reactor for
+/// all I/O resources and timers created while the future runs. The thread
+/// alternates between polling the future and waiting for events on the
+/// reactor, without any other threads involved, which makes it a complete
+/// runtime for simple single threaded applications.
+///
+/// Other tasks can be run alongside the future by passing them to [`spawn`].
+/// Any of them still running when the future completes are dropped.
+///
+/// I/O resources created inside of `run` are tied to its reactor, which is
+/// shut down once `run` returns. Using them afterwards fails with an error.
+/// Resources created elsewhere keep using the global reactor and can be used
+/// in `run` as well.
+///
+/// [`spawn`]: fn.spawn.html
+///
+/// # Panics
+///
+/// This function panics if the reactor can't be created, or if polling it
+/// fails.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// #![feature(async_await, await_macro, futures_api)]
+/// use futures::prelude::*;
+/// use romio::TcpListener;
+///
+/// fn main() -> std::io::Result<()> {
+///     romio::run(async {
+///         let mut listener = TcpListener::bind(&"127.0.0.1:7878".parse().unwrap())?;
+///
+///         while let Some(stream) = await!(listener.next()) {
+///             let mut stream = stream?;
+///             await!(stream.write_all(b"Good morrow!\n"))?;
+///         }
+///         Ok(())
+///     })
+/// }
+/// ```
+pub fn run<F: Future>(future: F) -> F::Output {
+    let mut reactor = Reactor::new().expect("failed to create reactor");
+    let handle = reactor.handle().into_priv().unwrap();
+    let _enter = Enter::new(handle.clone());
+
+    let scheduler = Arc::new(Scheduler {
+        ready: Mutex::new(vec![MAIN]),
+        handle,
+    });
+    let main_lw = scheduler.waker(MAIN);
+    let mut future = Box::pinned(future);
+    let mut tasks: Slab<(Task, LocalWaker)> = Slab::new();
+
+    loop {
+        let ready = mem::replace(&mut *scheduler.ready.lock().unwrap(), Vec::new());
+        for key in ready {
+            if key == MAIN {
+                if let Poll::Ready(output) = future.as_mut().poll(&main_lw) {
+                    return output;
+                }
+            } else if let Some((task, lw)) = tasks.get_mut(key) {
+                if task.as_mut().poll(lw).is_ready() {
+                    tasks.remove(key);
+                }
+            }
+        }
+
+        let spawned = SPAWNED.with(|spawned| match &mut *spawned.borrow_mut() {
+            Some(spawned) => mem::replace(spawned, Vec::new()),
+            None => Vec::new(),
+        });
+        for task in spawned {
+            let entry = tasks.vacant_entry();
+            let key = entry.key();
+            entry.insert((task, scheduler.waker(key)));
+            scheduler.schedule(key);
+        }
+
+        // Scheduling a task also wakes up the reactor, so this only blocks
+        // until there is something to do.
+        reactor.turn(None).expect("failed to poll reactor");
+    }
+}
+
+/// Spawns a task onto the [`run`] call of the current thread.
+///
+/// The task runs concurrently with the future passed to `run`, on the same
+/// thread, so it doesn't need to be `Send`. The returned [`JoinHandle`]
+/// resolves to its output. Dropping the handle cancels the task, call
+/// [`detach`] on it to let the task run to completion in the background.
+///
+/// Tasks still running when `run` returns are dropped.
+///
+/// [`run`]: fn.run.html
+/// [`JoinHandle`]: struct.JoinHandle.html
+/// [`detach`]: struct.JoinHandle.html#method.detach
+///
+/// # Panics
+///
+/// This function panics if it is called outside of `run`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// #![feature(async_await, await_macro, futures_api)]
+/// use futures::prelude::*;
+/// use romio::TcpListener;
+///
+/// fn main() -> std::io::Result<()> {
+///     romio::run(async {
+///         let mut listener = TcpListener::bind(&"127.0.0.1:7878".parse().unwrap())?;
+///
+///         while let Some(stream) = await!(listener.next()) {
+///             let mut stream = stream?;
+///             romio::spawn(async move {
+///                 let _ = await!(stream.write_all(b"Good morrow!\n"));
+///             })
+///             .detach();
+///         }
+///         Ok(())
+///     })
+/// }
+/// ```
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + 'static,
+    F::Output: 'static,
+{
+    let join = Arc::new(Mutex::new(Join {
+        output: None,
+        finished: false,
+        cancelled: false,
+        waker: None,
+        task: None,
+    }));
+    let task = Spawned {
+        future: Box::pinned(future),
+        join: join.clone(),
+    };
+
+    SPAWNED.with(|spawned| match &mut *spawned.borrow_mut() {
+        Some(spawned) => spawned.push(Box::pinned(task)),
+        None => panic!("`romio::spawn` called outside of `romio::run`"),
+    });
+
+    JoinHandle { join: Some(join) }
+}
+
+/// A handle to a task spawned with [`spawn`], resolving to its output.
+///
+/// Dropping the handle cancels the task, unless it was detached.
+///
+/// [`spawn`]: fn.spawn.html
+#[must_use = "dropping a `JoinHandle` cancels its task, use `detach` to let it run"]
+pub struct JoinHandle<T> {
+    /// `None` once detached.
+    join: Option<Arc<Mutex<Join<T>>>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Lets the task run to completion without waiting for it, discarding its
+    /// output.
+    pub fn detach(mut self) {
+        self.join.take();
+    }
+}
+
+impl<T> Unpin for JoinHandle<T> {}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = T;
+
+    /// # Panics
+    ///
+    /// Panics if the task was dropped before it completed, because the `run`
+    /// call it was spawned onto returned.
+    fn poll(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<T> {
+        let mut join = self.join.as_ref().unwrap().lock().unwrap();
+
+        if let Some(output) = join.output.take() {
+            return Poll::Ready(output);
+        }
+        if join.finished {
+            panic!("task was dropped before completing");
+        }
+
+        join.waker = Some(lw.clone().into_waker());
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for JoinHandle<T> {
+    fn drop(&mut self) {
+        let join = match self.join.take() {
+            Some(join) => join,
+            None => return,
+        };
+
+        let task = {
+            let mut join = join.lock().unwrap();
+            join.cancelled = true;
+            join.task.take()
+        };
+
+        // The task notices being cancelled the next time it is polled.
+        if let Some(task) = task {
+            task.wake();
+        }
+    }
+}
+
+impl<T> fmt::Debug for JoinHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinHandle")
+            .field("detached", &self.join.is_none())
+            .finish()
+    }
+}
+
+/// The state shared by a spawned task and its `JoinHandle`.
+struct Join<T> {
+    output: Option<T>,
+    /// Set once the task was dropped, completed or not.
+    finished: bool,
+    /// Set once the `JoinHandle` was dropped.
+    cancelled: bool,
+    /// The task waiting on the `JoinHandle`.
+    waker: Option<Waker>,
+    /// The spawned task, woken up to cancel it.
+    task: Option<Waker>,
+}
+
+/// Runs a spawned future, passing its output to the `JoinHandle`.
+struct Spawned<F: Future> {
+    future: Pin<Box<F>>,
+    join: Arc<Mutex<Join<F::Output>>>,
+}
+
+impl<F: Future> Unpin for Spawned<F> {}
+
+impl<F: Future> Future for Spawned<F> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<()> {
+        {
+            let mut join = self.join.lock().unwrap();
+            if join.cancelled {
+                return Poll::Ready(());
+            }
+            join.task = Some(lw.clone().into_waker());
+        }
+
+        if let Poll::Ready(output) = self.future.as_mut().poll(lw) {
+            self.join.lock().unwrap().output = Some(output);
+            return Poll::Ready(());
+        }
+        Poll::Pending
+    }
+}
+
+impl<F: Future> Drop for Spawned<F> {
+    fn drop(&mut self) {
+        let waker = {
+            let mut join = self.join.lock().unwrap();
+            join.finished = true;
+            join.task = None;
+            join.waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// The queue of tasks ready to be polled, shared with their wakers.
+struct Scheduler {
+    ready: Mutex<Vec<usize>>,
+    handle: HandlePriv,
+}
+
+impl Scheduler {
+    fn schedule(&self, key: usize) {
+        self.ready.lock().unwrap().push(key);
+        self.handle.wakeup();
+    }
+
+    fn waker(self: &Arc<Self>, key: usize) -> LocalWaker {
+        local_waker_from_nonlocal(Arc::new(Notify {
+            key,
+            scheduler: self.clone(),
+        }))
+    }
+}
+
+/// Wakes up a task by scheduling it.
+struct Notify {
+    key: usize,
+    scheduler: Arc<Scheduler>,
+}
+
+impl Wake for Notify {
+    fn wake(arc_self: &Arc<Self>) {
+        arc_self.scheduler.schedule(arc_self.key);
+    }
+}
+
+/// Makes a reactor the current one for the thread, and accepts tasks spawned
+/// on it, restoring the previous state when dropped.
+struct Enter {
+    prev_reactor: Option<HandlePriv>,
+    prev_spawned: Option<Vec<Task>>,
+}
+
+impl Enter {
+    fn new(handle: HandlePriv) -> Enter {
+        let prev_reactor = CURRENT_REACTOR.with(|current| current.borrow_mut().replace(handle));
+        let prev_spawned = SPAWNED.with(|spawned| spawned.borrow_mut().replace(Vec::new()));
+        Enter {
+            prev_reactor,
+            prev_spawned,
+        }
+    }
+}
+
+impl Drop for Enter {
+    fn drop(&mut self) {
+        let prev_spawned = self.prev_spawned.take();
+        let spawned =
+            SPAWNED.with(|spawned| mem::replace(&mut *spawned.borrow_mut(), prev_spawned));
+        // Dropping the tasks may spawn new ones, so it happens outside of the
+        // borrow.
+        drop(spawned);
+
+        let prev_reactor = self.prev_reactor.take();
+        CURRENT_REACTOR.with(|current| *current.borrow_mut() = prev_reactor);
+    }
+}
@@ -1,17 +1,23 @@
 pub(crate) mod background;
+mod current_thread;
 mod poll_evented;
 mod registration;
 mod sharded_rwlock;
+mod timer;
 
 // ===== Public re-exports =====
 
 use self::background::Background;
+pub use self::current_thread::{run, spawn, JoinHandle};
 pub use self::poll_evented::PollEvented;
+pub(crate) use self::poll_evented::{budget, set_budget};
 use self::registration::Registration;
+pub use self::timer::Delay;
 
 // ===== Private imports =====
 
 use self::sharded_rwlock::RwLock;
+use self::timer::Timers;
 
 use std::cell::RefCell;
 use std::io;
@@ -87,8 +93,49 @@ struct Inner {
     /// Dispatch slabs for I/O and futures events
     io_dispatch: RwLock<Slab<ScheduledIo>>,
 
+    /// Deadlines of the pending delays
+    timers: Timers,
+
     /// Used to wake up the reactor from a call to `turn`
     wakeup: mio::SetReadiness,
+
+    /// Counters exposed through `metrics`
+    metrics: Metrics,
+}
+
+/// The counters behind `ReactorMetrics`, updated with relaxed atomics.
+#[derive(Default)]
+struct Metrics {
+    registrations_total: AtomicUsize,
+    wakeups: AtomicUsize,
+    spurious_wakeups: AtomicUsize,
+}
+
+/// A snapshot of the counters of a reactor, as returned by
+/// `romio::io::reactor_metrics`.
+///
+/// The counters are updated without synchronization, so a snapshot taken
+/// while the reactor is busy may be slightly out of date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReactorMetrics {
+    /// The number of I/O resources registered with the reactor since it was
+    /// created.
+    pub registrations_total: u64,
+
+    /// The number of I/O resources currently registered with the reactor.
+    pub registrations: usize,
+
+    /// The number of readiness events the reactor dispatched to I/O
+    /// resources.
+    pub wakeups: u64,
+
+    /// The number of times an I/O resource was reported ready, but the first
+    /// operation attempted on it failed with `WouldBlock`.
+    ///
+    /// A few of these are expected, for example when the readiness was used
+    /// up by another handle to the same socket. A high rate compared to
+    /// `wakeups` hints at tasks waking up for nothing.
+    pub spurious_wakeups: u64,
 }
 
 struct ScheduledIo {
@@ -96,6 +143,9 @@ struct ScheduledIo {
     readiness: AtomicUsize,
     reader: AtomicWaker,
     writer: AtomicWaker,
+    /// Woken along with `reader`, for watching a resource without taking the
+    /// place of the task reading from it.
+    watcher: AtomicWaker,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
@@ -145,7 +195,9 @@ impl Reactor {
                 io: io,
                 next_aba_guard: AtomicUsize::new(0),
                 io_dispatch: RwLock::new(Slab::with_capacity(1)),
+                timers: Timers::default(),
                 wakeup: wakeup_pair.1,
+                metrics: Metrics::default(),
             }),
         })
     }
@@ -214,6 +266,12 @@ impl Reactor {
     }
 
     fn poll(&mut self, max_wait: Option<Duration>) -> io::Result<()> {
+        // Don't block past the next deadline of a pending delay.
+        let max_wait = match (max_wait, self.inner.timers.next_timeout(Instant::now())) {
+            (Some(max_wait), Some(timeout)) => Some(max_wait.min(timeout)),
+            (max_wait, timeout) => max_wait.or(timeout),
+        };
+
         // Block waiting for an event to happen, peeling out how many events
         // happened.
         match self.inner.io.poll(&mut self.events, max_wait) {
@@ -229,6 +287,7 @@ impl Reactor {
 
         // Process all the events that came in, dispatching appropriately
         let mut events = 0;
+        let mut dispatched = 0;
         for event in self.events.iter() {
             events += 1;
             let token = event.token();
@@ -241,9 +300,13 @@ impl Reactor {
                     .unwrap();
             } else {
                 self.dispatch(token, event.readiness());
+                dispatched += 1;
             }
         }
 
+        self.inner.metrics.wakeups.fetch_add(dispatched, Relaxed);
+        self.inner.timers.fire(Instant::now());
+
         if let Some(start) = start {
             let dur = start.elapsed();
             trace!(
@@ -263,6 +326,7 @@ impl Reactor {
 
         let mut rd = None;
         let mut wr = None;
+        let mut watch = None;
 
         // Create a scope to ensure that notifying the tasks stays out of the
         // lock's critical section.
@@ -280,12 +344,13 @@ impl Reactor {
 
             io.readiness.fetch_or(ready.as_usize(), Relaxed);
 
-            if ready.is_writable() || platform::is_hup(&ready) {
+            if ready.is_writable() || platform::is_hup(&ready) || platform::is_error(&ready) {
                 wr = io.writer.take();
             }
 
             if !(ready & (!mio::Ready::writable())).is_empty() {
                 rd = io.reader.take();
+                watch = io.watcher.take();
             }
         }
 
@@ -293,6 +358,10 @@ impl Reactor {
             task.wake();
         }
 
+        if let Some(task) = watch {
+            task.wake();
+        }
+
         if let Some(task) = wr {
             task.wake();
         }
@@ -452,6 +521,15 @@ impl HandlePriv {
     }
 }
 
+/// Returns a snapshot of the counters of the reactor for the current
+/// execution context.
+pub(crate) fn metrics() -> io::Result<ReactorMetrics> {
+    match HandlePriv::try_current()?.inner() {
+        Some(inner) => Ok(inner.metrics()),
+        None => Err(io::Error::new(io::ErrorKind::Other, "reactor gone")),
+    }
+}
+
 impl fmt::Debug for HandlePriv {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "HandlePriv")
@@ -461,10 +539,11 @@ impl fmt::Debug for HandlePriv {
 // ===== impl Inner =====
 
 impl Inner {
-    /// Register an I/O resource with the reactor.
+    /// Register an I/O resource with the reactor, receiving the readiness
+    /// events in `interest`.
     ///
     /// The registration token is returned.
-    fn add_source(&self, source: &dyn Evented) -> io::Result<usize> {
+    fn add_source(&self, source: &dyn Evented, interest: mio::Ready) -> io::Result<usize> {
         // Get an ABA guard value
         let aba_guard = self.next_aba_guard.fetch_add(1 << TOKEN_SHIFT, Relaxed);
 
@@ -484,18 +563,35 @@ impl Inner {
             readiness: AtomicUsize::new(0),
             reader: AtomicWaker::new(),
             writer: AtomicWaker::new(),
+            watcher: AtomicWaker::new(),
         });
 
         self.io.register(
             source,
             mio::Token(aba_guard | key),
-            mio::Ready::all(),
+            interest,
             mio::PollOpt::edge(),
         )?;
 
+        self.metrics.registrations_total.fetch_add(1, Relaxed);
         Ok(key)
     }
 
+    /// Counts an operation which failed with `WouldBlock` right after its I/O
+    /// resource was reported ready.
+    fn record_spurious_wakeup(&self) {
+        self.metrics.spurious_wakeups.fetch_add(1, Relaxed);
+    }
+
+    fn metrics(&self) -> ReactorMetrics {
+        ReactorMetrics {
+            registrations_total: self.metrics.registrations_total.load(Relaxed) as u64,
+            registrations: self.io_dispatch.read().len(),
+            wakeups: self.metrics.wakeups.load(Relaxed) as u64,
+            spurious_wakeups: self.metrics.spurious_wakeups.load(Relaxed) as u64,
+        }
+    }
+
     /// Deregisters an I/O resource from the reactor.
     fn deregister_source(&self, source: &dyn Evented) -> io::Result<()> {
         self.io.deregister(source)
@@ -523,6 +619,13 @@ impl Inner {
             waker.wake();
         }
     }
+
+    /// Registers a task to be notified of the next read readiness event of
+    /// the I/O resource associated with `token`, leaving its readiness as is.
+    fn register_watcher(&self, lw: &LocalWaker, token: usize) {
+        let io_dispatch = self.io_dispatch.read();
+        io_dispatch.get(token).unwrap().watcher.register(lw);
+    }
 }
 
 impl Drop for Inner {
@@ -534,7 +637,10 @@ impl Drop for Inner {
         for (_, io) in io.iter() {
             io.writer.wake();
             io.reader.wake();
+            io.watcher.wake();
         }
+
+        self.timers.fire_all();
     }
 }
 
@@ -545,13 +651,13 @@ impl Direction {
                 // Everything except writable is signaled through read.
                 mio::Ready::all() - mio::Ready::writable()
             }
-            Direction::Write => mio::Ready::writable() | platform::hup(),
+            Direction::Write => mio::Ready::writable() | platform::hup() | platform::error(),
         }
     }
 }
 
 #[cfg(unix)]
-mod platform {
+pub(crate) mod platform {
     use mio::unix::UnixReady;
     use mio::Ready;
 
@@ -562,10 +668,30 @@ mod platform {
     pub fn is_hup(ready: &Ready) -> bool {
         UnixReady::from(*ready).is_hup()
     }
+
+    pub fn error() -> Ready {
+        UnixReady::error().into()
+    }
+
+    pub fn is_error(ready: &Ready) -> bool {
+        UnixReady::from(*ready).is_error()
+    }
+
+    /// The readiness urgent data is reported with.
+    #[cfg(target_os = "linux")]
+    pub fn priority() -> Ready {
+        UnixReady::priority().into()
+    }
+
+    /// The readiness urgent data is reported with.
+    #[cfg(not(target_os = "linux"))]
+    pub fn priority() -> Ready {
+        Ready::readable()
+    }
 }
 
 #[cfg(windows)]
-mod platform {
+pub(crate) mod platform {
     use mio::Ready;
 
     pub fn hup() -> Ready {
@@ -575,4 +701,12 @@ mod platform {
     pub fn is_hup(_: &Ready) -> bool {
         false
     }
+
+    pub fn error() -> Ready {
+        Ready::empty()
+    }
+
+    pub fn is_error(_: &Ready) -> bool {
+        false
+    }
 }
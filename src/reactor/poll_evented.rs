@@ -8,8 +8,23 @@ use mio::event::Evented;
 
 use std::fmt;
 use std::io::{self, Read, Write};
-use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering::Relaxed;
+use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT};
+
+/// The number of readiness checks which succeed in a row before yielding, or 0
+/// if unlimited. Starts out as `DEFAULT_BUDGET`.
+static BUDGET: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// The budget used unless `set_budget` was called.
+const DEFAULT_BUDGET: usize = 32;
+
+/// Stands in for the readiness checks of a resource which was assumed to be
+/// ready instead of being reported ready, so that the first attempt blocking
+/// isn't counted as a spurious wakeup.
+const ASSUMED: usize = usize::max_value() / 2;
+
+/// Encodes an unlimited budget in `BUDGET`, which is also `None` when decoded.
+const UNLIMITED: usize = usize::max_value();
 
 /// Associates an I/O resource that implements the [`std::io::Read`] and/or
 /// [`std::io::Write`] traits with the reactor that drives it.
@@ -69,12 +84,44 @@ use std::sync::atomic::Ordering::Relaxed;
 /// }
 /// ```
 ///
+/// ## Budget
+///
+/// A resource which is always ready would let a task loop over it forever
+/// within a single poll, starving the other tasks of its executor thread. To
+/// prevent that, a `PollEvented` counts the readiness checks in each direction
+/// which succeed in a row. Once that count exceeds the crate-wide budget set
+/// with `romio::io::set_poll_budget`, the check returns `Poll::Pending` once,
+/// after waking the current task, so that it is polled again after the others.
+///
+/// The budget is meant for a single poll of the task. The count starts over
+/// whenever readiness is cleared, and whenever the task had to wait for the
+/// reactor to report the resource ready again, since the event wakes the task
+/// up for a new poll. Operations handling a batch of events at once, like
+/// accepting many connections, are counted once.
+///
 /// ## Platform-specific events
 ///
 /// `PollEvented` also allows receiving platform-specific `mio::Ready` events.
 /// These events are included as part of the read readiness event stream. The
 /// write readiness event stream is only for `Ready::writable()` events.
 ///
+/// By default a resource is registered for all events, but only readable,
+/// writable, HUP and error readiness are reported. Passing a different
+/// interest to [`new_with_interest`] also reports the platform-specific
+/// events it contains. The flags supported by `mio` are:
+///
+/// * `UnixReady::hup()` and `UnixReady::error()` on all Unix platforms. These
+///   are always reported, whether they are part of the interest or not.
+/// * `UnixReady::priority()` on Linux, for priority data like TCP urgent
+///   data (`EPOLLPRI`). It is reported together with readable readiness.
+/// * `UnixReady::aio()` and `UnixReady::lio()` on the BSDs and macOS.
+///
+/// Windows has no platform-specific events, and never reports HUP or errors.
+///
+/// HUP and error readiness can also be checked on their own with
+/// [`poll_hup_ready`], to find out about a connection being closed or
+/// failing without reading from it.
+///
 /// [`std::io::Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
 /// [`std::io::Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
 /// [`AsyncRead`]: ../io/trait.AsyncRead.html
@@ -84,6 +131,8 @@ use std::sync::atomic::Ordering::Relaxed;
 /// [`TcpListener`]: ../net/struct.TcpListener.html
 /// [`clear_read_ready`]: #method.clear_read_ready
 /// [`clear_write_ready`]: #method.clear_write_ready
+/// [`new_with_interest`]: #method.new_with_interest
+/// [`poll_hup_ready`]: #method.poll_hup_ready
 /// [`poll_read_ready`]: #method.poll_read_ready
 /// [`poll_write_ready`]: #method.poll_write_ready
 pub struct PollEvented<E: Evented> {
@@ -94,11 +143,47 @@ pub struct PollEvented<E: Evented> {
 struct Inner {
     registration: Registration,
 
+    /// The events the resource is registered for
+    interest: mio::Ready,
+
+    /// The read readiness reported besides HUP and error, which stays set
+    /// until cleared
+    read_interest: mio::Ready,
+
     /// Currently visible read readiness
     read_readiness: AtomicUsize,
 
     /// Currently visible write readiness
     write_readiness: AtomicUsize,
+
+    /// Read readiness checks which succeeded in a row
+    read_streak: AtomicUsize,
+
+    /// Write readiness checks which succeeded in a row
+    write_streak: AtomicUsize,
+
+    /// Read readiness checks which succeeded since read readiness was last
+    /// cleared
+    read_checks: AtomicUsize,
+
+    /// Write readiness checks which succeeded since write readiness was last
+    /// cleared
+    write_checks: AtomicUsize,
+}
+
+/// Returns the budget shared by all `PollEvented` instances, see the type
+/// documentation.
+pub(crate) fn budget() -> Option<usize> {
+    match BUDGET.load(Relaxed) {
+        0 => Some(DEFAULT_BUDGET),
+        UNLIMITED => None,
+        budget => Some(budget),
+    }
+}
+
+/// Sets the budget shared by all `PollEvented` instances, `None` disables it.
+pub(crate) fn set_budget(budget: Option<usize>) {
+    BUDGET.store(budget.unwrap_or(UNLIMITED), Relaxed);
 }
 
 // ===== impl PollEvented =====
@@ -109,12 +194,66 @@ where
 {
     /// Creates a new `PollEvented` associated with the default reactor.
     pub fn new(io: E) -> PollEvented<E> {
+        PollEvented::with_interest(io, mio::Ready::all(), mio::Ready::readable())
+    }
+
+    /// Creates a new `PollEvented` associated with the default reactor, which
+    /// only registers for the readiness events in `interest`.
+    ///
+    /// Besides readable readiness, [`poll_read_ready`] reports the
+    /// platform-specific events in `interest`, see the type documentation
+    /// for the supported ones. Leaving out `readable` or `writable` means the
+    /// resource is never ready in that direction.
+    ///
+    /// [`poll_read_ready`]: #method.poll_read_ready
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use mio::unix::UnixReady;
+    ///
+    /// // Wake up readers for TCP urgent data as well.
+    /// let interest = mio::Ready::readable() | mio::Ready::writable() | UnixReady::priority();
+    /// let io = PollEvented::new_with_interest(stream, interest);
+    /// ```
+    pub fn new_with_interest(io: E, interest: mio::Ready) -> PollEvented<E> {
+        let read_interest = interest - mio::Ready::writable() - hup_or_error();
+        PollEvented::with_interest(io, interest, read_interest)
+    }
+
+    /// Creates a new `PollEvented` associated with the default reactor, which
+    /// starts out readable and writable.
+    ///
+    /// The first read and write are attempted right away instead of waiting
+    /// for the reactor to report the resource ready, which takes a round-trip
+    /// through the reactor even if data is already there. Once an attempt
+    /// would block, clearing the readiness registers the task as usual.
+    pub(crate) fn new_ready(io: E) -> PollEvented<E> {
+        let io = PollEvented::new(io);
+        io.inner
+            .read_readiness
+            .store(mio::Ready::readable().as_usize(), Relaxed);
+        io.inner
+            .write_readiness
+            .store(mio::Ready::writable().as_usize(), Relaxed);
+        io.inner.read_checks.store(ASSUMED, Relaxed);
+        io.inner.write_checks.store(ASSUMED, Relaxed);
+        io
+    }
+
+    fn with_interest(io: E, interest: mio::Ready, read_interest: mio::Ready) -> PollEvented<E> {
         PollEvented {
             io: Some(io),
             inner: Inner {
                 registration: Registration::new(),
+                interest,
+                read_interest,
                 read_readiness: AtomicUsize::new(0),
                 write_readiness: AtomicUsize::new(0),
+                read_streak: AtomicUsize::new(0),
+                write_streak: AtomicUsize::new(0),
+                read_checks: AtomicUsize::new(0),
+                write_checks: AtomicUsize::new(0),
             },
         }
     }
@@ -145,12 +284,35 @@ where
         Ok(io)
     }
 
+    /// Consumes self, returning the inner I/O object.
+    ///
+    /// Like `into_inner`, this deregisters the I/O resource from the reactor,
+    /// but the resource is returned even if that fails, for example because
+    /// the reactor is gone.
+    pub(crate) fn detach(mut self) -> E {
+        let io = self.io.take().unwrap();
+        let _ = self.inner.registration.deregister(&io);
+        io
+    }
+
+    /// Deregisters the I/O resource from the reactor that drives it.
+    ///
+    /// Once deregistered, the reactor no longer delivers readiness events for
+    /// the resource, so tasks waiting on it will not be notified again. This is
+    /// useful when handing the underlying resource back to blocking code.
+    ///
+    /// Dropping a `PollEvented` deregisters the resource as well, so calling
+    /// this function is only necessary when the resource outlives it.
+    pub fn deregister(&self) -> io::Result<()> {
+        self.inner.registration.deregister(self.get_ref())
+    }
+
     /// Check the I/O resource's read readiness state.
     ///
     /// The mask argument allows specifying what readiness to notify on. This
     /// can be any value, including platform specific readiness, **except**
-    /// `writable`. HUP is always implicitly included on platforms that support
-    /// it.
+    /// `writable`. HUP and error readiness are always implicitly included on
+    /// platforms that support them.
     ///
     /// If the resource is not ready for a read then `Async::NotReady` is
     /// returned and the current task is notified once a new event is received.
@@ -160,14 +322,30 @@ where
     ///
     /// [`clear_read_ready`]: #method.clear_read_ready
     pub fn poll_read_ready(&self, lw: &LocalWaker) -> Poll<io::Result<mio::Ready>> {
+        let ready = ready!(self.poll_read_ready_unbudgeted(lw)?);
+        ready!(poll_budget(&self.inner.read_streak, lw));
+        Poll::Ready(Ok(ready))
+    }
+
+    /// Like `poll_read_ready`, without counting the check against the budget.
+    ///
+    /// This is for operations which handle many events at once, like
+    /// accepting a batch of connections, and are charged as one.
+    pub(crate) fn poll_read_ready_unbudgeted(
+        &self,
+        lw: &LocalWaker,
+    ) -> Poll<io::Result<mio::Ready>> {
         self.register()?;
 
         // Load cached & encoded readiness.
         let mut cached = self.inner.read_readiness.load(Relaxed);
-        let mask = mio::Ready::readable() | super::platform::hup();
+        let mask = self.inner.read_interest | hup_or_error();
 
-        // See if the current readiness matches any bits.
-        let mut ret = mio::Ready::from_usize(cached) & mio::Ready::readable();
+        // See if the current readiness matches any bits. HUP is final, so once
+        // it was seen the resource stays ready, even if it was the only event
+        // delivered and readable readiness is cleared.
+        let mut ret =
+            mio::Ready::from_usize(cached) & (self.inner.read_interest | super::platform::hup());
 
         if ret.is_empty() {
             // Readiness does not match, consume the registration's readiness
@@ -183,6 +361,10 @@ where
                 ret |= ready & mask;
 
                 if !ret.is_empty() {
+                    // The task waited for this event, so the budget of the
+                    // task poll it causes starts over.
+                    self.inner.read_streak.store(0, Relaxed);
+                    self.inner.read_checks.fetch_add(1, Relaxed);
                     return Poll::Ready(Ok(ret));
                 }
             }
@@ -194,6 +376,7 @@ where
                 self.inner.read_readiness.store(cached, Relaxed);
             }
 
+            self.inner.read_checks.fetch_add(1, Relaxed);
             Poll::Ready(Ok(mio::Ready::from_usize(cached)))
         }
     }
@@ -204,25 +387,30 @@ where
     /// After calling this function, `poll_read_ready` will return `NotReady`
     /// until a new read readiness event has been received.
     ///
-    /// The `mask` argument specifies the readiness bits to clear. This may not
-    /// include `writable` or `hup`.
+    /// This only clears readable readiness, and the platform-specific
+    /// readiness the resource was registered for. HUP (on platforms that
+    /// support HUP) cannot be cleared as it is a final state, so a resource
+    /// whose peer hung up stays ready and reads see the end of the stream.
     pub fn clear_read_ready(&self, lw: &LocalWaker) -> io::Result<()> {
         self.inner
             .read_readiness
-            .fetch_and(!mio::Ready::readable().as_usize(), Relaxed);
+            .fetch_and(!self.inner.read_interest.as_usize(), Relaxed);
+        self.inner.read_streak.store(0, Relaxed);
+        self.record_clear(&self.inner.read_checks);
 
-        if self.poll_read_ready(lw)?.is_ready() {
+        if self.poll_read_ready_unbudgeted(lw)?.is_ready() {
             // Notify the current task
             lw.wake();
         }
+        self.inner.read_checks.store(0, Relaxed);
 
         Ok(())
     }
 
     /// Check the I/O resource's write readiness state.
     ///
-    /// This always checks for writable readiness and also checks for HUP
-    /// readiness on platforms that support it.
+    /// This always checks for writable readiness and also checks for HUP and
+    /// error readiness on platforms that support them.
     ///
     /// If the resource is not ready for a write then `Async::NotReady` is
     /// returned and the current task is notified once a new event is received.
@@ -239,13 +427,23 @@ where
     /// * `ready` contains bits besides `writable` and `hup`.
     /// * called from outside of a task context.
     pub fn poll_write_ready(&self, lw: &LocalWaker) -> Poll<Result<mio::Ready, io::Error>> {
+        let ready = ready!(self.poll_write_ready_unbudgeted(lw)?);
+        ready!(poll_budget(&self.inner.write_streak, lw));
+        Poll::Ready(Ok(ready))
+    }
+
+    /// Like `poll_write_ready`, without counting the check against the budget.
+    fn poll_write_ready_unbudgeted(&self, lw: &LocalWaker) -> Poll<io::Result<mio::Ready>> {
         self.register()?;
 
         // Load cached & encoded readiness.
         let mut cached = self.inner.write_readiness.load(Relaxed);
-        let mask = mio::Ready::writable() | super::platform::hup();
+        let mask = mio::Ready::writable() | hup_or_error();
 
-        // See if the current readiness matches any bits.
+        // See if the current readiness matches any bits. Unlike reads, a
+        // cached HUP doesn't keep writes ready: a peer that only shut down
+        // its side still accepts data, and a full send buffer would otherwise
+        // wake the writer over and over.
         let mut ret = mio::Ready::from_usize(cached) & mio::Ready::writable();
 
         if ret.is_empty() {
@@ -262,6 +460,8 @@ where
                 ret |= ready & mask;
 
                 if !ret.is_empty() {
+                    self.inner.write_streak.store(0, Relaxed);
+                    self.inner.write_checks.fetch_add(1, Relaxed);
                     return Poll::Ready(Ok(ret));
                 }
             }
@@ -273,6 +473,7 @@ where
                 self.inner.write_readiness.store(cached, Relaxed);
             }
 
+            self.inner.write_checks.fetch_add(1, Relaxed);
             Poll::Ready(Ok(mio::Ready::from_usize(cached)))
         }
     }
@@ -293,20 +494,181 @@ where
         self.inner
             .write_readiness
             .fetch_and(!mio::Ready::writable().as_usize(), Relaxed);
+        self.inner.write_streak.store(0, Relaxed);
+        self.record_clear(&self.inner.write_checks);
 
-        if self.poll_write_ready(lw)?.is_ready() {
+        if self.poll_write_ready_unbudgeted(lw)?.is_ready() {
             // Notify the current task
             lw.wake();
         }
+        self.inner.write_checks.store(0, Relaxed);
 
         Ok(())
     }
 
+    /// Check the I/O resource's HUP and error readiness state, without waiting
+    /// for it to be readable or writable.
+    ///
+    /// This allows noticing that a connection was closed or failed while no
+    /// data is being read, for example a half-open connection whose peer went
+    /// away. The returned readiness contains the HUP and error bits which are
+    /// set. Both are final states, so once returned they are returned again.
+    ///
+    /// Waiting for HUP or error readiness uses the same task slot as
+    /// [`poll_read_ready`], so it must not be done concurrently with reading
+    /// from another task. On platforms without HUP and error readiness this
+    /// never becomes ready.
+    ///
+    /// [`poll_read_ready`]: #method.poll_read_ready
+    pub fn poll_hup_ready(&self, lw: &LocalWaker) -> Poll<io::Result<mio::Ready>> {
+        self.poll_cached_ready(hup_or_error(), lw)
+    }
+
+    /// Check the I/O resource's readiness for urgent data, like TCP
+    /// out-of-band data.
+    ///
+    /// On Linux this is priority readiness, which stays set until cleared by
+    /// [`clear_priority_ready`], independently of readable readiness. Other
+    /// platforms report urgent data as readable, so this checks readable
+    /// readiness instead.
+    ///
+    /// Like [`poll_hup_ready`], this uses the task slot of [`poll_read_ready`].
+    ///
+    /// [`clear_priority_ready`]: #method.clear_priority_ready
+    /// [`poll_hup_ready`]: #method.poll_hup_ready
+    /// [`poll_read_ready`]: #method.poll_read_ready
+    #[cfg(unix)]
+    pub(crate) fn poll_priority_ready(&self, lw: &LocalWaker) -> Poll<io::Result<mio::Ready>> {
+        self.poll_cached_ready(super::platform::priority(), lw)
+    }
+
+    /// Clears the I/O resource's urgent data readiness and registers the
+    /// current task to be notified once it is set again.
+    #[cfg(unix)]
+    pub(crate) fn clear_priority_ready(&self, lw: &LocalWaker) -> io::Result<()> {
+        self.inner
+            .read_readiness
+            .fetch_and(!super::platform::priority().as_usize(), Relaxed);
+
+        if self.poll_priority_ready(lw)?.is_ready() {
+            // Notify the current task
+            lw.wake();
+        }
+
+        Ok(())
+    }
+
+    /// Registers the current task to be notified once the next read readiness
+    /// event is received, without consuming any readiness.
+    ///
+    /// This doesn't take the place of the task polling for read readiness, so
+    /// another task can use it to watch the resource, for example to notice
+    /// the connection being closed.
+    pub(crate) fn register_watcher(&self, lw: &LocalWaker) -> io::Result<()> {
+        self.register()?;
+        self.inner.registration.register_watcher(lw)
+    }
+
+    /// Checks the I/O resource's readiness for any of the events in
+    /// `interest`, without performing any I/O.
+    ///
+    /// Readable (and platform-specific) interest is checked like in
+    /// [`poll_read_ready`], writable interest like in [`poll_write_ready`].
+    /// The returned readiness includes all events which are set, or
+    /// `Poll::Pending` is returned and the current task is notified once one
+    /// of the requested events is received.
+    ///
+    /// [`poll_read_ready`]: #method.poll_read_ready
+    /// [`poll_write_ready`]: #method.poll_write_ready
+    pub fn poll_ready(
+        &self,
+        interest: mio::Ready,
+        lw: &LocalWaker,
+    ) -> Poll<io::Result<mio::Ready>> {
+        let mut ret = mio::Ready::empty();
+
+        if !(interest - mio::Ready::writable()).is_empty() {
+            if let Poll::Ready(ready) = self.poll_read_ready(lw)? {
+                ret |= ready;
+            }
+        }
+        if interest.is_writable() {
+            if let Poll::Ready(ready) = self.poll_write_ready(lw)? {
+                ret |= ready;
+            }
+        }
+
+        if ret.is_empty() {
+            Poll::Pending
+        } else {
+            Poll::Ready(Ok(ret))
+        }
+    }
+
+    /// Clears the readable and writable bits of `ready` from the I/O resource's
+    /// readiness state.
+    ///
+    /// Unlike [`clear_read_ready`] and [`clear_write_ready`] this doesn't need
+    /// a task context. No task is registered for notification, the next call
+    /// to one of the `poll_*_ready` functions does that.
+    ///
+    /// [`clear_read_ready`]: #method.clear_read_ready
+    /// [`clear_write_ready`]: #method.clear_write_ready
+    pub fn clear_ready(&self, ready: mio::Ready) {
+        if ready.is_readable() {
+            self.inner
+                .read_readiness
+                .fetch_and(!mio::Ready::readable().as_usize(), Relaxed);
+            self.inner.read_streak.store(0, Relaxed);
+            self.record_clear(&self.inner.read_checks);
+        }
+        if ready.is_writable() {
+            self.inner
+                .write_readiness
+                .fetch_and(!mio::Ready::writable().as_usize(), Relaxed);
+            self.inner.write_streak.store(0, Relaxed);
+            self.record_clear(&self.inner.write_checks);
+        }
+    }
+
+    /// Resets the readiness checks of a direction whose readiness is cleared,
+    /// counting a spurious wakeup if the first operation after the resource
+    /// was reported ready would block.
+    fn record_clear(&self, checks: &AtomicUsize) {
+        if checks.swap(0, Relaxed) == 1 {
+            self.inner.registration.record_spurious_wakeup();
+        }
+    }
+
+    /// Waits for any of the events in `mask` to be set in the readiness cached
+    /// by either direction, consuming the read readiness stream.
+    fn poll_cached_ready(&self, mask: mio::Ready, lw: &LocalWaker) -> Poll<io::Result<mio::Ready>> {
+        self.register()?;
+
+        // The write direction consumes error readiness too, so look at both
+        // caches.
+        let mut cached =
+            self.inner.read_readiness.load(Relaxed) | self.inner.write_readiness.load(Relaxed);
+
+        loop {
+            let ret = mio::Ready::from_usize(cached) & mask;
+            if !ret.is_empty() {
+                return Poll::Ready(Ok(ret));
+            }
+
+            let ready = ready!(self.inner.registration.poll_read_ready(lw)?);
+            self.inner
+                .read_readiness
+                .fetch_or(ready.as_usize(), Relaxed);
+            cached |= ready.as_usize();
+        }
+    }
+
     /// Ensure that the I/O resource is registered with the reactor.
     fn register(&self) -> io::Result<()> {
         self.inner
             .registration
-            .register(self.io.as_ref().unwrap())?;
+            .register_with_interest(self.io.as_ref().unwrap(), self.inner.interest)?;
         Ok(())
     }
 }
@@ -423,6 +785,27 @@ where
     }
 }
 
+/// The readiness which is reported in both directions, where supported.
+fn hup_or_error() -> mio::Ready {
+    super::platform::hup() | super::platform::error()
+}
+
+/// Counts a readiness check against the budget, yielding once it is used up.
+fn poll_budget(streak: &AtomicUsize, lw: &LocalWaker) -> Poll<()> {
+    let budget = match budget() {
+        Some(budget) => budget,
+        None => return Poll::Ready(()),
+    };
+
+    if streak.fetch_add(1, Relaxed) < budget {
+        Poll::Ready(())
+    } else {
+        streak.store(0, Relaxed);
+        lw.wake();
+        Poll::Pending
+    }
+}
+
 fn is_wouldblock<T>(r: &io::Result<T>) -> bool {
     match *r {
         Ok(_) => false,
@@ -444,3 +827,55 @@ impl<E: Evented> Drop for PollEvented<E> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::PollEvented;
+    use crate::reactor::{platform, HandlePriv};
+
+    use futures::{executor, future};
+
+    use std::net::{self, Shutdown};
+
+    #[test]
+    fn drop_releases_registration() {
+        let inner = HandlePriv::try_current().unwrap().inner().unwrap();
+        let before = inner.io_dispatch.read().len();
+
+        for _ in 0..2048 {
+            let addr = "127.0.0.1:0".parse().unwrap();
+            let io = PollEvented::new(mio::net::UdpSocket::bind(&addr).unwrap());
+            io.register().unwrap();
+            assert_eq!(inner.io_dispatch.read().len(), before + 1);
+        }
+
+        assert_eq!(inner.io_dispatch.read().len(), before);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn poll_hup_ready_on_closed_connection() {
+        let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+
+        let client = mio::net::TcpStream::from_stream(client).unwrap();
+        let io = PollEvented::new_with_interest(client, mio::Ready::readable());
+
+        io.get_ref().shutdown(Shutdown::Write).unwrap();
+        drop(server);
+
+        let ready = executor::block_on(future::poll_fn(|lw| io.poll_hup_ready(lw))).unwrap();
+        assert!(platform::is_hup(&ready));
+    }
+
+    #[test]
+    fn deregister_before_register() {
+        let addr = "127.0.0.1:0".parse().unwrap();
+        let io = PollEvented::new(mio::net::UdpSocket::bind(&addr).unwrap());
+        io.deregister().unwrap();
+
+        io.register().unwrap();
+        io.deregister().unwrap();
+    }
+}
@@ -113,7 +113,20 @@ impl Registration {
     ///
     /// If an error is encountered during registration, `Err` is returned.
     pub fn register(&self, io: &impl Evented) -> io::Result<bool> {
-        self.register2(io, || HandlePriv::try_current())
+        self.register_with_interest(io, mio::Ready::all())
+    }
+
+    /// Register the I/O resource with the default reactor, only receiving the
+    /// readiness events in `interest`.
+    ///
+    /// Like `register`, only the first call establishes the registration, and
+    /// the interest passed to subsequent calls is ignored.
+    pub fn register_with_interest(
+        &self,
+        io: &impl Evented,
+        interest: mio::Ready,
+    ) -> io::Result<bool> {
+        self.register2(io, interest, || HandlePriv::try_current())
     }
 
     /// Deregister the I/O resource from the reactor it is associated with.
@@ -132,10 +145,15 @@ impl Registration {
     /// no longer result in notifications getting sent for this registration.
     ///
     /// `Err` is returned if an error is encountered.
-    pub fn deregister(&mut self, io: &impl Evented) -> io::Result<()> {
-        // The state does not need to be checked and coordination is not
-        // necessary as this function takes `&mut self`. This guarantees a
-        // single thread is accessing the instance.
+    pub fn deregister(&self, io: &impl Evented) -> io::Result<()> {
+        // The inner value is only written while the state is `LOCKED`. Once
+        // the state has transitioned to `READY` it is never written again, so
+        // it can be read without further coordination. If the resource was
+        // never registered there is nothing to do.
+        if self.state.load(SeqCst) != READY {
+            return Ok(());
+        }
+
         if let Some(inner) = unsafe { (*self.inner.get()).as_ref() } {
             inner.deregister(io)?;
         }
@@ -143,7 +161,39 @@ impl Registration {
         Ok(())
     }
 
-    fn register2<T, F>(&self, io: &T, f: F) -> io::Result<bool>
+    /// Registers the current task to be notified once the next read readiness
+    /// event is received.
+    ///
+    /// Unlike `poll_read_ready`, this neither consumes the readiness nor takes
+    /// the place of the task waiting on it, so it can be used to watch the
+    /// resource from another task. The resource has to be registered first.
+    pub(crate) fn register_watcher(&self, lw: &LocalWaker) -> io::Result<()> {
+        if self.state.load(SeqCst) != READY {
+            // Still being registered by another thread, check again shortly.
+            lw.wake();
+            return Ok(());
+        }
+
+        match unsafe { (*self.inner.get()).as_ref() } {
+            Some(inner) => inner.register_watcher(lw),
+            None => Ok(()),
+        }
+    }
+
+    /// Counts a spurious wakeup in the metrics of the reactor the resource is
+    /// registered with.
+    pub(crate) fn record_spurious_wakeup(&self) {
+        if self.state.load(SeqCst) != READY {
+            return;
+        }
+
+        let inner = unsafe { (*self.inner.get()).as_ref() };
+        if let Some(reactor) = inner.and_then(|inner| inner.handle.inner()) {
+            reactor.record_spurious_wakeup();
+        }
+    }
+
+    fn register2<T, F>(&self, io: &T, interest: mio::Ready, f: F) -> io::Result<bool>
     where
         T: Evented,
         F: Fn() -> io::Result<HandlePriv>,
@@ -165,7 +215,7 @@ impl Registration {
                     }
 
                     // Create the actual registration
-                    let (inner, res) = Inner::new(io, handle);
+                    let (inner, res) = Inner::new(io, interest, handle);
 
                     unsafe {
                         *self.inner.get() = Some(inner);
@@ -392,11 +442,11 @@ unsafe impl Sync for Registration {}
 // ===== impl Inner =====
 
 impl Inner {
-    fn new(io: &impl Evented, handle: HandlePriv) -> (Self, io::Result<()>) {
+    fn new(io: &impl Evented, interest: mio::Ready, handle: HandlePriv) -> (Self, io::Result<()>) {
         let mut res = Ok(());
 
         let token = match handle.inner() {
-            Some(inner) => match inner.add_source(io) {
+            Some(inner) => match inner.add_source(io, interest) {
                 Ok(token) => token,
                 Err(e) => {
                     res = Err(e);
@@ -431,6 +481,23 @@ impl Inner {
         inner.register(lw, self.token, direction);
     }
 
+    fn register_watcher(&self, lw: &LocalWaker) -> io::Result<()> {
+        if self.token == ERROR {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "failed to associate with reactor",
+            ));
+        }
+
+        match self.handle.inner() {
+            Some(inner) => {
+                inner.register_watcher(lw, self.token);
+                Ok(())
+            }
+            None => Err(io::Error::new(io::ErrorKind::Other, "reactor gone")),
+        }
+    }
+
     fn deregister<E: Evented>(&self, io: &E) -> io::Result<()> {
         if self.token == ERROR {
             return Err(io::Error::new(
@@ -0,0 +1,362 @@
+use super::HandlePriv;
+
+use futures::task::{AtomicWaker, LocalWaker};
+use futures::{Future, Poll};
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::io;
+use std::mem;
+use std::pin::Pin;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+/// A future that completes at a specific instant in time.
+///
+/// The deadline is tracked by the reactor, which wakes the task once it has
+/// passed, so no thread is spawned per delay. The delay resolves with an error
+/// only if the reactor is gone.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// #![feature(async_await, await_macro, futures_api)]
+/// use romio::timer::Delay;
+/// use std::time::{Duration, Instant};
+///
+/// # async fn run() -> std::io::Result<()> {
+/// await!(Delay::new(Instant::now() + Duration::from_secs(1)))?;
+/// println!("one second later");
+/// # Ok(()) }
+/// ```
+pub struct Delay {
+    deadline: Instant,
+    registration: Option<(HandlePriv, Arc<Entry>)>,
+}
+
+/// The deadlines of all pending delays, driven by the reactor's poll loop.
+#[derive(Default)]
+pub(super) struct Timers {
+    queue: Mutex<Queue>,
+}
+
+/// The deadline heap, along with the number of entries in it whose delay was
+/// dropped or reset since.
+///
+/// Removing an entry from the middle of a `BinaryHeap` isn't possible, so
+/// cancelled entries are skipped when they reach the top, and the heap is
+/// rebuilt without them once they make up half of it.
+#[derive(Default)]
+struct Queue {
+    heap: BinaryHeap<Queued>,
+    cancelled: usize,
+}
+
+/// State shared between a `Delay` and the reactor.
+struct Entry {
+    fired: AtomicBool,
+    waker: AtomicWaker,
+}
+
+/// An entry in the deadline heap, ordered so that the earliest deadline is at
+/// the top.
+struct Queued {
+    deadline: Instant,
+    entry: Weak<Entry>,
+}
+
+// ===== impl Delay =====
+
+impl Delay {
+    /// Creates a new `Delay` which completes at `deadline`.
+    pub fn new(deadline: Instant) -> Delay {
+        Delay {
+            deadline,
+            registration: None,
+        }
+    }
+
+    /// Returns the instant at which this delay completes.
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Resets the delay to complete at `deadline` instead.
+    ///
+    /// This works whether or not the delay has completed already, so the same
+    /// `Delay` can be reused for heartbeats or retries.
+    pub fn reset(&mut self, deadline: Instant) {
+        self.deadline = deadline;
+        self.cancel();
+    }
+
+    /// Takes the entry out of the reactor's heap, if it is still in there.
+    fn cancel(&mut self) {
+        if let Some((handle, entry)) = self.registration.take() {
+            if let Some(inner) = handle.inner() {
+                inner.timers.cancel(entry);
+            }
+        }
+    }
+
+    fn register(&mut self, lw: &LocalWaker) -> io::Result<()> {
+        let handle = HandlePriv::try_current()?;
+        let inner = match handle.inner() {
+            Some(inner) => inner,
+            None => return Err(io::Error::new(io::ErrorKind::Other, "reactor gone")),
+        };
+
+        let entry = Arc::new(Entry {
+            fired: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+        });
+
+        // Register the waker before the reactor can see the entry, so that
+        // firing right away can't be missed.
+        entry.waker.register(lw);
+
+        // The reactor may be blocked with a timeout computed from a later
+        // deadline, in which case it has to start over.
+        if inner.timers.insert(self.deadline, &entry) {
+            handle.wakeup();
+        }
+
+        self.registration = Some((handle, entry));
+        Ok(())
+    }
+}
+
+impl Future for Delay {
+    type Output = io::Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(Ok(()));
+        }
+
+        match &self.registration {
+            Some((handle, entry)) => {
+                entry.waker.register(lw);
+
+                if entry.fired.load(SeqCst) {
+                    return Poll::Ready(Ok(()));
+                }
+
+                if handle.inner().is_none() {
+                    return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, "reactor gone")));
+                }
+            }
+            None => self.register(lw)?,
+        }
+
+        Poll::Pending
+    }
+}
+
+impl Drop for Delay {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+impl fmt::Debug for Delay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Delay")
+            .field("deadline", &self.deadline)
+            .finish()
+    }
+}
+
+// ===== impl Timers =====
+
+impl Timers {
+    /// Adds an entry to fire at `deadline`.
+    ///
+    /// Returns `true` if it is now the earliest deadline.
+    fn insert(&self, deadline: Instant, entry: &Arc<Entry>) -> bool {
+        let mut queue = self.queue.lock().unwrap();
+        queue.skip_cancelled();
+
+        let earliest = match queue.heap.peek() {
+            Some(next) => deadline < next.deadline,
+            None => true,
+        };
+
+        queue.heap.push(Queued {
+            deadline,
+            entry: Arc::downgrade(entry),
+        });
+
+        earliest
+    }
+
+    /// Forgets about `entry`, whose delay was dropped or reset.
+    fn cancel(&self, entry: Arc<Entry>) {
+        let mut queue = self.queue.lock().unwrap();
+
+        // Entries are marked as fired while the lock is held, so an entry
+        // which wasn't is still in the heap.
+        if entry.fired.load(SeqCst) {
+            return;
+        }
+        drop(entry);
+        queue.cancelled += 1;
+
+        if queue.cancelled > queue.heap.len() / 2 {
+            let heap = mem::replace(&mut queue.heap, BinaryHeap::new());
+            queue.heap = heap
+                .into_vec()
+                .into_iter()
+                .filter(|queued| queued.entry.upgrade().is_some())
+                .collect();
+            queue.cancelled = 0;
+        }
+    }
+
+    /// Returns how long the reactor may block before the next deadline.
+    pub(super) fn next_timeout(&self, now: Instant) -> Option<Duration> {
+        let mut queue = self.queue.lock().unwrap();
+        queue.skip_cancelled();
+        queue.heap.peek().map(|next| {
+            if next.deadline > now {
+                next.deadline - now
+            } else {
+                Duration::from_millis(0)
+            }
+        })
+    }
+
+    /// Wakes the tasks of all entries whose deadline has passed.
+    pub(super) fn fire(&self, now: Instant) {
+        let mut expired = vec![];
+
+        // Notify the tasks outside of the lock's critical section.
+        {
+            let mut queue = self.queue.lock().unwrap();
+
+            while queue.heap.peek().map_or(false, |next| next.deadline <= now) {
+                match queue.heap.pop().unwrap().entry.upgrade() {
+                    Some(entry) => {
+                        entry.fired.store(true, SeqCst);
+                        expired.push(entry);
+                    }
+                    // The entry is gone if its delay was dropped or reset.
+                    None => queue.cancelled = queue.cancelled.saturating_sub(1),
+                }
+            }
+        }
+
+        for entry in expired {
+            entry.waker.wake();
+        }
+    }
+
+    /// Wakes the tasks of all entries, as the reactor is going away.
+    pub(super) fn fire_all(&self) {
+        // Waking a task may drop its delay, which takes the lock as well.
+        let entries: Vec<_> = {
+            let queue = self.queue.lock().unwrap();
+            queue
+                .heap
+                .iter()
+                .filter_map(|queued| queued.entry.upgrade())
+                .collect()
+        };
+
+        for entry in entries {
+            entry.waker.wake();
+        }
+    }
+}
+
+// ===== impl Queue =====
+
+impl Queue {
+    /// Pops the entries of dropped or reset delays off the top of the heap,
+    /// so that they don't wake up the reactor.
+    fn skip_cancelled(&mut self) {
+        while self
+            .heap
+            .peek()
+            .map_or(false, |next| next.entry.upgrade().is_none())
+        {
+            self.heap.pop();
+            self.cancelled = self.cancelled.saturating_sub(1);
+        }
+    }
+}
+
+// ===== impl Queued =====
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Queued) -> bool {
+        self.deadline == other.deadline
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Queued) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Queued) -> Ordering {
+        // `BinaryHeap` is a max-heap, reverse the order to pop the earliest
+        // deadline first.
+        other.deadline.cmp(&self.deadline)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Entry, Timers};
+
+    use futures::task::AtomicWaker;
+
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    fn entry() -> Arc<Entry> {
+        Arc::new(Entry {
+            fired: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+        })
+    }
+
+    #[test]
+    fn cancelled_entries_are_removed() {
+        let timers = Timers::default();
+        let now = Instant::now();
+
+        let early = entry();
+        timers.insert(now + Duration::from_secs(1), &early);
+        let mut late: Vec<_> = (0..10)
+            .map(|_| {
+                let late = entry();
+                timers.insert(now + Duration::from_secs(10), &late);
+                late
+            })
+            .collect();
+
+        // the cancelled entry at the top doesn't decide the timeout anymore
+        timers.cancel(early);
+        assert_eq!(timers.next_timeout(now), Some(Duration::from_secs(10)));
+        assert_eq!(timers.queue.lock().unwrap().heap.len(), 10);
+
+        // cancelled entries further down are dropped once they pile up
+        let kept = late.pop().unwrap();
+        for late in late {
+            timers.cancel(late);
+        }
+        assert_eq!(timers.queue.lock().unwrap().heap.len(), 1);
+        assert_eq!(timers.next_timeout(now), Some(Duration::from_secs(10)));
+        drop(kept);
+    }
+}
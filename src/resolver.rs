@@ -0,0 +1,105 @@
+//! Async DNS resolution.
+//!
+//! Resolving a hostname with `std::net::ToSocketAddrs` blocks the calling
+//! thread until the lookup completes. The functions in this module perform the
+//! lookup on a background thread pool instead, and return a future which
+//! resolves once it is done.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! #![feature(async_await, await_macro, futures_api)]
+//! use romio::resolver;
+//! use romio::TcpStream;
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let addrs = await!(resolver::resolve("www.rust-lang.org", 443))?;
+//! let stream = await!(TcpStream::connect(&addrs[0]))?;
+//! # Ok(()) }
+//! ```
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::pin::Pin;
+use std::sync::Mutex;
+
+use futures::channel::oneshot;
+use futures::executor::ThreadPool;
+use futures::future;
+use futures::task::{LocalWaker, SpawnExt};
+use futures::{ready, Future, Poll};
+
+lazy_static::lazy_static! {
+    /// The pool running the blocking lookups, created on first use.
+    static ref POOL: Mutex<Option<ThreadPool>> = Mutex::new(None);
+}
+
+/// Resolves `host` to the socket addresses it refers to, using `port` for
+/// each of them.
+///
+/// `host` can be a hostname or the textual representation of an IP address.
+/// The addresses are returned in the order the system resolver produced them,
+/// which usually reflects its preferences, so callers can try connecting to
+/// them in turn.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// #![feature(async_await, await_macro, futures_api)]
+/// use romio::resolver;
+///
+/// # async fn run() -> std::io::Result<()> {
+/// for addr in await!(resolver::resolve("localhost", 8080))? {
+///     println!("localhost is at {}", addr);
+/// }
+/// # Ok(()) }
+/// ```
+pub fn resolve(host: &str, port: u16) -> Resolve {
+    let (tx, rx) = oneshot::channel();
+    let host = host.to_owned();
+
+    let lookup = future::lazy(move |_| {
+        let addrs = (&host[..], port).to_socket_addrs().map(Iterator::collect);
+        let _ = tx.send(addrs);
+    });
+
+    // If the lookup can't be spawned it's dropped together with the sender,
+    // which `Resolve` reports as an error.
+    if let Ok(mut pool) = pool() {
+        let _ = pool.spawn(lookup);
+    }
+
+    Resolve { rx }
+}
+
+fn pool() -> io::Result<ThreadPool> {
+    let mut pool = POOL.lock().unwrap();
+
+    if pool.is_none() {
+        *pool = Some(ThreadPool::builder().name_prefix("romio-resolver-").create()?);
+    }
+
+    Ok(pool.as_ref().unwrap().clone())
+}
+
+/// The future returned by `resolve`, which resolves to the addresses of a
+/// host.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct Resolve {
+    rx: oneshot::Receiver<io::Result<Vec<SocketAddr>>>,
+}
+
+impl Future for Resolve {
+    type Output = io::Result<Vec<SocketAddr>>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        match ready!(Pin::new(&mut self.rx).poll(lw)) {
+            Ok(addrs) => Poll::Ready(addrs),
+            Err(_) => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::Other,
+                "failed to run the lookup on the resolver thread pool",
+            ))),
+        }
+    }
+}
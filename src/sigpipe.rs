@@ -0,0 +1,134 @@
+//! Writing to stream sockets without raising `SIGPIPE` once the peer is gone,
+//! with `MSG_NOSIGNAL` where it is available and `SO_NOSIGPIPE` on macOS.
+//! System calls which take no flags, like `sendfile` and `splice`, run with
+//! the signal blocked instead.
+//!
+//! Either way the write fails with `ErrorKind::BrokenPipe` instead of killing
+//! a process which doesn't ignore the signal.
+
+use std::io;
+use std::mem;
+use std::os::unix::io::AsRawFd;
+
+use iovec::IoVec;
+
+/// The flags every write is sent with.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+const FLAGS: libc::c_int = libc::MSG_NOSIGNAL;
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd"
+)))]
+const FLAGS: libc::c_int = 0;
+
+/// Writes `buf` to `socket`.
+pub(crate) fn send(socket: &impl AsRawFd, buf: &[u8]) -> io::Result<usize> {
+    send_with_flags(socket, buf, 0)
+}
+
+/// Writes `buf` to `socket`, passing `flags` to `send`.
+pub(crate) fn send_with_flags(
+    socket: &impl AsRawFd,
+    buf: &[u8],
+    flags: libc::c_int,
+) -> io::Result<usize> {
+    let r = unsafe {
+        libc::send(
+            socket.as_raw_fd(),
+            buf.as_ptr() as *const libc::c_void,
+            buf.len(),
+            flags | FLAGS,
+        )
+    };
+    if r < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(r as usize)
+}
+
+/// Writes `bufs` to `socket`, like `writev`.
+pub(crate) fn send_bufs(socket: &impl AsRawFd, bufs: &[&IoVec]) -> io::Result<usize> {
+    let iovecs = iovec::unix::as_os_slice(bufs);
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = iovecs.as_ptr() as *mut libc::iovec;
+    msg.msg_iovlen = iovecs.len() as _;
+
+    let r = unsafe { libc::sendmsg(socket.as_raw_fd(), &msg, FLAGS) };
+    if r < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(r as usize)
+}
+
+/// Sets `SO_NOSIGPIPE` on `socket`, for platforms without `MSG_NOSIGNAL`.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub(crate) fn set_nosigpipe(socket: &impl AsRawFd) -> io::Result<()> {
+    crate::sockopt::setsockopt(socket, libc::SOL_SOCKET, libc::SO_NOSIGPIPE, 1)
+}
+
+/// Does nothing, every write is sent with `MSG_NOSIGNAL` instead.
+#[cfg(not(any(target_os = "macos", target_os = "ios")))]
+pub(crate) fn set_nosigpipe(_: &impl AsRawFd) -> io::Result<()> {
+    Ok(())
+}
+
+/// Runs `f`, a write which can't be passed `MSG_NOSIGNAL`, with `SIGPIPE`
+/// blocked for the current thread.
+///
+/// If `f` fails with `EPIPE`, the signal it raised is still pending once `f`
+/// returns, and is consumed before the signal mask is restored. A `SIGPIPE`
+/// which was pending before is left alone.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "dragonfly"
+))]
+pub(crate) fn without_sigpipe<T>(f: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+    use std::ptr;
+
+    unsafe {
+        let mut pipe: libc::sigset_t = mem::zeroed();
+        libc::sigemptyset(&mut pipe);
+        libc::sigaddset(&mut pipe, libc::SIGPIPE);
+
+        let mut pending: libc::sigset_t = mem::zeroed();
+        libc::sigpending(&mut pending);
+        let was_pending = libc::sigismember(&pending, libc::SIGPIPE) == 1;
+
+        let mut old: libc::sigset_t = mem::zeroed();
+        libc::pthread_sigmask(libc::SIG_BLOCK, &pipe, &mut old);
+
+        let res = f();
+
+        let broken = match &res {
+            Err(e) => e.raw_os_error() == Some(libc::EPIPE),
+            Ok(_) => false,
+        };
+        if broken && !was_pending {
+            let poll = libc::timespec {
+                tv_sec: 0,
+                tv_nsec: 0,
+            };
+            while libc::sigtimedwait(&pipe, ptr::null_mut(), &poll) < 0 {
+                if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+                    break;
+                }
+            }
+        }
+
+        libc::pthread_sigmask(libc::SIG_SETMASK, &old, ptr::null_mut());
+        res
+    }
+}
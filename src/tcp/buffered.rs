@@ -0,0 +1,302 @@
+use std::cmp;
+use std::fmt;
+use std::io;
+use std::mem;
+use std::pin::Pin;
+
+use futures::io::{AsyncRead, AsyncWrite, Initializer};
+use futures::task::LocalWaker;
+use futures::{ready, Future, Poll};
+use iovec::IoVec;
+
+use super::TcpStream;
+
+/// A `TcpStream` which reads from the socket in large chunks, serving small
+/// reads from an internal buffer.
+///
+/// Every read from a plain `TcpStream` is a system call, which dominates the
+/// cost of many small reads, like parsing a protocol a few bytes at a time.
+/// A `BufTcpStream` reads as much as fits into its buffer at once instead.
+/// Reads at least as large as the buffer bypass it when it is empty.
+///
+/// Writes go to the socket directly and aren't buffered, so like for a plain
+/// `TcpStream`, flushing completes right away.
+///
+/// Data that was read into the buffer has been taken from the socket's
+/// receive queue. Peeking at the socket with `MSG_PEEK` doesn't see it, and
+/// neither do other handles to the socket, like those created with
+/// `TcpStream::try_clone`. Use [`buffer`] to inspect it instead.
+///
+/// This is created by the `TcpStream::with_capacity` method.
+///
+/// Text protocols can be read a line at a time with [`read_line`].
+///
+/// [`buffer`]: #method.buffer
+/// [`read_line`]: #method.read_line
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// #![feature(async_await, await_macro, futures_api)]
+/// use futures::prelude::*;
+/// use romio::tcp::TcpStream;
+///
+/// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+/// let addr = "127.0.0.1:8080".parse()?;
+/// let stream = await!(TcpStream::connect(&addr))?;
+/// let mut stream = stream.with_capacity(16 * 1024);
+///
+/// let mut len = [0; 2];
+/// await!(stream.read_exact(&mut len))?;
+/// # Ok(())}
+/// ```
+pub struct BufTcpStream {
+    inner: TcpStream,
+    buf: Box<[u8]>,
+    pos: usize,
+    cap: usize,
+    max_line_length: Option<usize>,
+}
+
+impl BufTcpStream {
+    pub(super) fn new(inner: TcpStream, capacity: usize) -> BufTcpStream {
+        assert!(capacity > 0, "read buffer capacity must be non-zero");
+
+        BufTcpStream {
+            inner,
+            buf: vec![0; capacity].into_boxed_slice(),
+            pos: 0,
+            cap: 0,
+            max_line_length: None,
+        }
+    }
+
+    /// Returns a reference to the underlying stream.
+    pub fn get_ref(&self) -> &TcpStream {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying stream.
+    ///
+    /// Reading from the underlying stream directly skips the data held in the
+    /// buffer.
+    pub fn get_mut(&mut self) -> &mut TcpStream {
+        &mut self.inner
+    }
+
+    /// Returns the data read from the socket which hasn't been consumed yet.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..self.cap]
+    }
+
+    /// Returns the capacity of the internal buffer.
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Reads a line into `line`, appending to it.
+    ///
+    /// Lines are terminated by `\n` or `\r\n`, which isn't appended. The last
+    /// line before the end of the stream doesn't need to be terminated. Lines
+    /// are reassembled from as many reads as it takes, so they may be longer
+    /// than the buffer, up to the limit set with [`set_max_line_length`].
+    ///
+    /// The returned future resolves to the number of bytes consumed from the
+    /// stream, including the terminator, so `0` means that the end of the
+    /// stream was reached. A line which isn't valid UTF-8, or is longer than
+    /// the maximum length, fails with an error of kind `InvalidData`.
+    ///
+    /// Dropping the future before it completes loses the part of the line
+    /// read so far.
+    ///
+    /// [`set_max_line_length`]: #method.set_max_line_length
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:25".parse()?;
+    /// let stream = await!(TcpStream::connect(&addr))?;
+    /// let mut stream = stream.with_capacity(4 * 1024);
+    /// stream.set_max_line_length(Some(1000));
+    ///
+    /// let mut greeting = String::new();
+    /// await!(stream.read_line(&mut greeting))?;
+    /// assert!(greeting.starts_with("220 "));
+    /// # Ok(())}
+    /// ```
+    pub fn read_line<'a, 'b>(&'a mut self, line: &'b mut String) -> ReadLine<'a, 'b> {
+        ReadLine {
+            stream: self,
+            line,
+            bytes: Vec::new(),
+            read: 0,
+        }
+    }
+
+    /// Returns the maximum length of a line read with [`read_line`], if any.
+    ///
+    /// [`read_line`]: #method.read_line
+    pub fn max_line_length(&self) -> Option<usize> {
+        self.max_line_length
+    }
+
+    /// Sets the maximum length of a line read with [`read_line`], not
+    /// counting the terminator.
+    ///
+    /// By default there is no limit, so a peer which never sends a newline
+    /// can make `read_line` buffer an unbounded amount of data. Reading a
+    /// longer line fails with an error of kind `InvalidData`, as soon as the
+    /// limit is exceeded.
+    ///
+    /// [`read_line`]: #method.read_line
+    pub fn set_max_line_length(&mut self, max_line_length: Option<usize>) {
+        self.max_line_length = max_line_length;
+    }
+
+    /// Consumes the `BufTcpStream`, returning the underlying stream.
+    ///
+    /// Any data left in the buffer is lost, check [`buffer`] first.
+    ///
+    /// [`buffer`]: #method.buffer
+    pub fn into_inner(self) -> TcpStream {
+        self.inner
+    }
+
+    /// Refills the buffer if all of it was consumed, returning the data in it.
+    fn poll_fill_buf(&mut self, lw: &LocalWaker) -> Poll<io::Result<&[u8]>> {
+        if self.pos >= self.cap {
+            self.cap = ready!(self.inner.poll_read(lw, &mut self.buf))?;
+            self.pos = 0;
+        }
+        Poll::Ready(Ok(&self.buf[self.pos..self.cap]))
+    }
+}
+
+impl AsyncRead for BufTcpStream {
+    unsafe fn initializer(&self) -> Initializer {
+        // Reads are copied out of the buffer or go to the socket directly.
+        self.inner.initializer()
+    }
+
+    fn poll_read(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        // Copying through the buffer gains nothing if it would be emptied by
+        // this read anyway.
+        if self.pos >= self.cap && buf.len() >= self.buf.len() {
+            return self.inner.poll_read(lw, buf);
+        }
+
+        let n = {
+            let available = ready!(self.poll_fill_buf(lw))?;
+            let n = cmp::min(available.len(), buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
+        self.pos += n;
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl AsyncWrite for BufTcpStream {
+    fn poll_write(&mut self, lw: &LocalWaker, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.inner.poll_write(lw, buf)
+    }
+
+    fn poll_vectored_write(&mut self, lw: &LocalWaker, vec: &[&IoVec]) -> Poll<io::Result<usize>> {
+        self.inner.poll_vectored_write(lw, vec)
+    }
+
+    fn poll_flush(&mut self, lw: &LocalWaker) -> Poll<io::Result<()>> {
+        self.inner.poll_flush(lw)
+    }
+
+    fn poll_close(&mut self, lw: &LocalWaker) -> Poll<io::Result<()>> {
+        self.inner.poll_close(lw)
+    }
+}
+
+/// The future returned by `BufTcpStream::read_line`.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct ReadLine<'a, 'b> {
+    stream: &'a mut BufTcpStream,
+    line: &'b mut String,
+    /// The part of the line read so far.
+    bytes: Vec<u8>,
+    /// The number of bytes consumed from the stream so far.
+    read: usize,
+}
+
+impl<'a, 'b> Future for ReadLine<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<usize>> {
+        let ReadLine {
+            stream,
+            line,
+            bytes,
+            read,
+        } = &mut *self;
+
+        loop {
+            let (done, used) = {
+                let available = ready!(stream.poll_fill_buf(lw))?;
+                match available.iter().position(|&b| b == b'\n') {
+                    Some(i) => {
+                        bytes.extend_from_slice(&available[..i]);
+                        (true, i + 1)
+                    }
+                    // An empty buffer means the end of the stream.
+                    None => {
+                        bytes.extend_from_slice(available);
+                        (available.is_empty(), available.len())
+                    }
+                }
+            };
+            stream.pos += used;
+            *read += used;
+
+            if done && bytes.last() == Some(&b'\r') {
+                bytes.pop();
+            }
+
+            if let Some(max) = stream.max_line_length {
+                // Until the line is complete, the last byte may be the `\r`
+                // of its terminator.
+                let len = if done {
+                    bytes.len()
+                } else {
+                    bytes.len().saturating_sub(1)
+                };
+                if len > max {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "line exceeds the maximum length",
+                    )));
+                }
+            }
+
+            if done {
+                let bytes = mem::replace(bytes, Vec::new());
+                let s = String::from_utf8(bytes).map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "line is not valid UTF-8")
+                })?;
+                line.push_str(&s);
+                return Poll::Ready(Ok(mem::replace(read, 0)));
+            }
+        }
+    }
+}
+
+impl fmt::Debug for BufTcpStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufTcpStream")
+            .field("inner", &self.inner)
+            .field("buffered", &(self.cap - self.pos))
+            .field("capacity", &self.buf.len())
+            .finish()
+    }
+}
@@ -0,0 +1,372 @@
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use mio;
+use net2;
+
+use super::{ConnectFuture, TcpListener, TcpStream};
+use crate::io::{AddrError, Operation};
+
+/// A builder for configuring a [`TcpListener`] before it is bound.
+///
+/// Some socket options only take effect if they are set before the socket is
+/// bound to an address. `TcpListenerBuilder` collects those options and
+/// applies them when [`bind`] is called.
+///
+/// [`TcpListener`]: struct.TcpListener.html
+/// [`bind`]: #method.bind
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use romio::tcp::TcpListenerBuilder;
+///
+/// # fn main () -> Result<(), Box<dyn std::error::Error + 'static>> {
+/// let socket_addr = "[::]:8080".parse()?;
+/// let listener = TcpListenerBuilder::new()
+///     .only_v6(false)
+///     .bind(&socket_addr)?;
+/// # Ok(())}
+/// ```
+#[derive(Debug, Clone)]
+pub struct TcpListenerBuilder {
+    only_v6: Option<bool>,
+    defer_accept: Option<Duration>,
+    #[cfg(unix)]
+    fd_reserve: bool,
+    accept_backoff: Option<Duration>,
+    #[cfg(unix)]
+    reuse_port: Option<bool>,
+    backlog: i32,
+}
+
+impl TcpListenerBuilder {
+    /// Creates a new builder with the default configuration.
+    ///
+    /// A listener bound from the default configuration behaves exactly like one
+    /// created by [`TcpListener::bind`].
+    ///
+    /// [`TcpListener::bind`]: struct.TcpListener.html#method.bind
+    pub fn new() -> TcpListenerBuilder {
+        TcpListenerBuilder {
+            only_v6: None,
+            defer_accept: None,
+            #[cfg(unix)]
+            fd_reserve: false,
+            accept_backoff: None,
+            #[cfg(unix)]
+            reuse_port: None,
+            backlog: 1024,
+        }
+    }
+
+    /// Sets the value of the `IPV6_V6ONLY` option on the socket.
+    ///
+    /// If this is set to `true` then a listener bound to an IPv6 address only
+    /// accepts IPv6 connections. If it is set to `false` then a listener bound
+    /// to the IPv6 wildcard address `[::]` also accepts IPv4 connections, whose
+    /// peer addresses are reported as IPv4-mapped IPv6 addresses.
+    ///
+    /// When this option is not set the operating system default is used, which
+    /// differs between platforms. It is ignored when binding to an IPv4
+    /// address.
+    pub fn only_v6(&mut self, only_v6: bool) -> &mut TcpListenerBuilder {
+        self.only_v6 = Some(only_v6);
+        self
+    }
+
+    /// Sets the value of the `TCP_DEFER_ACCEPT` option on the socket.
+    ///
+    /// The option is set before the socket starts listening, so no connection
+    /// is reported before the client sent some data or the timeout elapsed.
+    /// See [`TcpListener::set_defer_accept`] for details.
+    ///
+    /// [`TcpListener::set_defer_accept`]: struct.TcpListener.html#method.set_defer_accept
+    pub fn defer_accept(&mut self, timeout: Duration) -> &mut TcpListenerBuilder {
+        self.defer_accept = Some(timeout);
+        self
+    }
+
+    /// Sets whether the listener keeps a file descriptor in reserve, for when
+    /// the process runs out of them.
+    ///
+    /// The reserve is opened when the listener is bound. See
+    /// [`TcpListener::set_fd_reserve`] for details.
+    ///
+    /// [`TcpListener::set_fd_reserve`]: struct.TcpListener.html#method.set_fd_reserve
+    #[cfg(unix)]
+    pub fn fd_reserve(&mut self, enabled: bool) -> &mut TcpListenerBuilder {
+        self.fd_reserve = enabled;
+        self
+    }
+
+    /// Sets how long accepting backs off after the process ran out of file
+    /// descriptors.
+    ///
+    /// See [`TcpListener::set_accept_backoff`] for details.
+    ///
+    /// [`TcpListener::set_accept_backoff`]: struct.TcpListener.html#method.set_accept_backoff
+    pub fn accept_backoff(&mut self, backoff: Duration) -> &mut TcpListenerBuilder {
+        self.accept_backoff = Some(backoff);
+        self
+    }
+
+    /// Sets the value of the `SO_REUSEPORT` option on the socket.
+    ///
+    /// Several listeners which all set this option can be bound to the same
+    /// address. On Linux the incoming connections are distributed between
+    /// them, see [`TcpListener::bind_reuseport_group`].
+    ///
+    /// [`TcpListener::bind_reuseport_group`]: struct.TcpListener.html#method.bind_reuseport_group
+    #[cfg(unix)]
+    pub fn reuse_port(&mut self, reuse: bool) -> &mut TcpListenerBuilder {
+        self.reuse_port = Some(reuse);
+        self
+    }
+
+    /// Creates a new `TcpListener` bound to the specified address, with the
+    /// options of this builder applied before binding.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::tcp::TcpListenerBuilder;
+    ///
+    /// # fn main () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let socket_addr = "[::1]:0".parse()?;
+    /// let listener = TcpListenerBuilder::new()
+    ///     .only_v6(true)
+    ///     .bind(&socket_addr)?;
+    ///
+    /// assert_eq!(listener.only_v6()?, true);
+    /// # Ok(())}
+    /// ```
+    pub fn bind(&self, addr: &SocketAddr) -> Result<TcpListener, AddrError> {
+        self.build(addr)
+            .map_err(|e| AddrError::new(Operation::Bind, addr, e))
+    }
+
+    fn build(&self, addr: &SocketAddr) -> io::Result<TcpListener> {
+        let builder = match *addr {
+            SocketAddr::V4(..) => net2::TcpBuilder::new_v4()?,
+            SocketAddr::V6(..) => net2::TcpBuilder::new_v6()?,
+        };
+
+        // Set SO_REUSEADDR, mirroring `mio::net::TcpListener::bind`.
+        if cfg!(unix) {
+            builder.reuse_address(true)?;
+        }
+
+        if let (SocketAddr::V6(..), Some(only_v6)) = (addr, self.only_v6) {
+            builder.only_v6(only_v6)?;
+        }
+
+        #[cfg(unix)]
+        {
+            use net2::unix::UnixTcpBuilderExt;
+
+            if let Some(reuse) = self.reuse_port {
+                builder.reuse_port(reuse)?;
+            }
+        }
+
+        builder.bind(addr)?;
+
+        if let Some(timeout) = self.defer_accept {
+            super::listener::set_defer_accept(&builder, Some(timeout))?;
+        }
+
+        let listener = builder.listen(self.backlog)?;
+        let listener = mio::net::TcpListener::from_std(listener)?;
+
+        let listener = TcpListener::new(listener);
+
+        #[cfg(unix)]
+        {
+            if self.fd_reserve {
+                listener.set_fd_reserve(true)?;
+            }
+        }
+
+        if let Some(backoff) = self.accept_backoff {
+            listener.set_accept_backoff(backoff);
+        }
+
+        Ok(listener)
+    }
+}
+
+impl Default for TcpListenerBuilder {
+    fn default() -> TcpListenerBuilder {
+        TcpListenerBuilder::new()
+    }
+}
+
+/// A builder for configuring a [`TcpStream`] before it connects.
+///
+/// Options set on a connected stream only take effect for the packets sent
+/// afterwards. `TcpStreamBuilder` sets them on the socket before the connect is
+/// issued, so that they also apply to the handshake.
+///
+/// [`TcpStream`]: struct.TcpStream.html
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// #![feature(async_await, await_macro, futures_api)]
+/// use romio::tcp::TcpStreamBuilder;
+///
+/// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+/// let addr = "127.0.0.1:8080".parse()?;
+/// let stream = await!(TcpStreamBuilder::new().ttl(1).connect(&addr))?;
+///
+/// assert_eq!(stream.ttl()?, 1);
+/// # Ok(())}
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TcpStreamBuilder {
+    local_addr: Option<SocketAddr>,
+    ttl: Option<u32>,
+    #[cfg(unix)]
+    mss: Option<u32>,
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    device: Option<String>,
+    #[cfg(target_os = "linux")]
+    mark: Option<u32>,
+}
+
+impl TcpStreamBuilder {
+    /// Creates a new builder with the default configuration.
+    ///
+    /// A stream connected from the default configuration behaves exactly like
+    /// one created by [`TcpStream::connect`].
+    ///
+    /// [`TcpStream::connect`]: struct.TcpStream.html#method.connect
+    pub fn new() -> TcpStreamBuilder {
+        TcpStreamBuilder::default()
+    }
+
+    /// Binds the socket to `addr` before connecting, so that the connection
+    /// originates from it.
+    ///
+    /// This picks the source address on hosts with several of them. A port of
+    /// `0` lets the OS pick an ephemeral port on that address. If binding
+    /// fails, the returned future resolves to an error naming the local
+    /// address, see [`TcpStream::connect_bound`].
+    ///
+    /// [`TcpStream::connect_bound`]: struct.TcpStream.html#method.connect_bound
+    pub fn local_addr(&mut self, addr: SocketAddr) -> &mut TcpStreamBuilder {
+        self.local_addr = Some(addr);
+        self
+    }
+
+    /// Sets the value of the `IP_TTL` option on the socket.
+    ///
+    /// This value sets the time-to-live field that is used in every packet
+    /// sent from this socket, starting with the SYN of the handshake.
+    pub fn ttl(&mut self, ttl: u32) -> &mut TcpStreamBuilder {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the value of the `TCP_MAXSEG` option on the socket.
+    ///
+    /// This clamps the maximum segment size announced to the peer in the
+    /// handshake, so neither side sends segments larger than `mss` bytes. The
+    /// size in use once connected is returned by [`TcpStream::mss`].
+    ///
+    /// [`TcpStream::mss`]: struct.TcpStream.html#method.mss
+    #[cfg(unix)]
+    pub fn mss(&mut self, mss: u32) -> &mut TcpStreamBuilder {
+        self.mss = Some(mss);
+        self
+    }
+
+    /// Binds the socket to the network interface named `device`.
+    ///
+    /// The connection is made through that interface regardless of the
+    /// routing table, see [`TcpStream::set_device`]. If the binding fails,
+    /// for example for a lack of permissions, the returned future resolves
+    /// to the error.
+    ///
+    /// [`TcpStream::set_device`]: struct.TcpStream.html#method.set_device
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn device(&mut self, device: &str) -> &mut TcpStreamBuilder {
+        self.device = Some(device.to_owned());
+        self
+    }
+
+    /// Sets the value of the `SO_MARK` option on the socket.
+    ///
+    /// Every packet of the connection carries the mark, starting with the SYN,
+    /// see [`TcpStream::set_mark`]. Without the `CAP_NET_ADMIN` capability the
+    /// returned future resolves to a `PermissionDenied` error.
+    ///
+    /// [`TcpStream::set_mark`]: struct.TcpStream.html#method.set_mark
+    #[cfg(target_os = "linux")]
+    pub fn mark(&mut self, mark: u32) -> &mut TcpStreamBuilder {
+        self.mark = Some(mark);
+        self
+    }
+
+    /// Opens a TCP connection to a remote host, with the options of this
+    /// builder applied before connecting.
+    ///
+    /// The returned future resolves to the connected stream, like the one
+    /// returned by [`TcpStream::connect`].
+    ///
+    /// [`TcpStream::connect`]: struct.TcpStream.html#method.connect
+    pub fn connect(&self, addr: &SocketAddr) -> ConnectFuture {
+        let connect_error = |e| AddrError::new(Operation::Connect, addr, e);
+        let socket = self
+            .socket(addr)
+            .map_err(connect_error)
+            .and_then(|builder| {
+                if let Some(local) = &self.local_addr {
+                    super::stream::bind_local(&builder, local)?;
+                }
+                builder.to_tcp_stream().map_err(connect_error)
+            });
+
+        match socket {
+            Ok(stream) => TcpStream::connect_from_std(stream, addr),
+            Err(e) => ConnectFuture::error(e),
+        }
+    }
+
+    /// Creates the socket with the options of this builder applied.
+    fn socket(&self, addr: &SocketAddr) -> io::Result<net2::TcpBuilder> {
+        let builder = match *addr {
+            SocketAddr::V4(..) => net2::TcpBuilder::new_v4()?,
+            SocketAddr::V6(..) => net2::TcpBuilder::new_v6()?,
+        };
+
+        if let Some(ttl) = self.ttl {
+            builder.ttl(ttl)?;
+        }
+
+        #[cfg(unix)]
+        {
+            if let Some(mss) = self.mss {
+                super::stream::set_mss(&builder, mss)?;
+            }
+        }
+
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        {
+            if let Some(device) = &self.device {
+                crate::device::set_device(&builder, Some(device.as_str()))?;
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(mark) = self.mark {
+                crate::mark::set_mark(&builder, mark)?;
+            }
+        }
+
+        Ok(builder)
+    }
+}
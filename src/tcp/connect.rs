@@ -0,0 +1,253 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+use futures::task::LocalWaker;
+use futures::{ready, Future, Poll};
+
+use super::stream::ConnectFuture;
+use super::TcpStream;
+use crate::reactor::Delay;
+use crate::resolver::{self, Resolve};
+
+/// The default delay between starting connection attempts, as recommended by
+/// RFC 8305.
+const DEFAULT_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// The future returned by `TcpStream::connect_multi`, which resolves to the
+/// first connection established to any of the addresses.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct ConnectMulti {
+    addrs: VecDeque<SocketAddr>,
+    attempts: Vec<(SocketAddr, ConnectFuture)>,
+    next_attempt: Option<Delay>,
+    attempt_delay: Duration,
+    errors: Vec<(SocketAddr, io::Error)>,
+}
+
+/// The future returned by `TcpStream::connect_happy_eyeballs`, which resolves
+/// a host and then connects to the first of its addresses which accepts the
+/// connection.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct ConnectHappyEyeballs {
+    state: HappyEyeballsState,
+    attempt_delay: Duration,
+}
+
+/// A connect future which resolves to the stream along with the address it
+/// connected to.
+///
+/// This is created by the `with_addr` methods of [`ConnectFuture`],
+/// [`ConnectMulti`] and [`ConnectHappyEyeballs`].
+///
+/// [`ConnectFuture`]: struct.ConnectFuture.html#method.with_addr
+/// [`ConnectMulti`]: struct.ConnectMulti.html#method.with_addr
+/// [`ConnectHappyEyeballs`]: struct.ConnectHappyEyeballs.html#method.with_addr
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct ConnectWithAddr<F> {
+    inner: F,
+}
+
+#[derive(Debug)]
+enum HappyEyeballsState {
+    Resolving(Resolve),
+    Connecting(ConnectMulti),
+}
+
+impl ConnectMulti {
+    pub(super) fn new(addrs: &[SocketAddr]) -> ConnectMulti {
+        ConnectMulti {
+            addrs: addrs.iter().cloned().collect(),
+            attempts: Vec::new(),
+            next_attempt: None,
+            attempt_delay: DEFAULT_ATTEMPT_DELAY,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Sets how long to wait for a connection attempt before starting the next
+    /// one in parallel.
+    ///
+    /// Defaults to 250 milliseconds.
+    pub fn attempt_delay(mut self, delay: Duration) -> ConnectMulti {
+        self.attempt_delay = delay;
+        self
+    }
+
+    /// Resolves to the stream along with the address of the attempt which
+    /// succeeded.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// # use std::io;
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn connect_any() -> io::Result<TcpStream> {
+    /// let addrs = ["10.0.0.1:80".parse().unwrap(), "10.0.0.2:80".parse().unwrap()];
+    /// let (stream, addr) = await!(TcpStream::connect_multi(&addrs).with_addr())?;
+    /// println!("connected to {}", addr);
+    /// # Ok(stream)
+    /// # }
+    /// ```
+    pub fn with_addr(self) -> ConnectWithAddr<ConnectMulti> {
+        ConnectWithAddr::new(self)
+    }
+
+    /// Builds the error returned once every attempt failed.
+    fn error(&mut self) -> io::Error {
+        if self.errors.is_empty() {
+            return io::Error::new(io::ErrorKind::InvalidInput, "no addresses to connect to");
+        }
+
+        if self.errors.len() == 1 {
+            return self.errors.pop().unwrap().1;
+        }
+
+        // The errors name the addresses they are about.
+        let mut msg = String::from("failed to connect to any address:");
+        for (_, e) in &self.errors {
+            let _ = write!(msg, " {};", e);
+        }
+        msg.pop();
+
+        io::Error::new(io::ErrorKind::Other, msg)
+    }
+}
+
+impl Future for ConnectMulti {
+    type Output = io::Result<TcpStream>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<TcpStream>> {
+        let this = &mut *self;
+
+        loop {
+            // Start the next attempt once the previous one had some time to
+            // complete, or right away if there is none left in flight.
+            let start_next = match &mut this.next_attempt {
+                _ if this.attempts.is_empty() => true,
+                Some(delay) => Pin::new(delay).poll(lw)?.is_ready(),
+                None => false,
+            };
+
+            if start_next {
+                this.next_attempt = None;
+
+                match this.addrs.pop_front() {
+                    Some(addr) => {
+                        this.attempts.push((addr, TcpStream::connect(&addr)));
+                        this.next_attempt = Some(Delay::new(Instant::now() + this.attempt_delay));
+                    }
+                    None if this.attempts.is_empty() => return Poll::Ready(Err(this.error())),
+                    None => {}
+                }
+            }
+
+            // The first established connection wins, the attempts still in
+            // flight are aborted when this future is dropped.
+            let mut i = 0;
+            while i < this.attempts.len() {
+                match Pin::new(&mut this.attempts[i].1).poll(lw) {
+                    Poll::Ready(Ok(stream)) => return Poll::Ready(Ok(stream)),
+                    Poll::Ready(Err(e)) => {
+                        let (addr, _) = this.attempts.remove(i);
+                        this.errors.push((addr, io::Error::from(e)));
+                    }
+                    Poll::Pending => i += 1,
+                }
+            }
+
+            // Loop again to start the next attempt if all of them failed, or
+            // if the delay has to be polled for the first time.
+            if !this.attempts.is_empty() && !start_next {
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+impl ConnectHappyEyeballs {
+    pub(super) fn new(host: &str, port: u16) -> ConnectHappyEyeballs {
+        ConnectHappyEyeballs {
+            state: HappyEyeballsState::Resolving(resolver::resolve(host, port)),
+            attempt_delay: DEFAULT_ATTEMPT_DELAY,
+        }
+    }
+
+    /// Sets how long to wait for a connection attempt before starting the next
+    /// one in parallel.
+    ///
+    /// Defaults to 250 milliseconds.
+    pub fn attempt_delay(mut self, delay: Duration) -> ConnectHappyEyeballs {
+        self.attempt_delay = delay;
+        self
+    }
+
+    /// Resolves to the stream along with the address of the attempt which
+    /// succeeded, out of those the host resolved to.
+    pub fn with_addr(self) -> ConnectWithAddr<ConnectHappyEyeballs> {
+        ConnectWithAddr::new(self)
+    }
+}
+
+impl Future for ConnectHappyEyeballs {
+    type Output = io::Result<TcpStream>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<TcpStream>> {
+        let this = &mut *self;
+
+        loop {
+            match &mut this.state {
+                HappyEyeballsState::Resolving(resolve) => {
+                    let addrs = interleave(ready!(Pin::new(resolve).poll(lw))?);
+                    let connect = ConnectMulti::new(&addrs).attempt_delay(this.attempt_delay);
+                    this.state = HappyEyeballsState::Connecting(connect);
+                }
+                HappyEyeballsState::Connecting(connect) => return Pin::new(connect).poll(lw),
+            }
+        }
+    }
+}
+
+impl<F> ConnectWithAddr<F> {
+    pub(super) fn new(inner: F) -> ConnectWithAddr<F> {
+        ConnectWithAddr { inner }
+    }
+}
+
+impl<F, E> Future for ConnectWithAddr<F>
+where
+    F: Future<Output = Result<TcpStream, E>> + Unpin,
+{
+    type Output = Result<(TcpStream, SocketAddr), E>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Result<(TcpStream, SocketAddr), E>> {
+        let stream = ready!(Pin::new(&mut self.inner).poll(lw))?;
+        // The stream remembers the address it was connecting to, so this
+        // doesn't ask the socket.
+        let addr = stream.connect_addr();
+        Poll::Ready(Ok((stream, addr)))
+    }
+}
+
+/// Orders `addrs` alternating between IPv6 and IPv4, starting with IPv6, as
+/// RFC 8305 recommends. The order of the addresses of each family is kept.
+fn interleave(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let (mut v6, mut v4): (VecDeque<_>, VecDeque<_>) =
+        addrs.into_iter().partition(SocketAddr::is_ipv6);
+
+    let mut ordered = Vec::with_capacity(v6.len() + v4.len());
+    loop {
+        match (v6.pop_front(), v4.pop_front()) {
+            (None, None) => return ordered,
+            (a, b) => ordered.extend(a.into_iter().chain(b)),
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::io;
+use std::mem;
+use std::net::SocketAddr;
+use std::pin::Pin;
+
+use futures::io::AsyncWrite;
+use futures::task::LocalWaker;
+use futures::{ready, Future, Poll};
+
+use super::stream::ConnectFuture;
+use super::TcpStream;
+
+/// The future returned by `TcpStream::connect_fastopen`, which resolves to the
+/// connected stream and the number of bytes sent in the SYN once all of the
+/// initial data has been written.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct ConnectFastOpen<'a> {
+    state: State,
+    data: &'a [u8],
+    in_syn: usize,
+    written: usize,
+}
+
+#[derive(Debug)]
+enum State {
+    Connecting(ConnectFuture),
+    Writing(TcpStream),
+    Error(io::Error),
+    Empty,
+}
+
+impl<'a> ConnectFastOpen<'a> {
+    pub(super) fn new(addr: &SocketAddr, data: &'a [u8]) -> ConnectFastOpen<'a> {
+        let (state, in_syn) = match connect(addr, data) {
+            Ok((connect, n)) => (State::Connecting(connect), n),
+            Err(e) => (State::Error(e), 0),
+        };
+
+        ConnectFastOpen {
+            state,
+            data,
+            in_syn,
+            written: in_syn,
+        }
+    }
+}
+
+fn connect(addr: &SocketAddr, data: &[u8]) -> io::Result<(ConnectFuture, usize)> {
+    let builder = match addr {
+        SocketAddr::V4(..) => net2::TcpBuilder::new_v4()?,
+        SocketAddr::V6(..) => net2::TcpBuilder::new_v6()?,
+    };
+    let socket = builder.to_tcp_stream()?;
+    socket.set_nonblocking(true)?;
+
+    match sys::connect(&socket, addr, data)? {
+        Some(n) => Ok((TcpStream::connecting(socket, addr), n)),
+        None => Ok((TcpStream::connect(addr), 0)),
+    }
+}
+
+impl<'a> Future for ConnectFastOpen<'a> {
+    type Output = io::Result<(TcpStream, usize)>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<(TcpStream, usize)>> {
+        let this = &mut *self;
+
+        loop {
+            match &mut this.state {
+                State::Connecting(connect) => {
+                    let stream = ready!(Pin::new(connect).poll(lw))?;
+                    this.state = State::Writing(stream);
+                }
+                State::Writing(stream) => {
+                    // Whatever didn't fit in the SYN is written once the
+                    // connection is established.
+                    while this.written < this.data.len() {
+                        let n = ready!(stream.poll_write(lw, &this.data[this.written..]))?;
+                        if n == 0 {
+                            return Poll::Ready(Err(io::Error::new(
+                                io::ErrorKind::WriteZero,
+                                "write zero byte into writer",
+                            )));
+                        }
+                        this.written += n;
+                    }
+
+                    match mem::replace(&mut this.state, State::Empty) {
+                        State::Writing(stream) => return Poll::Ready(Ok((stream, this.in_syn))),
+                        _ => unreachable!(),
+                    }
+                }
+                State::Error(_) => match mem::replace(&mut this.state, State::Empty) {
+                    State::Error(e) => return Poll::Ready(Err(e)),
+                    _ => unreachable!(),
+                },
+                State::Empty => panic!("can't poll TCP stream twice"),
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::io;
+    use std::net::{self, SocketAddr};
+    use std::os::unix::prelude::*;
+
+    /// Issues a nonblocking connect to `addr` on `socket`, sending as much of
+    /// `data` in the SYN as the kernel allows.
+    ///
+    /// Returns the number of bytes sent, or `None` if Fast Open isn't available
+    /// and no connect has been issued.
+    pub(super) fn connect(
+        socket: &net::TcpStream,
+        addr: &SocketAddr,
+        data: &[u8],
+    ) -> io::Result<Option<usize>> {
+        let (addr, len) = crate::sockaddr::from_socket_addr(addr);
+        let n = unsafe {
+            libc::sendto(
+                socket.as_raw_fd(),
+                data.as_ptr() as *const libc::c_void,
+                data.len(),
+                libc::MSG_FASTOPEN | libc::MSG_NOSIGNAL,
+                &addr as *const _ as *const libc::sockaddr,
+                len,
+            )
+        };
+        if n >= 0 {
+            return Ok(Some(n as usize));
+        }
+
+        let e = io::Error::last_os_error();
+        match e.raw_os_error() {
+            // There is no cookie for this server yet, so a plain SYN asking
+            // for one is on its way.
+            Some(libc::EINPROGRESS) => Ok(Some(0)),
+            // Fast Open is disabled for clients by `net.ipv4.tcp_fastopen`.
+            Some(libc::EOPNOTSUPP) => Ok(None),
+            _ => Err(e),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod sys {
+    use std::io;
+    use std::net::{self, SocketAddr};
+    use std::os::unix::prelude::*;
+    use std::ptr;
+
+    /// Issues a nonblocking connect to `addr` on `socket`, sending as much of
+    /// `data` in the SYN as the kernel allows.
+    ///
+    /// Returns the number of bytes sent, or `None` if Fast Open isn't available
+    /// and no connect has been issued.
+    pub(super) fn connect(
+        socket: &net::TcpStream,
+        addr: &SocketAddr,
+        data: &[u8],
+    ) -> io::Result<Option<usize>> {
+        let (addr, len) = crate::sockaddr::from_socket_addr(addr);
+        let endpoints = libc::sa_endpoints_t {
+            sae_srcif: 0,
+            sae_srcaddr: ptr::null(),
+            sae_srcaddrlen: 0,
+            sae_dstaddr: &addr as *const _ as *const libc::sockaddr,
+            sae_dstaddrlen: len,
+        };
+        let iov = libc::iovec {
+            iov_base: data.as_ptr() as *mut libc::c_void,
+            iov_len: data.len(),
+        };
+        let mut sent = 0;
+        let r = unsafe {
+            libc::connectx(
+                socket.as_raw_fd(),
+                &endpoints,
+                libc::SAE_ASSOCID_ANY,
+                libc::CONNECT_RESUME_ON_READ_WRITE | libc::CONNECT_DATA_IDEMPOTENT,
+                if data.is_empty() { ptr::null() } else { &iov },
+                if data.is_empty() { 0 } else { 1 },
+                &mut sent,
+                ptr::null_mut(),
+            )
+        };
+        if r < 0 {
+            let e = io::Error::last_os_error();
+            match e.raw_os_error() {
+                Some(libc::EINPROGRESS) => {}
+                Some(libc::EOPNOTSUPP) | Some(libc::ENOSYS) => return Ok(None),
+                _ => return Err(e),
+            }
+        }
+        Ok(Some(sent))
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod sys {
+    use std::io;
+    use std::net::{self, SocketAddr};
+
+    /// Issues a nonblocking connect to `addr` on `socket`, sending as much of
+    /// `data` in the SYN as the kernel allows.
+    ///
+    /// Returns the number of bytes sent, or `None` if Fast Open isn't available
+    /// and no connect has been issued.
+    pub(super) fn connect(
+        _: &net::TcpStream,
+        _: &SocketAddr,
+        _: &[u8],
+    ) -> io::Result<Option<usize>> {
+        Ok(None)
+    }
+}
@@ -0,0 +1,128 @@
+use std::io;
+use std::mem;
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+/// Statistics about a TCP connection, as returned by `TcpStream::tcp_info`.
+///
+/// These are read from the `TCP_INFO` socket option. Fields which the running
+/// kernel doesn't report yet are zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TcpInfo {
+    /// The smoothed round trip time.
+    pub rtt: Duration,
+
+    /// The variation of the round trip time.
+    pub rttvar: Duration,
+
+    /// The size of the congestion window, in segments.
+    pub snd_cwnd: u32,
+
+    /// The maximum segment size used for sending, in bytes.
+    pub snd_mss: u32,
+
+    /// The number of segments currently being retransmitted.
+    pub retrans: u32,
+
+    /// The number of segments retransmitted over the lifetime of the
+    /// connection.
+    pub total_retrans: u32,
+
+    /// The number of segments currently considered lost.
+    pub lost: u32,
+
+    /// The most recent estimate of the delivery rate, in bytes per second.
+    ///
+    /// This is reported since Linux 4.9.
+    pub delivery_rate: u64,
+}
+
+/// The beginning of the kernel's `struct tcp_info`, up to the last field
+/// `TcpInfo` reads.
+///
+/// Kernels append fields to the struct over time, and older ones return a
+/// shorter struct, in which case the remaining fields are left zeroed.
+#[repr(C)]
+#[derive(Default)]
+struct RawTcpInfo {
+    tcpi_state: u8,
+    tcpi_ca_state: u8,
+    tcpi_retransmits: u8,
+    tcpi_probes: u8,
+    tcpi_backoff: u8,
+    tcpi_options: u8,
+    tcpi_wscale: u8,
+    tcpi_flags: u8,
+
+    tcpi_rto: u32,
+    tcpi_ato: u32,
+    tcpi_snd_mss: u32,
+    tcpi_rcv_mss: u32,
+
+    tcpi_unacked: u32,
+    tcpi_sacked: u32,
+    tcpi_lost: u32,
+    tcpi_retrans: u32,
+    tcpi_fackets: u32,
+
+    tcpi_last_data_sent: u32,
+    tcpi_last_ack_sent: u32,
+    tcpi_last_data_recv: u32,
+    tcpi_last_ack_recv: u32,
+
+    tcpi_pmtu: u32,
+    tcpi_rcv_ssthresh: u32,
+    tcpi_rtt: u32,
+    tcpi_rttvar: u32,
+    tcpi_snd_ssthresh: u32,
+    tcpi_snd_cwnd: u32,
+    tcpi_advmss: u32,
+    tcpi_reordering: u32,
+
+    tcpi_rcv_rtt: u32,
+    tcpi_rcv_space: u32,
+
+    tcpi_total_retrans: u32,
+
+    tcpi_pacing_rate: u64,
+    tcpi_max_pacing_rate: u64,
+    tcpi_bytes_acked: u64,
+    tcpi_bytes_received: u64,
+    tcpi_segs_out: u32,
+    tcpi_segs_in: u32,
+
+    tcpi_notsent_bytes: u32,
+    tcpi_min_rtt: u32,
+    tcpi_data_segs_in: u32,
+    tcpi_data_segs_out: u32,
+
+    tcpi_delivery_rate: u64,
+}
+
+pub(super) fn tcp_info(socket: &impl AsRawFd) -> io::Result<TcpInfo> {
+    let mut raw = RawTcpInfo::default();
+    let mut len = mem::size_of::<RawTcpInfo>() as libc::socklen_t;
+    let r = unsafe {
+        libc::getsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_INFO,
+            &mut raw as *mut _ as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if r < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(TcpInfo {
+        rtt: Duration::from_micros(u64::from(raw.tcpi_rtt)),
+        rttvar: Duration::from_micros(u64::from(raw.tcpi_rttvar)),
+        snd_cwnd: raw.tcpi_snd_cwnd,
+        snd_mss: raw.tcpi_snd_mss,
+        retrans: raw.tcpi_retrans,
+        total_retrans: raw.tcpi_total_retrans,
+        lost: raw.tcpi_lost,
+        delivery_rate: raw.tcpi_delivery_rate,
+    })
+}
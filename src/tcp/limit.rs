@@ -0,0 +1,98 @@
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Arc;
+
+use futures::stream::Stream;
+use futures::task::{AtomicWaker, LocalWaker};
+use futures::{ready, Poll};
+
+use super::{TcpListener, TcpStream};
+
+/// A stream of the connections accepted on a `TcpListener`, which stops
+/// accepting while too many of them are alive.
+///
+/// Each connection is yielded along with a [`ConcurrencyPermit`], and counts
+/// as alive until the permit is dropped.
+///
+/// This is created by the `TcpListener::limit_concurrency` method.
+///
+/// [`ConcurrencyPermit`]: struct.ConcurrencyPermit.html
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct LimitConcurrency<'a> {
+    listener: &'a TcpListener,
+    slots: Arc<Slots>,
+}
+
+/// A token for a connection yielded by `LimitConcurrency`.
+///
+/// Dropping the permit allows the next connection to be accepted. It is
+/// usually kept alongside the `TcpStream` in the task handling the connection.
+#[derive(Debug)]
+pub struct ConcurrencyPermit {
+    slots: Arc<Slots>,
+}
+
+#[derive(Debug)]
+struct Slots {
+    available: AtomicUsize,
+    waker: AtomicWaker,
+}
+
+impl<'a> LimitConcurrency<'a> {
+    pub(super) fn new(listener: &'a TcpListener, max: usize) -> LimitConcurrency<'a> {
+        LimitConcurrency {
+            listener,
+            slots: Arc::new(Slots {
+                available: AtomicUsize::new(max),
+                waker: AtomicWaker::new(),
+            }),
+        }
+    }
+
+    /// Returns the number of connections which may still be accepted before
+    /// the limit is reached.
+    pub fn available(&self) -> usize {
+        self.slots.available.load(SeqCst)
+    }
+}
+
+impl<'a> Stream for LimitConcurrency<'a> {
+    type Item = io::Result<(TcpStream, SocketAddr, ConcurrencyPermit)>;
+
+    fn poll_next(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Option<Self::Item>> {
+        // Register before checking, so a permit dropped in between can't be
+        // missed. Only this stream takes slots, so the count can't drop to
+        // zero before the slot is taken below.
+        self.slots.waker.register(lw);
+
+        if self.slots.available.load(SeqCst) == 0 {
+            return Poll::Pending;
+        }
+
+        match ready!(self.listener.poll_accept(lw)) {
+            Ok((stream, addr)) => {
+                self.slots.available.fetch_sub(1, SeqCst);
+                let permit = ConcurrencyPermit {
+                    slots: self.slots.clone(),
+                };
+                Poll::Ready(Some(Ok((stream, addr, permit))))
+            }
+            Err(_) if self.listener.is_closed() => Poll::Ready(None),
+            Err(e) => {
+                self.listener.halt_on_error(&e);
+                Poll::Ready(Some(Err(e)))
+            }
+        }
+    }
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        self.slots.available.fetch_add(1, SeqCst);
+        self.slots.waker.wake();
+    }
+}
@@ -1,16 +1,31 @@
+use super::limit::LimitConcurrency;
+use super::reserve::{self, Reserve};
+use super::serve::Serve;
+use super::throttle::Throttle;
 use super::TcpStream;
 
-use std::fmt;
+use std::fmt::{self, Write};
 use std::io;
-use std::net::{self, SocketAddr};
+use std::mem;
+use std::net::{SocketAddr, ToSocketAddrs};
+#[cfg(target_os = "linux")]
+use std::os::unix::io::AsRawFd;
 use std::pin::Pin;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use futures::stream::Stream;
-use futures::task::LocalWaker;
-use futures::{ready, Poll};
+use futures::task::{local_waker_from_nonlocal, LocalWaker, Spawn, Wake, Waker};
+use futures::{ready, Future, Poll};
+use log::debug;
 use mio;
 
+use crate::io::{AddrError, Operation};
 use crate::reactor::PollEvented;
+#[cfg(target_os = "linux")]
+use crate::sockopt;
 
 /// A TCP socket server, listening for connections.
 ///
@@ -50,6 +65,122 @@ use crate::reactor::PollEvented;
 #[must_use = "streams do nothing unless polled"]
 pub struct TcpListener {
     io: PollEvented<mio::net::TcpListener>,
+    acceptors: Arc<Acceptors>,
+    /// Set once the listener was closed, shared with its `CloseHandle`s.
+    closed: Arc<AtomicBool>,
+    /// Set while accepting is paused.
+    paused: AtomicBool,
+    /// Limits the rate at which connections are accepted.
+    throttle: Mutex<Throttle>,
+    /// An error hit in the middle of a batch of accepts, returned by the next
+    /// attempt.
+    deferred: Mutex<Option<io::Error>>,
+    /// The `AcceptErrorPolicy`, stored as its discriminant.
+    error_policy: AtomicUsize,
+    /// A descriptor kept for when the process runs out of them.
+    reserve: Mutex<Reserve>,
+    accept_options: AcceptOptions,
+    /// The local address of every accepted connection, if the listener is
+    /// bound to a specific address.
+    accepted_local_addr: Option<SocketAddr>,
+}
+
+/// Socket options set on every accepted connection, `UNSET` unless they were
+/// configured.
+///
+/// They are atomics so that they can be changed through a shared listener.
+#[derive(Debug)]
+struct AcceptOptions {
+    nodelay: AtomicUsize,
+    ttl: AtomicUsize,
+}
+
+/// The value of an accept option which wasn't configured.
+const UNSET: usize = usize::max_value();
+
+/// The tasks currently waiting to accept a connection.
+///
+/// The reactor only tracks a single waker per readiness stream, so instead of
+/// registering the accepting task directly, the listener registers a waker
+/// that wakes every waiting task. Each of them then attempts one accept, and
+/// those that hit `WouldBlock` wait again.
+#[derive(Debug, Default)]
+struct Acceptors {
+    wakers: Mutex<Vec<Waker>>,
+}
+
+/// How a `TcpListener` deals with errors while accepting connections.
+///
+/// Accepting fails for a number of reasons, which fall into three groups:
+///
+/// - Transient errors, like a connection that was aborted by its peer while it
+///   waited in the backlog (`ECONNABORTED`) or an accept interrupted by a
+///   signal (`EINTR`). They only concern a single connection, or none at all,
+///   and accepting the next one usually succeeds.
+/// - Running out of file descriptors (`EMFILE` or `ENFILE`). The pending
+///   connections stay in the backlog, and the listener backs off for a moment
+///   before trying again, see `TcpListener::set_accept_backoff`. These are
+///   handled like transient errors, except that they never close the listener.
+/// - All other errors, like running out of memory. The pending connections
+///   stay in the backlog and may be accepted once the resource is available
+///   again.
+///
+/// This is set with `TcpListener::set_accept_error_policy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcceptErrorPolicy {
+    /// Transient errors are skipped and accepting continues with the next
+    /// connection. All other errors are returned, after which the listener can
+    /// still be used to accept connections.
+    ///
+    /// This is the default.
+    Continue,
+
+    /// Every error is returned, including transient ones. The listener can
+    /// still be used to accept connections afterwards.
+    ReportAll,
+
+    /// Transient errors are skipped, and the listener is closed after the
+    /// first other error was returned by its `Stream` implementations or ended
+    /// `TcpListener::serve`, unless it was running out of file descriptors.
+    /// The streams end after yielding that error, as if the listener had been
+    /// closed through a `CloseHandle`.
+    Halt,
+}
+
+/// A handle to close a `TcpListener`, which may be used from any task.
+///
+/// This is created by the `TcpListener::close_handle` method.
+#[derive(Debug, Clone)]
+pub struct CloseHandle {
+    closed: Arc<AtomicBool>,
+    acceptors: Arc<Acceptors>,
+}
+
+/// The future returned by `TcpListener::accept`, which resolves to the accepted
+/// `TcpStream` and the address of its peer.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct Accept<'a> {
+    listener: &'a TcpListener,
+}
+
+/// The future returned by `TcpListener::accept_batch`, which resolves to the
+/// connections accepted at once, together with the addresses of their peers.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct AcceptBatch<'a> {
+    listener: &'a TcpListener,
+    max: usize,
+}
+
+/// A stream of the connections accepted on a `TcpListener`, together with the
+/// addresses of their peers.
+///
+/// This is created by the `TcpListener::incoming_with_addr` method.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct IncomingWithAddr<'a> {
+    listener: &'a TcpListener,
 }
 
 impl TcpListener {
@@ -75,14 +206,166 @@ impl TcpListener {
     /// ```
     ///
     /// [`local_addr`]: #method.local_addr
-    pub fn bind(addr: &SocketAddr) -> io::Result<TcpListener> {
-        let l = mio::net::TcpListener::bind(addr)?;
+    pub fn bind(addr: &SocketAddr) -> Result<TcpListener, AddrError> {
+        let l = mio::net::TcpListener::bind(addr)
+            .map_err(|e| AddrError::new(Operation::Bind, addr, e))?;
         Ok(TcpListener::new(l))
     }
 
-    fn new(listener: mio::net::TcpListener) -> TcpListener {
+    /// Creates a new `TcpListener` bound to the first of the addresses `addrs`
+    /// resolves to which can be bound.
+    ///
+    /// The addresses are tried in order, so for a name like `localhost:0`
+    /// the listener uses whichever address family the resolver lists first,
+    /// falling back to the other one. If no address can be bound, the
+    /// returned error lists the failure for each address.
+    ///
+    /// Resolving a host name blocks the current thread, so names should only
+    /// be passed during startup, or resolved with [`romio::resolver`] first.
+    ///
+    /// [`romio::resolver`]: ../resolver/index.html
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::tcp::TcpListener;
+    ///
+    /// # fn main () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let listener = TcpListener::bind_addrs("localhost:8080")?;
+    /// println!("listening on {}", listener.local_addr()?);
+    /// # Ok(())}
+    /// ```
+    pub fn bind_addrs(addrs: impl ToSocketAddrs) -> io::Result<TcpListener> {
+        let mut errors = Vec::new();
+        for addr in addrs.to_socket_addrs()? {
+            match TcpListener::bind(&addr) {
+                Ok(listener) => return Ok(listener),
+                Err(e) => errors.push((addr, io::Error::from(e))),
+            }
+        }
+
+        if errors.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no addresses to bind to",
+            ));
+        }
+        if errors.len() == 1 {
+            return Err(errors.pop().unwrap().1);
+        }
+
+        // The errors name the addresses they are about.
+        let mut msg = String::from("failed to bind to any address:");
+        for (_, e) in &errors {
+            let _ = write!(msg, " {};", e);
+        }
+        msg.pop();
+
+        Err(io::Error::new(io::ErrorKind::Other, msg))
+    }
+
+    /// Creates `n` listeners bound to the same address with `SO_REUSEPORT`,
+    /// letting the kernel distribute the incoming connections between them.
+    ///
+    /// This removes the single listener as a bottleneck when accepting from
+    /// many threads: each listener can be driven by a worker of its own. If
+    /// the port of `addr` is 0, the port assigned to the first listener is
+    /// used for the others.
+    ///
+    /// An error is returned if the kernel doesn't support `SO_REUSEPORT`, or
+    /// if the address is already in use by a socket without it. No listener
+    /// is left bound in that case.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::tcp::TcpListener;
+    ///
+    /// # fn main () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let socket_addr = "0.0.0.0:8080".parse()?;
+    /// let listeners = TcpListener::bind_reuseport_group(&socket_addr, 4)?;
+    ///
+    /// for listener in listeners {
+    ///     std::thread::spawn(move || {
+    ///         // accept from `listener` on this thread
+    ///     });
+    /// }
+    /// # Ok(())}
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn bind_reuseport_group(addr: &SocketAddr, n: usize) -> io::Result<Vec<TcpListener>> {
+        assert!(n > 0, "reuseport group must not be empty");
+
+        let mut builder = super::TcpListenerBuilder::new();
+        builder.reuse_port(true);
+
+        let first = builder.bind(addr)?;
+        let addr = first.local_addr()?;
+
+        let mut group = Vec::with_capacity(n);
+        group.push(first);
+        for _ in 1..n {
+            group.push(builder.bind(&addr)?);
+        }
+        Ok(group)
+    }
+
+    /// Creates a new `TcpListener` from a listening socket configured with
+    /// `socket2`.
+    ///
+    /// This allows setting options this crate doesn't offer, like
+    /// `IP_TRANSPARENT`, before binding. `listen` must have been called on
+    /// the socket already. This requires the `socket2` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::tcp::TcpListener;
+    /// use socket2::{Domain, Socket, Type};
+    /// use std::net::SocketAddr;
+    ///
+    /// # fn main () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr: SocketAddr = "127.0.0.1:8080".parse()?;
+    /// let socket = Socket::new(Domain::ipv4(), Type::stream(), None)?;
+    /// socket.set_reuse_address(true)?;
+    /// socket.bind(&addr.into())?;
+    /// socket.listen(1024)?;
+    ///
+    /// let listener = TcpListener::from_socket(socket)?;
+    /// # Ok(())}
+    /// ```
+    #[cfg(feature = "socket2")]
+    pub fn from_socket(socket: socket2::Socket) -> io::Result<TcpListener> {
+        let listener = mio::net::TcpListener::from_std(socket.into_tcp_listener())?;
+        Ok(TcpListener::new(listener))
+    }
+
+    pub(crate) fn new(listener: mio::net::TcpListener) -> TcpListener {
+        // Connections accepted on an unspecified address, like `0.0.0.0`, can
+        // be to any of the local addresses.
+        let accepted_local_addr = match listener.local_addr() {
+            Ok(addr) if !addr.ip().is_unspecified() && addr.port() != 0 => Some(addr),
+            _ => None,
+        };
+
         let io = PollEvented::new(listener);
-        TcpListener { io }
+        let acceptors = Arc::new(Acceptors::default());
+        TcpListener {
+            io,
+            acceptors,
+            closed: Arc::new(AtomicBool::new(false)),
+            paused: AtomicBool::new(false),
+            throttle: Mutex::new(Throttle::default()),
+            deferred: Mutex::new(None),
+            error_policy: AtomicUsize::new(AcceptErrorPolicy::default() as usize),
+            reserve: Mutex::new(Reserve::default()),
+            accept_options: AcceptOptions::default(),
+            accepted_local_addr,
+        }
     }
 
     /// Returns the local address that this listener is bound to.
@@ -154,28 +437,1041 @@ impl TcpListener {
         self.io.get_ref().set_ttl(ttl)
     }
 
-    fn poll_accept(&mut self, lw: &LocalWaker) -> Poll<io::Result<(TcpStream, SocketAddr)>> {
-        let (io, addr) = ready!(self.poll_accept_std(lw)?);
+    /// Returns the value of the `SO_ERROR` option.
+    ///
+    /// This retrieves and clears the pending error on the socket, so a
+    /// subsequent call returns `Ok(None)` until another error occurs.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::tcp::TcpListener;
+    ///
+    /// # fn main () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let socket_addr = "127.0.0.1:0".parse()?;
+    /// let listener = TcpListener::bind(&socket_addr)?;
+    /// if let Some(err) = listener.take_error()? {
+    ///     println!("Got error: {:?}", err);
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.io.get_ref().take_error()
+    }
+
+    /// Gets the value of the `IPV6_V6ONLY` option for this socket.
+    ///
+    /// If this is `true`, a listener bound to an IPv6 address only accepts
+    /// IPv6 connections. The option can be configured before binding with
+    /// [`TcpListenerBuilder::only_v6`].
+    ///
+    /// [`TcpListenerBuilder::only_v6`]: struct.TcpListenerBuilder.html#method.only_v6
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::tcp::TcpListenerBuilder;
+    ///
+    /// # fn main () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let socket_addr = "[::]:0".parse()?;
+    /// let listener = TcpListenerBuilder::new()
+    ///     .only_v6(false)
+    ///     .bind(&socket_addr)?;
+    /// assert_eq!(listener.only_v6()?, false);
+    /// # Ok(()) }
+    /// ```
+    pub fn only_v6(&self) -> io::Result<bool> {
+        self.io.get_ref().only_v6()
+    }
+
+    /// Gets the value of the `TCP_DEFER_ACCEPT` option for this socket.
+    ///
+    /// For more information about this option, see [`set_defer_accept`].
+    ///
+    /// [`set_defer_accept`]: #method.set_defer_accept
+    pub fn defer_accept(&self) -> io::Result<Option<Duration>> {
+        defer_accept(self.io.get_ref())
+    }
+
+    /// Sets the value of the `TCP_DEFER_ACCEPT` option on this socket.
+    ///
+    /// With this option set, a connection is only reported to `accept` once
+    /// the client has sent some data, or after the timeout elapsed, so tasks
+    /// aren't woken up for idle connections. `None` disables it.
+    ///
+    /// The timeout is rounded up to whole seconds, and the kernel rounds it
+    /// further up to the next SYN-ACK retransmission, so [`defer_accept`] may
+    /// report a larger value than was set. The option can also be set before
+    /// listening with [`TcpListenerBuilder::defer_accept`].
+    ///
+    /// This option is only supported on Linux, on other platforms this is a
+    /// no-op and [`defer_accept`] always returns `None`.
+    ///
+    /// [`defer_accept`]: #method.defer_accept
+    /// [`TcpListenerBuilder::defer_accept`]: struct.TcpListenerBuilder.html#method.defer_accept
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::tcp::TcpListener;
+    /// use std::time::Duration;
+    ///
+    /// # fn main () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let socket_addr = "127.0.0.1:0".parse()?;
+    /// let listener = TcpListener::bind(&socket_addr)?;
+    /// listener.set_defer_accept(Some(Duration::from_secs(3)))?;
+    /// # Ok(()) }
+    /// ```
+    pub fn set_defer_accept(&self, timeout: Option<Duration>) -> io::Result<()> {
+        set_defer_accept(self.io.get_ref(), timeout)
+    }
+
+    /// Returns the name of the network interface this listener is bound to,
+    /// if any.
+    ///
+    /// For more information about this option, see [`set_device`].
+    ///
+    /// [`set_device`]: #method.set_device
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn device(&self) -> io::Result<Option<String>> {
+        crate::device::device(self.io.get_ref())
+    }
+
+    /// Binds this listener to the network interface named `device`, or
+    /// removes the binding if `None`.
+    ///
+    /// Only connections arriving on that interface are accepted. This uses
+    /// `SO_BINDTODEVICE` on Linux, which usually requires the `CAP_NET_RAW`
+    /// capability, and `IP_BOUND_IF` on macOS. Names longer than the platform
+    /// allows are rejected with an `InvalidInput` error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::tcp::TcpListener;
+    ///
+    /// # fn main () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let socket_addr = "0.0.0.0:8080".parse()?;
+    /// let listener = TcpListener::bind(&socket_addr)?;
+    /// listener.set_device(Some("eth1"))?;
+    /// # Ok(()) }
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn set_device(&self, device: Option<&str>) -> io::Result<()> {
+        crate::device::set_device(self.io.get_ref(), device)
+    }
+
+    /// Gets the value of the `SO_MARK` option on this socket.
+    ///
+    /// For more information about this option, see [`set_mark`].
+    ///
+    /// [`set_mark`]: #method.set_mark
+    #[cfg(target_os = "linux")]
+    pub fn mark(&self) -> io::Result<u32> {
+        crate::mark::mark(self.io.get_ref())
+    }
 
-        let io = mio::net::TcpStream::from_stream(io)?;
-        let io = TcpStream::new(io);
+    /// Sets the value of the `SO_MARK` option on this socket.
+    ///
+    /// The mark is attached to the packets sent from the socket, so they can
+    /// be routed with `ip rule fwmark` or matched by packet filters. Setting it
+    /// requires the `CAP_NET_ADMIN` capability, otherwise an error of kind
+    /// `PermissionDenied` is returned.
+    ///
+    /// This option is only supported on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn set_mark(&self, mark: u32) -> io::Result<()> {
+        crate::mark::set_mark(self.io.get_ref(), mark)
+    }
 
-        Poll::Ready(Ok((io, addr)))
+    /// Gets the value of the `SO_PRIORITY` option on this socket.
+    ///
+    /// For more information about this option, see [`set_priority`].
+    ///
+    /// [`set_priority`]: #method.set_priority
+    pub fn priority(&self) -> io::Result<u32> {
+        crate::priority::priority(self.io.get_ref())
     }
 
-    fn poll_accept_std(
-        &mut self,
+    /// Sets the value of the `SO_PRIORITY` option on this socket.
+    ///
+    /// The priority selects the band of a queueing discipline like `prio`
+    /// which the packets sent from the socket are queued in. Connections
+    /// accepted from the listener inherit it. Priorities from 0 to 6 can be
+    /// set by anyone, higher ones require the `CAP_NET_ADMIN` capability,
+    /// otherwise an error of kind `PermissionDenied` is returned.
+    ///
+    /// This option is only supported on Linux, elsewhere an error of kind
+    /// `Other` is returned.
+    pub fn set_priority(&self, priority: u32) -> io::Result<()> {
+        crate::priority::set_priority(self.io.get_ref(), priority)
+    }
+
+    /// Sets the value of the `TCP_NODELAY` option on every connection accepted
+    /// from now on.
+    ///
+    /// The option is set before the accepted `TcpStream` is returned, so the
+    /// connection is never used without it. If setting it fails, the
+    /// connection is closed and skipped like one aborted by its peer.
+    ///
+    /// See [`TcpStream::set_nodelay`] for details about the option.
+    ///
+    /// [`TcpStream::set_nodelay`]: struct.TcpStream.html#method.set_nodelay
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpListener;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let socket_addr = "127.0.0.1:80".parse()?;
+    /// let listener = TcpListener::bind(&socket_addr)?;
+    /// listener.set_accept_nodelay(true);
+    ///
+    /// let (stream, _) = await!(listener.accept())?;
+    /// assert_eq!(stream.nodelay()?, true);
+    /// # Ok(())}
+    /// ```
+    pub fn set_accept_nodelay(&self, nodelay: bool) {
+        self.accept_options.nodelay.store(nodelay as usize, SeqCst);
+    }
+
+    /// Sets the value of the `IP_TTL` option on every connection accepted from
+    /// now on.
+    ///
+    /// Like [`set_accept_nodelay`], the option is set before the accepted
+    /// `TcpStream` is returned.
+    ///
+    /// [`set_accept_nodelay`]: #method.set_accept_nodelay
+    pub fn set_accept_ttl(&self, ttl: u32) {
+        self.accept_options.ttl.store(ttl as usize, SeqCst);
+    }
+
+    /// Accepts a new incoming connection to this listener.
+    ///
+    /// The returned future resolves to the accepted stream and the address of
+    /// its peer. Unlike the `Stream` implementation, this only requires a
+    /// shared reference, so several tasks can accept from the same listener
+    /// concurrently, for example through an `Arc<TcpListener>`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpListener;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let socket_addr = "127.0.0.1:80".parse()?;
+    /// let listener = TcpListener::bind(&socket_addr)?;
+    ///
+    /// let (stream, addr) = await!(listener.accept())?;
+    /// println!("new client from {}", addr);
+    /// # Ok(())}
+    /// ```
+    pub fn accept(&self) -> Accept<'_> {
+        Accept { listener: self }
+    }
+
+    /// Accepts up to `max` connections at once.
+    ///
+    /// The returned future waits until at least one connection is pending,
+    /// then accepts connections until `max` is reached or none is pending
+    /// anymore. It resolves to the accepted streams and the addresses of their
+    /// peers. Under load many connections are usually queued by the time the
+    /// listener becomes readable, and accepting them in one go saves a wakeup
+    /// for each of them.
+    ///
+    /// If `max` connections were accepted, the listener is still considered
+    /// readable, so the remaining ones are accepted by the next attempt
+    /// without waiting for another notification.
+    ///
+    /// Errors are handled like in [`poll_accept_many`].
+    ///
+    /// [`poll_accept_many`]: #method.poll_accept_many
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpListener;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let socket_addr = "127.0.0.1:80".parse()?;
+    /// let listener = TcpListener::bind(&socket_addr)?;
+    ///
+    /// loop {
+    ///     for (stream, addr) in await!(listener.accept_batch(64))? {
+    ///         println!("new client from {}", addr);
+    ///     }
+    /// }
+    /// # Ok(())}
+    /// ```
+    pub fn accept_batch(&self, max: usize) -> AcceptBatch<'_> {
+        AcceptBatch {
+            listener: self,
+            max,
+        }
+    }
+
+    /// Returns a handle which closes this listener.
+    ///
+    /// Once `close` is called on the handle, tasks waiting for a connection
+    /// are woken up right away: the `Stream` implementations end, and
+    /// `accept` resolves with an error. Accepting from a closed listener
+    /// keeps failing. The socket itself is only closed once the listener is
+    /// dropped.
+    ///
+    /// This allows a server which is blocked accepting connections to be shut
+    /// down from another task.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use futures::prelude::*;
+    /// use romio::tcp::TcpListener;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let socket_addr = "127.0.0.1:80".parse()?;
+    /// let mut listener = TcpListener::bind(&socket_addr)?;
+    /// let close = listener.close_handle();
+    ///
+    /// // calling `close.close()` from elsewhere ends the loop
+    /// while let Some(stream) = await!(listener.next()) {
+    ///     println!("new client!");
+    /// }
+    /// # Ok(())}
+    /// ```
+    pub fn close_handle(&self) -> CloseHandle {
+        CloseHandle {
+            closed: self.closed.clone(),
+            acceptors: self.acceptors.clone(),
+        }
+    }
+
+    /// Pauses accepting connections.
+    ///
+    /// While paused, accepting from the listener stays pending, but the socket
+    /// keeps listening: new connections queue up in the backlog of the
+    /// operating system. This can be used to shed load without refusing
+    /// connections outright.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::tcp::TcpListener;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let socket_addr = "127.0.0.1:80".parse()?;
+    /// let listener = TcpListener::bind(&socket_addr)?;
+    ///
+    /// listener.pause();
+    /// // ... once the load went down
+    /// listener.resume();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pause(&self) {
+        self.paused.store(true, SeqCst);
+    }
+
+    /// Resumes accepting connections after a call to `pause`.
+    ///
+    /// The connections which queued up in the meantime are accepted first.
+    pub fn resume(&self) {
+        self.paused.store(false, SeqCst);
+        Wake::wake(&self.acceptors);
+    }
+
+    /// Returns `true` if accepting connections is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(SeqCst)
+    }
+
+    /// Limits the rate at which connections are accepted.
+    ///
+    /// With a limit of `Some(n)`, at most `n` connections are accepted per
+    /// second on average, while bursts of up to the size set with
+    /// [`set_accept_burst`] are accepted right away. Accepting waits on the
+    /// reactor's timer until the next connection is allowed, leaving the
+    /// others queued in the backlog of the operating system.
+    ///
+    /// Setting the limit to `None` removes it, which is the default.
+    ///
+    /// [`set_accept_burst`]: #method.set_accept_burst
+    ///
+    /// # Panics
+    ///
+    /// Panics if the limit is `Some(0)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::tcp::TcpListener;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let socket_addr = "127.0.0.1:80".parse()?;
+    /// let listener = TcpListener::bind(&socket_addr)?;
+    ///
+    /// listener.set_accept_burst(20);
+    /// listener.set_accept_rate(Some(100));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_accept_rate(&self, per_second: Option<u32>) {
+        assert!(per_second != Some(0), "accept rate must be non-zero");
+        self.throttle.lock().unwrap().set_rate(per_second);
+        Wake::wake(&self.acceptors);
+    }
+
+    /// Sets how many connections may be accepted at once when the rate is
+    /// limited with [`set_accept_rate`].
+    ///
+    /// Defaults to 1, values below 1 are treated as 1.
+    ///
+    /// [`set_accept_rate`]: #method.set_accept_rate
+    pub fn set_accept_burst(&self, burst: u32) {
+        self.throttle.lock().unwrap().set_burst(burst);
+    }
+
+    /// Returns how errors are handled while accepting connections.
+    ///
+    /// See [`set_accept_error_policy`] for details.
+    ///
+    /// [`set_accept_error_policy`]: #method.set_accept_error_policy
+    pub fn accept_error_policy(&self) -> AcceptErrorPolicy {
+        AcceptErrorPolicy::from_usize(self.error_policy.load(SeqCst))
+    }
+
+    /// Sets how errors are handled while accepting connections.
+    ///
+    /// By default transient errors like a connection aborted by its peer are
+    /// skipped, and all other errors are returned without ending the `Stream`
+    /// implementations, which remain usable afterwards. The policy applies to
+    /// every way of accepting connections on this listener. Futures like
+    /// `accept` return the error either way, while the `Stream`
+    /// implementations, `limit_concurrency` included, and `serve` stop once a
+    /// `Halt` closed the listener.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::tcp::{AcceptErrorPolicy, TcpListener};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let socket_addr = "127.0.0.1:80".parse()?;
+    /// let listener = TcpListener::bind(&socket_addr)?;
+    ///
+    /// // stop accepting after the first error that isn't transient
+    /// listener.set_accept_error_policy(AcceptErrorPolicy::Halt);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_accept_error_policy(&self, policy: AcceptErrorPolicy) {
+        self.error_policy.store(policy as usize, SeqCst);
+    }
+
+    /// Returns `true` if the listener keeps a file descriptor in reserve.
+    ///
+    /// See [`set_fd_reserve`] for details.
+    ///
+    /// [`set_fd_reserve`]: #method.set_fd_reserve
+    #[cfg(unix)]
+    pub fn fd_reserve(&self) -> bool {
+        self.reserve.lock().unwrap().is_enabled()
+    }
+
+    /// Sets whether the listener keeps a file descriptor in reserve, to deal
+    /// with the process running out of them.
+    ///
+    /// When accepting fails with `EMFILE` or `ENFILE`, the connection stays in
+    /// the backlog and the listener stays readable, so every further attempt
+    /// fails right away and an accept loop spins. With a reserve, which is an
+    /// open handle to `/dev/null`, the listener closes it to accept the
+    /// connection and close it right away, then reopens it and returns the
+    /// error. Without a reserve the error isn't returned, unless the policy is
+    /// `AcceptErrorPolicy::ReportAll`. Either way accepting backs off before
+    /// the next attempt, see [`set_accept_backoff`].
+    ///
+    /// This is disabled by default. Enabling it opens the reserve right away,
+    /// which fails if no descriptor is available.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::tcp::TcpListener;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let socket_addr = "127.0.0.1:80".parse()?;
+    /// let listener = TcpListener::bind(&socket_addr)?;
+    ///
+    /// listener.set_fd_reserve(true)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`set_accept_backoff`]: #method.set_accept_backoff
+    #[cfg(unix)]
+    pub fn set_fd_reserve(&self, enabled: bool) -> io::Result<()> {
+        self.reserve.lock().unwrap().set_enabled(enabled)
+    }
+
+    /// Returns how long accepting backs off after the process ran out of file
+    /// descriptors.
+    ///
+    /// See [`set_accept_backoff`] for details.
+    ///
+    /// [`set_accept_backoff`]: #method.set_accept_backoff
+    pub fn accept_backoff(&self) -> Duration {
+        self.reserve.lock().unwrap().backoff()
+    }
+
+    /// Sets how long accepting backs off after the process ran out of file
+    /// descriptors.
+    ///
+    /// When accepting fails with `EMFILE` or `ENFILE`, the connection stays in
+    /// the backlog and the listener stays readable, so trying again right
+    /// away fails the same way. Instead the listener waits for `backoff`
+    /// before the next attempt, giving the process a chance to close some
+    /// descriptors, and keeps trying until it succeeds. The error isn't
+    /// returned, so an accept loop which stops at the first error keeps
+    /// running. See [`set_fd_reserve`] to take connections off the backlog in
+    /// the meantime, and `AcceptErrorPolicy::ReportAll` to get the error.
+    ///
+    /// The default is 100 milliseconds. A backoff already in progress isn't
+    /// affected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::tcp::TcpListener;
+    /// use std::time::Duration;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let socket_addr = "127.0.0.1:80".parse()?;
+    /// let listener = TcpListener::bind(&socket_addr)?;
+    ///
+    /// listener.set_accept_backoff(Duration::from_secs(1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`set_fd_reserve`]: #method.set_fd_reserve
+    pub fn set_accept_backoff(&self, backoff: Duration) {
+        self.reserve.lock().unwrap().set_backoff(backoff);
+    }
+
+    /// Returns `true` if the listener was closed through a `CloseHandle`.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(SeqCst)
+    }
+
+    /// Returns a stream of the connections accepted on this listener, together
+    /// with the addresses of their peers.
+    ///
+    /// This is like the `Stream` implementation of the listener itself, but
+    /// doesn't throw away the peer address. Calling `peer_addr` on the stream
+    /// later fails if the peer has disconnected in the meantime.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use futures::prelude::*;
+    /// use romio::tcp::TcpListener;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let socket_addr = "127.0.0.1:80".parse()?;
+    /// let listener = TcpListener::bind(&socket_addr)?;
+    /// let mut incoming = listener.incoming_with_addr();
+    ///
+    /// while let Some(accepted) = await!(incoming.next()) {
+    ///     let (stream, addr) = accepted?;
+    ///     println!("new client from {}", addr);
+    /// }
+    /// # Ok(())}
+    /// ```
+    pub fn incoming_with_addr(&self) -> IncomingWithAddr<'_> {
+        IncomingWithAddr { listener: self }
+    }
+
+    /// Returns a stream of the connections accepted on this listener, which
+    /// accepts at most `max` connections at a time.
+    ///
+    /// Each connection is yielded together with the address of its peer and a
+    /// permit, and counts towards the limit until the permit is dropped. Once
+    /// `max` permits are alive, no more connections are accepted: they queue
+    /// up in the backlog of the operating system until a permit is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use futures::executor::ThreadPool;
+    /// use futures::prelude::*;
+    /// use futures::task::SpawnExt;
+    /// use romio::tcp::TcpListener;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let socket_addr = "127.0.0.1:80".parse()?;
+    /// let listener = TcpListener::bind(&socket_addr)?;
+    /// let mut pool = ThreadPool::new()?;
+    /// let mut incoming = listener.limit_concurrency(100);
+    ///
+    /// while let Some(accepted) = await!(incoming.next()) {
+    ///     let (mut stream, _, permit) = accepted?;
+    ///     pool.spawn(async move {
+    ///         let _ = await!(stream.write_all(b"Good night, good night!"));
+    ///         drop(permit);
+    ///     })?;
+    /// }
+    /// # Ok(())}
+    /// ```
+    pub fn limit_concurrency(&self, max: usize) -> LimitConcurrency<'_> {
+        assert!(max > 0, "concurrency limit must be non-zero");
+        LimitConcurrency::new(self, max)
+    }
+
+    /// Serves connections accepted on this listener until `shutdown`
+    /// completes.
+    ///
+    /// Each accepted connection is passed to `handler` together with the
+    /// address of its peer, and the future it returns is spawned on
+    /// `spawner`. Once `shutdown` completes the listener is closed right away,
+    /// so no further connections are accepted, but connections which are
+    /// already being handled are allowed to finish: the returned future
+    /// resolves once all of them are done.
+    ///
+    /// Errors while accepting are logged and accepting carries on, unless the
+    /// policy is `AcceptErrorPolicy::Halt`. Then the listener is closed, and
+    /// the returned future resolves with the error once the connections in
+    /// flight are done. It also resolves with an error if spawning a task
+    /// fails, leaving the connection tasks which were already spawned
+    /// running.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use futures::channel::oneshot;
+    /// use futures::executor::ThreadPool;
+    /// use futures::prelude::*;
+    /// use romio::tcp::TcpListener;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let socket_addr = "127.0.0.1:80".parse()?;
+    /// let listener = TcpListener::bind(&socket_addr)?;
+    /// let (stop, stopped) = oneshot::channel::<()>();
+    ///
+    /// let serve = listener.serve(ThreadPool::new()?, stopped.map(|_| ()), |mut stream, _| {
+    ///     async move {
+    ///         let _ = await!(stream.write_all(b"Good night, good night!"));
+    ///     }
+    /// });
+    ///
+    /// // calling `stop.send(())` stops accepting and waits for the
+    /// // connections in flight
+    /// await!(serve)?;
+    /// # Ok(())}
+    /// ```
+    pub fn serve<S, F, Fut, Sd>(self, spawner: S, shutdown: Sd, handler: F) -> Serve<S, F, Sd>
+    where
+        S: Spawn,
+        F: FnMut(TcpStream, SocketAddr) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+        Sd: Future<Output = ()>,
+    {
+        Serve::new(self, spawner, shutdown, handler)
+    }
+
+    /// Attempts to accept a new incoming connection to this listener.
+    ///
+    /// If no connection is pending, `Poll::Pending` is returned and the current
+    /// task is notified once the listener becomes readable again. Any number of
+    /// tasks can wait on the same listener: all of them are notified when a
+    /// connection arrives, and those that lose the race wait again.
+    pub fn poll_accept(&self, lw: &LocalWaker) -> Poll<io::Result<(TcpStream, SocketAddr)>> {
+        let (io, addr) = ready!(self.poll_accept_mio(lw, true)?);
+
+        Poll::Ready(Ok((self.accepted(io, addr), addr)))
+    }
+
+    /// Attempts to accept up to `max` incoming connections at once, pushing
+    /// them onto `out`.
+    ///
+    /// Connections are accepted until `max` is reached or none is pending
+    /// anymore, and the number accepted is returned. This saves a round trip
+    /// through the executor for every connection when many of them arrive at
+    /// once. If no connection is pending at all, `Poll::Pending` is returned
+    /// like for [`poll_accept`].
+    ///
+    /// If accepting fails after some connections were already accepted, those
+    /// are returned and the error is reported by the next attempt to accept.
+    ///
+    /// [`poll_accept`]: #method.poll_accept
+    pub fn poll_accept_many(
+        &self,
         lw: &LocalWaker,
-    ) -> Poll<io::Result<(net::TcpStream, SocketAddr)>> {
-        ready!(self.io.poll_read_ready(lw)?);
-
-        match self.io.get_ref().accept_std() {
-            Ok(pair) => Poll::Ready(Ok(pair)),
-            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
-                self.io.clear_read_ready(lw)?;
-                Poll::Pending
+        out: &mut Vec<TcpStream>,
+        max: usize,
+    ) -> Poll<io::Result<usize>> {
+        self.poll_accept_each(lw, max, |io, addr| out.push(self.accepted(io, addr)))
+    }
+
+    /// Wraps an accepted connection, remembering its addresses.
+    fn accepted(&self, io: mio::net::TcpStream, addr: SocketAddr) -> TcpStream {
+        TcpStream::with_addrs(io, self.accepted_local_addr, Some(addr))
+    }
+
+    /// Accepts up to `max` connections, passing each of them to `f`.
+    ///
+    /// Stopping at `max` leaves the readiness alone, it is only cleared once
+    /// accepting would block, so no connection is left waiting for a
+    /// notification which never comes.
+    fn poll_accept_each<F>(&self, lw: &LocalWaker, max: usize, mut f: F) -> Poll<io::Result<usize>>
+    where
+        F: FnMut(mio::net::TcpStream, SocketAddr),
+    {
+        let mut accepted = 0;
+
+        while accepted < max {
+            // The batch counts against the poll budget as a single accept.
+            match self.poll_accept_mio(lw, accepted == 0) {
+                Poll::Ready(Ok((io, addr))) => {
+                    f(io, addr);
+                    accepted += 1;
+                }
+                Poll::Ready(Err(e)) => {
+                    if accepted == 0 {
+                        return Poll::Ready(Err(e));
+                    }
+                    *self.deferred.lock().unwrap() = Some(e);
+                    break;
+                }
+                Poll::Pending if accepted == 0 => return Poll::Pending,
+                Poll::Pending => break,
+            }
+        }
+
+        Poll::Ready(Ok(accepted))
+    }
+
+    fn poll_accept_mio(
+        &self,
+        lw: &LocalWaker,
+        budgeted: bool,
+    ) -> Poll<io::Result<(mio::net::TcpStream, SocketAddr)>> {
+        // Enqueue the task before inspecting the readiness state, so a
+        // notification arriving in between can't be lost.
+        self.acceptors.register(lw);
+        let lw = local_waker_from_nonlocal(self.acceptors.clone());
+
+        if self.is_closed() {
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, "listener closed")));
+        }
+
+        if let Some(e) = self.deferred.lock().unwrap().take() {
+            return Poll::Ready(Err(e));
+        }
+
+        // While paused the readiness is left alone, it is still set when
+        // accepting resumes. The task is woken up by `resume`, or spuriously
+        // by a connection arriving, in which case it just waits again.
+        if self.is_paused() {
+            return Poll::Pending;
+        }
+
+        // Neither lock is held while accepting, so tasks accepting at the same
+        // time may both find the last token. The bucket then goes negative and
+        // the rate evens out over the next connections.
+        ready!(self.throttle.lock().unwrap().poll_ready(&lw)?);
+        ready!(self.reserve.lock().unwrap().poll_ready(&lw)?);
+
+        if budgeted {
+            ready!(self.io.poll_read_ready(&lw)?);
+        } else {
+            ready!(self.io.poll_read_ready_unbudgeted(&lw)?);
+        }
+
+        let skip_transient = self.accept_error_policy() != AcceptErrorPolicy::ReportAll;
+
+        loop {
+            match accept(self.io.get_ref()) {
+                Ok((io, addr)) => {
+                    self.throttle.lock().unwrap().consume();
+                    // The error only concerns this connection, which may well
+                    // have been reset already, so it is skipped.
+                    if let Err(e) = self.accept_options.apply(&io) {
+                        debug!("failed to set options on accepted connection: {}", e);
+                        ready!(self.throttle.lock().unwrap().poll_ready(&lw)?);
+                        continue;
+                    }
+                    return Poll::Ready(Ok((io, addr)));
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    self.io.clear_read_ready(&lw)?;
+                    return Poll::Pending;
+                }
+                Err(ref e) if skip_transient && is_transient(e) => {
+                    debug!("skipping transient accept error: {}", e);
+                }
+                Err(e) => {
+                    let report = self
+                        .reserve
+                        .lock()
+                        .unwrap()
+                        .handle_error(&e, || accept(self.io.get_ref()).map(drop));
+                    if report || !skip_transient {
+                        return Poll::Ready(Err(e));
+                    }
+
+                    // The connection is still in the backlog, so the readiness
+                    // is left alone and accepting is retried once the backoff
+                    // is over.
+                    ready!(self.reserve.lock().unwrap().poll_ready(&lw)?);
+                    lw.wake();
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+
+    /// Closes the listener after an accept error if the policy asks for it.
+    ///
+    /// Running out of descriptors never closes it, the listener backs off
+    /// instead.
+    pub(super) fn halt_on_error(&self, e: &io::Error) {
+        if self.accept_error_policy() == AcceptErrorPolicy::Halt && !reserve::is_exhausted(e) {
+            self.close_handle().close();
+        }
+    }
+}
+
+/// Returns `true` if an error returned by accept only concerns a single
+/// connection, so that accepting the next one may succeed right away.
+fn is_transient(e: &io::Error) -> bool {
+    match e.kind() {
+        io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::Interrupted => return true,
+        _ => {}
+    }
+
+    // Linux passes pending network errors of the new connection on to accept,
+    // `accept(2)` recommends to treat them like `EAGAIN` and retry.
+    #[cfg(target_os = "linux")]
+    {
+        match e.raw_os_error() {
+            Some(libc::ENETDOWN)
+            | Some(libc::EPROTO)
+            | Some(libc::ENOPROTOOPT)
+            | Some(libc::EHOSTDOWN)
+            | Some(libc::ENONET)
+            | Some(libc::EHOSTUNREACH)
+            | Some(libc::EOPNOTSUPP)
+            | Some(libc::ENETUNREACH) => return true,
+            _ => {}
+        }
+    }
+
+    false
+}
+
+#[cfg(target_os = "linux")]
+pub(super) fn defer_accept(socket: &impl AsRawFd) -> io::Result<Option<Duration>> {
+    let secs = sockopt::getsockopt(socket, libc::IPPROTO_TCP, libc::TCP_DEFER_ACCEPT)?;
+    Ok(if secs > 0 {
+        Some(Duration::from_secs(secs as u64))
+    } else {
+        None
+    })
+}
+
+#[cfg(target_os = "linux")]
+pub(super) fn set_defer_accept(socket: &impl AsRawFd, timeout: Option<Duration>) -> io::Result<()> {
+    use std::cmp;
+
+    let secs = match timeout {
+        Some(timeout) => {
+            let secs = timeout.as_secs() + if timeout.subsec_nanos() > 0 { 1 } else { 0 };
+            cmp::min(secs, libc::c_int::max_value() as u64) as libc::c_int
+        }
+        None => 0,
+    };
+    sockopt::setsockopt(socket, libc::IPPROTO_TCP, libc::TCP_DEFER_ACCEPT, secs)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(super) fn defer_accept<T>(_: &T) -> io::Result<Option<Duration>> {
+    Ok(None)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(super) fn set_defer_accept<T>(_: &T, _: Option<Duration>) -> io::Result<()> {
+    Ok(())
+}
+
+/// Accepts a connection, returning it in nonblocking mode.
+///
+/// On Linux `accept4` makes the new socket nonblocking and close-on-exec right
+/// away, saving the extra `ioctl` that `mio::net::TcpStream::from_stream` would
+/// issue for every connection.
+#[cfg(target_os = "linux")]
+fn accept(listener: &mio::net::TcpListener) -> io::Result<(mio::net::TcpStream, SocketAddr)> {
+    use std::os::unix::io::FromRawFd;
+
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    let fd = unsafe {
+        libc::accept4(
+            listener.as_raw_fd(),
+            &mut storage as *mut _ as *mut libc::sockaddr,
+            &mut len,
+            libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let stream = unsafe { mio::net::TcpStream::from_raw_fd(fd) };
+    let addr = crate::sockaddr::to_socket_addr(&storage)?;
+    Ok((stream, addr))
+}
+
+/// Accepts a connection, returning it in nonblocking mode.
+///
+/// `std` makes the new socket close-on-exec before returning it.
+#[cfg(not(target_os = "linux"))]
+fn accept(listener: &mio::net::TcpListener) -> io::Result<(mio::net::TcpStream, SocketAddr)> {
+    let (stream, addr) = listener.accept_std()?;
+    Ok((mio::net::TcpStream::from_stream(stream)?, addr))
+}
+
+// ===== impl AcceptOptions =====
+
+impl AcceptOptions {
+    fn apply(&self, io: &mio::net::TcpStream) -> io::Result<()> {
+        match self.nodelay.load(SeqCst) {
+            UNSET => {}
+            nodelay => io.set_nodelay(nodelay != 0)?,
+        }
+        match self.ttl.load(SeqCst) {
+            UNSET => {}
+            ttl => io.set_ttl(ttl as u32)?,
+        }
+        Ok(())
+    }
+}
+
+impl Default for AcceptOptions {
+    fn default() -> AcceptOptions {
+        AcceptOptions {
+            nodelay: AtomicUsize::new(UNSET),
+            ttl: AtomicUsize::new(UNSET),
+        }
+    }
+}
+
+// ===== impl AcceptErrorPolicy =====
+
+impl AcceptErrorPolicy {
+    /// Turns a discriminant stored by `set_accept_error_policy` back into the
+    /// policy.
+    fn from_usize(policy: usize) -> AcceptErrorPolicy {
+        match policy {
+            p if p == AcceptErrorPolicy::ReportAll as usize => AcceptErrorPolicy::ReportAll,
+            p if p == AcceptErrorPolicy::Halt as usize => AcceptErrorPolicy::Halt,
+            _ => AcceptErrorPolicy::Continue,
+        }
+    }
+}
+
+impl Default for AcceptErrorPolicy {
+    fn default() -> AcceptErrorPolicy {
+        AcceptErrorPolicy::Continue
+    }
+}
+
+// ===== impl Acceptors =====
+
+impl Acceptors {
+    fn register(&self, lw: &LocalWaker) {
+        let waker = lw.clone().into_waker();
+        let mut wakers = self.wakers.lock().unwrap();
+
+        if !wakers.iter().any(|w| w.will_wake(&waker)) {
+            wakers.push(waker);
+        }
+    }
+}
+
+impl Wake for Acceptors {
+    fn wake(arc_self: &Arc<Self>) {
+        let wakers = mem::replace(&mut *arc_self.wakers.lock().unwrap(), Vec::new());
+
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+// ===== impl CloseHandle =====
+
+impl CloseHandle {
+    /// Closes the listener, waking up all tasks waiting for a connection.
+    pub fn close(&self) {
+        self.closed.store(true, SeqCst);
+        Wake::wake(&self.acceptors);
+    }
+
+    /// Returns `true` if the listener has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(SeqCst)
+    }
+}
+
+// ===== impl AcceptBatch =====
+
+impl<'a> Future for AcceptBatch<'a> {
+    type Output = io::Result<Vec<(TcpStream, SocketAddr)>>;
+
+    fn poll(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        let listener = self.listener;
+        let mut accepted = Vec::new();
+        ready!(listener.poll_accept_each(lw, self.max, |io, addr| {
+            accepted.push((listener.accepted(io, addr), addr))
+        })?);
+        Poll::Ready(Ok(accepted))
+    }
+}
+
+// ===== impl Accept =====
+
+impl<'a> Future for Accept<'a> {
+    type Output = io::Result<(TcpStream, SocketAddr)>;
+
+    fn poll(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        self.listener.poll_accept(lw)
+    }
+}
+
+impl<'a> Stream for IncomingWithAddr<'a> {
+    type Item = io::Result<(TcpStream, SocketAddr)>;
+
+    fn poll_next(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Option<Self::Item>> {
+        match ready!(self.listener.poll_accept(lw)) {
+            Ok(accepted) => Poll::Ready(Some(Ok(accepted))),
+            Err(_) if self.listener.is_closed() => Poll::Ready(None),
+            Err(e) => {
+                self.listener.halt_on_error(&e);
+                Poll::Ready(Some(Err(e)))
             }
-            Err(e) => Poll::Ready(Err(e)),
         }
     }
 }
@@ -196,6 +1492,29 @@ mod sys {
             self.io.get_ref().as_raw_fd()
         }
     }
+
+    impl IntoRawFd for TcpListener {
+        /// Consumes the listener, returning its file descriptor.
+        ///
+        /// The socket is deregistered from the reactor, and the caller becomes
+        /// responsible for closing it. It stays in nonblocking mode.
+        fn into_raw_fd(self) -> RawFd {
+            self.io.detach().into_raw_fd()
+        }
+    }
+
+    impl FromRawFd for TcpListener {
+        /// Creates a `TcpListener` from a raw file descriptor, taking ownership of
+        /// it.
+        ///
+        /// # Safety
+        ///
+        /// `fd` must be an open file descriptor for a listening TCP socket in nonblocking
+        /// mode, which isn't owned by anything else.
+        unsafe fn from_raw_fd(fd: RawFd) -> TcpListener {
+            TcpListener::new(mio::net::TcpListener::from_raw_fd(fd))
+        }
+    }
 }
 
 /// An implementation of the `Stream` trait which
@@ -204,9 +1523,13 @@ mod sys {
 /// # Errors
 ///
 /// Note that accepting a connection can lead to various errors and not all of them are
-/// necessarily fatal ‒ for example having too many open file descriptors or the other side
-/// closing the connection while it waits in an accept queue. These would terminate the stream
-/// if not handled in any way.
+/// necessarily fatal ‒ for example running out of memory or the other side closing the
+/// connection while it waits in an accept queue. By default the latter kind is skipped, while
+/// the former is yielded and the stream can be polled again afterwards. Running out of file
+/// descriptors doesn't end the stream either, the listener backs off and tries again. See
+/// [`AcceptErrorPolicy`] for the alternatives.
+///
+/// [`AcceptErrorPolicy`]: enum.AcceptErrorPolicy.html
 ///
 /// # Examples
 ///
@@ -233,8 +1556,14 @@ mod sys {
 impl Stream for TcpListener {
     type Item = io::Result<TcpStream>;
 
-    fn poll_next(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Option<Self::Item>> {
-        let (socket, _) = ready!(self.poll_accept(lw)?);
-        Poll::Ready(Some(Ok(socket)))
+    fn poll_next(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Option<Self::Item>> {
+        match ready!(self.poll_accept(lw)) {
+            Ok((socket, _)) => Poll::Ready(Some(Ok(socket))),
+            Err(_) if self.is_closed() => Poll::Ready(None),
+            Err(e) => {
+                self.halt_on_error(&e);
+                Poll::Ready(Some(Err(e)))
+            }
+        }
     }
 }
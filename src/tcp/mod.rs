@@ -39,8 +39,38 @@
 //! }
 //! ```
 
+mod buffered;
+mod builder;
+mod connect;
+mod fastopen;
+#[cfg(target_os = "linux")]
+mod info;
+mod limit;
 mod listener;
+mod reserve;
+mod sendfile;
+mod serve;
+mod splice;
 mod stream;
+mod throttle;
 
-pub use self::listener::{TcpListener};
-pub use self::stream::{ConnectFuture, TcpStream};
+pub use self::buffered::{BufTcpStream, ReadLine};
+pub use self::builder::{TcpListenerBuilder, TcpStreamBuilder};
+pub use self::connect::{ConnectHappyEyeballs, ConnectMulti, ConnectWithAddr};
+pub use self::fastopen::ConnectFastOpen;
+#[cfg(target_os = "linux")]
+pub use self::info::TcpInfo;
+pub use self::limit::{ConcurrencyPermit, LimitConcurrency};
+pub use self::listener::{
+    Accept, AcceptBatch, AcceptErrorPolicy, CloseHandle, IncomingWithAddr, TcpListener,
+};
+pub use self::serve::Serve;
+pub use self::splice::{copy_splice, CopySplice};
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+pub use self::stream::Corked;
+pub use self::stream::{
+    Closed, ConnectFuture, KeepaliveParams, ReadOwned, ReadTimeout, ReadyFuture, SendFile,
+    SendFileError, TcpStream, WriteTimeout,
+};
+#[cfg(unix)]
+pub use self::stream::{RecvOob, SendOob};
@@ -0,0 +1,135 @@
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+use futures::task::LocalWaker;
+use futures::{Future, Poll};
+use log::debug;
+
+use crate::reactor::Delay;
+
+/// How long a listener waits after running out of descriptors before
+/// accepting again, unless configured otherwise.
+pub(super) const DEFAULT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// A file descriptor held in reserve, so that a listener which ran out of
+/// descriptors can still take pending connections off its backlog.
+///
+/// If accept fails with `EMFILE`, the connection stays in the backlog and the
+/// listener remains readable, so accepting again fails right away. Closing the
+/// reserve frees a descriptor to accept the connection with and close it
+/// immediately. Either way accepting backs off for a moment afterwards, giving
+/// the process a chance to release some descriptors.
+pub(super) struct Reserve {
+    enabled: bool,
+    file: Option<File>,
+    backoff_duration: Duration,
+    backoff: Option<Delay>,
+}
+
+impl Default for Reserve {
+    fn default() -> Reserve {
+        Reserve {
+            enabled: false,
+            file: None,
+            backoff_duration: DEFAULT_BACKOFF,
+            backoff: None,
+        }
+    }
+}
+
+impl Reserve {
+    pub(super) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub(super) fn set_enabled(&mut self, enabled: bool) -> io::Result<()> {
+        self.file = if enabled { Some(open()?) } else { None };
+        self.enabled = enabled;
+        Ok(())
+    }
+
+    pub(super) fn backoff(&self) -> Duration {
+        self.backoff_duration
+    }
+
+    pub(super) fn set_backoff(&mut self, backoff: Duration) {
+        self.backoff_duration = backoff;
+    }
+
+    /// Returns `Ready` once a connection may be accepted.
+    pub(super) fn poll_ready(&mut self, lw: &LocalWaker) -> Poll<io::Result<()>> {
+        if let Some(backoff) = &mut self.backoff {
+            match Pin::new(backoff).poll(lw) {
+                Poll::Ready(Ok(())) => self.backoff = None,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Handles an error returned by accept, backing off if the process ran
+    /// out of descriptors, after shedding a pending connection if the reserve
+    /// is enabled.
+    ///
+    /// Returns `false` if backing off took care of the error, which isn't
+    /// passed on then.
+    pub(super) fn handle_error<F>(&mut self, e: &io::Error, accept: F) -> bool
+    where
+        F: FnOnce() -> io::Result<()>,
+    {
+        if !is_exhausted(e) {
+            return true;
+        }
+
+        self.backoff = Some(Delay::new(Instant::now() + self.backoff_duration));
+
+        if !self.enabled {
+            debug!("out of file descriptors, backing off: {}", e);
+            return false;
+        }
+
+        // If the reserve couldn't be reopened last time there is nothing to
+        // accept the connection with, but backing off still keeps the listener
+        // from spinning.
+        if self.file.take().is_some() {
+            if let Err(e) = accept() {
+                debug!("failed to shed connection: {}", e);
+            }
+        }
+        self.file = open().ok();
+        true
+    }
+}
+
+impl fmt::Debug for Reserve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reserve")
+            .field("enabled", &self.enabled)
+            .field("held", &self.file.is_some())
+            .field("backoff", &self.backoff_duration)
+            .finish()
+    }
+}
+
+fn open() -> io::Result<File> {
+    File::open("/dev/null")
+}
+
+/// Returns `true` if an error means the process or the system ran out of file
+/// descriptors.
+#[cfg(unix)]
+pub(super) fn is_exhausted(e: &io::Error) -> bool {
+    match e.raw_os_error() {
+        Some(libc::EMFILE) | Some(libc::ENFILE) => true,
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+pub(super) fn is_exhausted(_: &io::Error) -> bool {
+    false
+}
@@ -0,0 +1,176 @@
+use std::cmp;
+use std::fs::File;
+use std::io;
+
+use mio;
+
+/// Sends up to `len` bytes of `file` starting at `offset` over `socket`,
+/// returning the number of bytes sent.
+#[cfg(target_os = "linux")]
+pub(crate) fn send_file(
+    socket: &mio::net::TcpStream,
+    file: &File,
+    offset: u64,
+    len: u64,
+) -> io::Result<usize> {
+    use std::os::unix::prelude::*;
+
+    // Linux never transfers more than this in a single call.
+    const MAX_LEN: u64 = 0x7fff_f000;
+
+    let mut offset = offset as libc::off_t;
+    crate::sigpipe::without_sigpipe(|| {
+        let n = unsafe {
+            libc::sendfile(
+                socket.as_raw_fd(),
+                file.as_raw_fd(),
+                &mut offset,
+                cmp::min(len, MAX_LEN) as usize,
+            )
+        };
+        if n < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(n as usize)
+        }
+    })
+}
+
+/// Sends up to `len` bytes of `file` starting at `offset` over `socket`,
+/// returning the number of bytes sent.
+#[cfg(target_os = "macos")]
+pub(crate) fn send_file(
+    socket: &mio::net::TcpStream,
+    file: &File,
+    offset: u64,
+    len: u64,
+) -> io::Result<usize> {
+    use std::os::unix::prelude::*;
+    use std::ptr;
+
+    let mut sent = cmp::min(len, libc::off_t::max_value() as u64) as libc::off_t;
+    let r = unsafe {
+        libc::sendfile(
+            file.as_raw_fd(),
+            socket.as_raw_fd(),
+            offset as libc::off_t,
+            &mut sent,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if r < 0 {
+        let e = io::Error::last_os_error();
+        // A nonblocking send may have transferred some data before it would
+        // have blocked, which is still reported through `sent`.
+        if e.kind() == io::ErrorKind::WouldBlock && sent > 0 {
+            return Ok(sent as usize);
+        }
+        return Err(e);
+    }
+    Ok(sent as usize)
+}
+
+/// Sends up to `len` bytes of `file` starting at `offset` over `socket`,
+/// returning the number of bytes sent.
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+pub(crate) fn send_file(
+    socket: &mio::net::TcpStream,
+    file: &File,
+    offset: u64,
+    len: u64,
+) -> io::Result<usize> {
+    use std::os::unix::prelude::*;
+    use std::ptr;
+
+    let mut sent: libc::off_t = 0;
+    let res = crate::sigpipe::without_sigpipe(|| {
+        let r = unsafe {
+            libc::sendfile(
+                file.as_raw_fd(),
+                socket.as_raw_fd(),
+                offset as libc::off_t,
+                cmp::min(len, isize::max_value() as u64) as usize,
+                ptr::null_mut(),
+                &mut sent,
+                0,
+            )
+        };
+        if r < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    });
+    if let Err(e) = res {
+        // Like on macOS, a nonblocking send reports the data it transferred
+        // before it would have blocked through `sent`.
+        if e.kind() == io::ErrorKind::WouldBlock && sent > 0 {
+            return Ok(sent as usize);
+        }
+        return Err(e);
+    }
+    Ok(sent as usize)
+}
+
+/// Sends up to `len` bytes of `file` starting at `offset` over `socket`,
+/// returning the number of bytes sent.
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "dragonfly"
+)))]
+pub(crate) fn send_file(
+    socket: &mio::net::TcpStream,
+    file: &File,
+    offset: u64,
+    len: u64,
+) -> io::Result<usize> {
+    let mut buf = [0; 64 * 1024];
+    let max = cmp::min(len, buf.len() as u64) as usize;
+    let n = read_at(file, &mut buf[..max], offset)?;
+
+    // Anything that doesn't fit into the socket is read again on the next
+    // call, as the caller only advances the offset by what was sent.
+    write(socket, &buf[..n])
+}
+
+#[cfg(all(
+    unix,
+    not(any(
+        target_os = "linux",
+        target_os = "macos",
+        target_os = "freebsd",
+        target_os = "dragonfly"
+    ))
+))]
+fn write(socket: &mio::net::TcpStream, buf: &[u8]) -> io::Result<usize> {
+    crate::sigpipe::send(socket, buf)
+}
+
+#[cfg(windows)]
+fn write(socket: &mio::net::TcpStream, buf: &[u8]) -> io::Result<usize> {
+    use std::io::Write;
+    (&*socket).write(buf)
+}
+
+#[cfg(all(
+    unix,
+    not(any(
+        target_os = "linux",
+        target_os = "macos",
+        target_os = "freebsd",
+        target_os = "dragonfly"
+    ))
+))]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    use std::os::unix::fs::FileExt;
+    file.read_at(buf, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    use std::os::windows::fs::FileExt;
+    file.seek_read(buf, offset)
+}
@@ -0,0 +1,171 @@
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Arc;
+
+use futures::task::{AtomicWaker, LocalWaker, Spawn, SpawnExt};
+use futures::{Future, Poll};
+use log::debug;
+
+use super::{TcpListener, TcpStream};
+
+/// The future returned by `TcpListener::serve`.
+///
+/// It accepts connections and spawns a task for each of them until the
+/// shutdown future completes, or an accept error closes the listener under
+/// `AcceptErrorPolicy::Halt`. It then closes the listener and resolves once
+/// every spawned connection task has finished.
+#[must_use = "futures do nothing unless polled"]
+pub struct Serve<S, F, Sd> {
+    listener: Option<TcpListener>,
+    spawner: S,
+    handler: F,
+    shutdown: Option<Pin<Box<Sd>>>,
+    in_flight: Arc<InFlight>,
+    /// The accept error which halted the listener, returned once the
+    /// connections in flight are done.
+    error: Option<io::Error>,
+}
+
+/// Tracks the number of connection tasks which haven't finished yet.
+#[derive(Debug, Default)]
+struct InFlight {
+    count: AtomicUsize,
+    waker: AtomicWaker,
+}
+
+/// Decrements the in-flight count when a connection task completes or is
+/// dropped by the executor.
+struct Guard {
+    in_flight: Arc<InFlight>,
+}
+
+/// A connection task, tracked by a `Serve` future.
+struct Tracked<Fut> {
+    future: Pin<Box<Fut>>,
+    _guard: Guard,
+}
+
+impl<S, F, Sd> Serve<S, F, Sd> {
+    pub(super) fn new(listener: TcpListener, spawner: S, shutdown: Sd, handler: F) -> Self {
+        Serve {
+            listener: Some(listener),
+            spawner,
+            handler,
+            shutdown: Some(Box::pinned(shutdown)),
+            in_flight: Arc::new(InFlight::default()),
+            error: None,
+        }
+    }
+}
+
+// `Serve` never pins its fields, so it can be moved freely.
+impl<S, F, Sd> Unpin for Serve<S, F, Sd> {}
+
+impl<S, F, Fut, Sd> Future for Serve<S, F, Sd>
+where
+    S: Spawn,
+    F: FnMut(TcpStream, SocketAddr) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+    Sd: Future<Output = ()>,
+{
+    type Output = io::Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+
+        // Stop accepting as soon as the shutdown future completes. Dropping the
+        // listener closes the socket, so new connections are refused.
+        if let Some(shutdown) = &mut this.shutdown {
+            if shutdown.as_mut().poll(lw).is_ready() {
+                this.shutdown = None;
+                this.listener = None;
+            }
+        }
+
+        if let Some(listener) = &this.listener {
+            loop {
+                match listener.poll_accept(lw) {
+                    Poll::Ready(Ok((stream, addr))) => {
+                        this.in_flight.count.fetch_add(1, SeqCst);
+
+                        let task = Tracked {
+                            future: Box::pinned((this.handler)(stream, addr)),
+                            _guard: Guard {
+                                in_flight: this.in_flight.clone(),
+                            },
+                        };
+
+                        this.spawner
+                            .spawn(task)
+                            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{:?}", e)))?;
+                    }
+                    // Closing the listener through its `CloseHandle` shuts
+                    // down like the shutdown future.
+                    Poll::Ready(Err(_)) if listener.is_closed() => break,
+                    Poll::Ready(Err(e)) => {
+                        // Only the `Halt` policy gives up on the listener
+                        // over an error, the connections in flight are still
+                        // drained then.
+                        listener.halt_on_error(&e);
+                        if listener.is_closed() {
+                            this.error = Some(e);
+                            break;
+                        }
+
+                        // The error may well persist, so try again only after
+                        // the other tasks had their turn.
+                        debug!("failed to accept a connection: {}", e);
+                        lw.wake();
+                        return Poll::Pending;
+                    }
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+
+            this.shutdown = None;
+            this.listener = None;
+        }
+
+        // Drain the in-flight connections. Register before checking the count
+        // so the wakeup from the last task can't be missed.
+        this.in_flight.waker.register(lw);
+
+        if this.in_flight.count.load(SeqCst) == 0 {
+            match this.error.take() {
+                Some(e) => Poll::Ready(Err(e)),
+                None => Poll::Ready(Ok(())),
+            }
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl<S, F, Sd> fmt::Debug for Serve<S, F, Sd> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Serve")
+            .field("listener", &self.listener)
+            .field("in_flight", &self.in_flight.count.load(SeqCst))
+            .finish()
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if self.in_flight.count.fetch_sub(1, SeqCst) == 1 {
+            self.in_flight.waker.wake();
+        }
+    }
+}
+
+impl<Fut: Future<Output = ()>> Future for Tracked<Fut> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<()> {
+        self.future.as_mut().poll(lw)
+    }
+}
@@ -0,0 +1,258 @@
+use std::fmt;
+use std::io;
+#[cfg(target_os = "linux")]
+use std::os::unix::io::AsRawFd;
+use std::pin::Pin;
+
+use futures::io::{AsyncRead, AsyncWrite};
+use futures::task::LocalWaker;
+use futures::{ready, Future, Poll};
+
+use super::TcpStream;
+
+/// The size of the buffer used to copy through userspace, and the amount of
+/// data moved into the pipe by a single `splice` call.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Copies all data from `reader` into `writer` until `reader` reaches end of
+/// stream.
+///
+/// On Linux the data is moved inside the kernel with `splice(2)` and an
+/// intermediate pipe, so it never has to be copied through userspace. On other
+/// platforms, or if the kernel refuses to splice the sockets, the data is
+/// copied through a buffer instead.
+///
+/// The returned future resolves to the total number of bytes copied.
+///
+/// # Examples
+///
+/// ```no_run
+/// #![feature(async_await, await_macro, futures_api)]
+/// use romio::tcp::{self, TcpStream};
+///
+/// # async fn proxy(mut client: TcpStream) -> std::io::Result<()> {
+/// let addr = "127.0.0.1:8080".parse().unwrap();
+/// let mut server = await!(TcpStream::connect(&addr))?;
+/// let copied = await!(tcp::copy_splice(&mut client, &mut server))?;
+/// println!("proxied {} bytes", copied);
+/// # Ok(()) }
+/// ```
+pub fn copy_splice<'a>(reader: &'a mut TcpStream, writer: &'a mut TcpStream) -> CopySplice<'a> {
+    CopySplice {
+        reader,
+        writer,
+        amt: 0,
+        read_done: false,
+        mode: Mode::Start,
+    }
+}
+
+/// The future returned by `copy_splice`.
+#[must_use = "futures do nothing unless polled"]
+pub struct CopySplice<'a> {
+    reader: &'a mut TcpStream,
+    writer: &'a mut TcpStream,
+    amt: u64,
+    read_done: bool,
+    mode: Mode,
+}
+
+enum Mode {
+    Start,
+    #[cfg(target_os = "linux")]
+    Splice {
+        pipe: sys::Pipe,
+        in_pipe: usize,
+    },
+    Buffered {
+        buf: Box<[u8]>,
+        pos: usize,
+        cap: usize,
+    },
+}
+
+impl Mode {
+    fn buffered() -> Mode {
+        Mode::Buffered {
+            buf: vec![0; CHUNK_SIZE].into_boxed_slice(),
+            pos: 0,
+            cap: 0,
+        }
+    }
+}
+
+impl<'a> Future for CopySplice<'a> {
+    type Output = io::Result<u64>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<u64>> {
+        let this = &mut *self;
+
+        loop {
+            match &mut this.mode {
+                Mode::Start => {
+                    #[cfg(target_os = "linux")]
+                    {
+                        this.mode = Mode::Splice {
+                            pipe: sys::Pipe::new()?,
+                            in_pipe: 0,
+                        };
+                    }
+                    #[cfg(not(target_os = "linux"))]
+                    {
+                        this.mode = Mode::buffered();
+                    }
+                }
+
+                #[cfg(target_os = "linux")]
+                Mode::Splice { pipe, in_pipe } => {
+                    // Drain the pipe into the writer before reading any more.
+                    if *in_pipe > 0 {
+                        ready!(this.writer.poll_write_ready(lw)?);
+
+                        match sys::splice(pipe.read, this.writer.as_raw_fd(), *in_pipe) {
+                            Ok(0) => {
+                                return Poll::Ready(Err(io::Error::new(
+                                    io::ErrorKind::WriteZero,
+                                    "write zero byte into writer",
+                                )));
+                            }
+                            Ok(n) => {
+                                *in_pipe -= n;
+                                this.amt += n as u64;
+                            }
+                            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                                this.writer.clear_write_ready(lw)?;
+                                return Poll::Pending;
+                            }
+                            Err(e) => return Poll::Ready(Err(e)),
+                        }
+                        continue;
+                    }
+
+                    if this.read_done {
+                        return Poll::Ready(Ok(this.amt));
+                    }
+
+                    ready!(this.reader.poll_read_ready(lw)?);
+
+                    match sys::splice(this.reader.as_raw_fd(), pipe.write, CHUNK_SIZE) {
+                        Ok(0) => this.read_done = true,
+                        Ok(n) => *in_pipe = n,
+                        Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                            this.reader.clear_read_ready(lw)?;
+                            return Poll::Pending;
+                        }
+                        // The kernel can't splice this socket, copy through
+                        // userspace instead. Nothing has been moved yet.
+                        Err(ref e) if e.raw_os_error() == Some(libc::EINVAL) && this.amt == 0 => {
+                            this.mode = Mode::buffered();
+                        }
+                        Err(e) => return Poll::Ready(Err(e)),
+                    }
+                }
+
+                Mode::Buffered { buf, pos, cap } => {
+                    // If our buffer is empty, then we need to read some data to
+                    // continue.
+                    if *pos == *cap && !this.read_done {
+                        let n = ready!(this.reader.poll_read(lw, buf))?;
+                        if n == 0 {
+                            this.read_done = true;
+                        } else {
+                            *pos = 0;
+                            *cap = n;
+                        }
+                    }
+
+                    // If our buffer has some data, let's write it out!
+                    while *pos < *cap {
+                        let i = ready!(this.writer.poll_write(lw, &buf[*pos..*cap]))?;
+                        if i == 0 {
+                            return Poll::Ready(Err(io::Error::new(
+                                io::ErrorKind::WriteZero,
+                                "write zero byte into writer",
+                            )));
+                        }
+                        *pos += i;
+                        this.amt += i as u64;
+                    }
+
+                    if *pos == *cap && this.read_done {
+                        ready!(this.writer.poll_flush(lw))?;
+                        return Poll::Ready(Ok(this.amt));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a> fmt::Debug for CopySplice<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CopySplice")
+            .field("reader", &self.reader)
+            .field("writer", &self.writer)
+            .field("amt", &self.amt)
+            .finish()
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::io;
+    use std::os::unix::prelude::*;
+    use std::ptr;
+
+    use libc;
+
+    /// A nonblocking pipe, closed on drop.
+    pub(super) struct Pipe {
+        pub(super) read: RawFd,
+        pub(super) write: RawFd,
+    }
+
+    impl Pipe {
+        pub(super) fn new() -> io::Result<Pipe> {
+            let mut fds = [0; 2];
+            if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Pipe {
+                read: fds[0],
+                write: fds[1],
+            })
+        }
+    }
+
+    impl Drop for Pipe {
+        fn drop(&mut self) {
+            unsafe {
+                libc::close(self.read);
+                libc::close(self.write);
+            }
+        }
+    }
+
+    /// Moves up to `len` bytes from `from` to `to`, one of which has to be a
+    /// pipe.
+    pub(super) fn splice(from: RawFd, to: RawFd, len: usize) -> io::Result<usize> {
+        // `splice` has no flag like `MSG_NOSIGNAL`.
+        crate::sigpipe::without_sigpipe(|| {
+            let n = unsafe {
+                libc::splice(
+                    from,
+                    ptr::null_mut(),
+                    to,
+                    ptr::null_mut(),
+                    len,
+                    libc::SPLICE_F_MOVE | libc::SPLICE_F_NONBLOCK,
+                )
+            };
+            if n < 0 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(n as usize)
+            }
+        })
+    }
+}
@@ -1,17 +1,40 @@
+use std::error::Error;
 use std::fmt;
-use std::io;
+use std::fs::File;
+use std::io::{self, Read};
 use std::mem;
-use std::net::{Shutdown, SocketAddr};
+use std::net::{self, Shutdown, SocketAddr};
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+use std::ops::{Deref, DerefMut};
 use std::pin::Pin;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use futures::io::{AsyncRead, AsyncWrite};
+use bytes::BytesMut;
+use futures::io::{AsyncRead, AsyncWrite, Initializer};
 use futures::task::LocalWaker;
 use futures::{ready, Future, Poll};
 use iovec::IoVec;
 use mio;
 
-use crate::reactor::PollEvented;
+use crate::io::{AddrError, Interest, Operation, Readiness};
+use crate::reactor::{platform, Delay, PollEvented};
+
+use super::buffered::BufTcpStream;
+use super::connect::{ConnectHappyEyeballs, ConnectMulti, ConnectWithAddr};
+use super::fastopen::ConnectFastOpen;
+#[cfg(target_os = "linux")]
+use super::info::TcpInfo;
+use super::sendfile;
+
+#[cfg(unix)]
+use self::sys::{keepalive_probes, set_keepalive_probes};
+#[cfg(unix)]
+pub(crate) use self::sys::{mss, set_mss};
+#[cfg(target_os = "linux")]
+use self::sys::{quickack, set_quickack};
+
+/// The space reserved by `TcpStream::read_owned` in a buffer without any.
+const READ_OWNED_CHUNK: usize = 8 * 1024;
 
 /// A TCP stream between a local and a remote socket.
 ///
@@ -24,15 +47,36 @@ use crate::reactor::PollEvented;
 /// portions of the connection can also be shut down individually with the [`shutdown`]
 /// method.
 ///
+/// Once the peer closes its end of the connection, reads return `Ok(0)`. If the
+/// peer resets the connection instead, reads fail with
+/// `io::ErrorKind::ConnectionReset`. Writing to a connection which was closed
+/// fails with `io::ErrorKind::BrokenPipe`, without raising `SIGPIPE`, so it
+/// doesn't kill the process even if it doesn't ignore the signal.
+///
+/// A `TcpStream` doesn't buffer writes: a completed write has been copied into
+/// the socket's send buffer in the kernel, which sends it on its own. Flushing
+/// is therefore a no-op which completes right away. It doesn't wait for the
+/// data to be sent, or acknowledged by the peer. Wrappers which do buffer
+/// writes, like `codec::Framed`, write out all of their buffer on flush.
+///
 /// [`connect`]: struct.TcpStream.html#method.connect
 /// [accepting]: struct.TcpListener.html#method.accept
 /// [listener]: struct.TcpListener.html
 pub struct TcpStream {
     io: PollEvented<mio::net::TcpStream>,
+    /// The local address, if it was known when the stream was created or once
+    /// it connected.
+    local_addr: Option<SocketAddr>,
+    /// The peer address, if it was known when the stream was created.
+    peer_addr: Option<SocketAddr>,
 }
 
 /// The future returned by `TcpStream::connect`, which will resolve to a `TcpStream`
 /// when the stream is connected.
+///
+/// Dropping the future before it completes cancels the connection attempt:
+/// the socket is deregistered from the reactor and closed, like a dropped
+/// `TcpStream`. This makes it safe to race a connect against other futures.
 #[must_use = "futures do nothing unless polled"]
 #[derive(Debug)]
 pub struct ConnectFuture {
@@ -43,7 +87,7 @@ pub struct ConnectFuture {
 #[derive(Debug)]
 enum ConnectFutureState {
     Waiting(TcpStream),
-    Error(io::Error),
+    Error(AddrError),
     Empty,
 }
 
@@ -64,23 +108,300 @@ impl TcpStream {
     ///
     /// # async fn connect_localhost() -> io::Result<TcpStream> {
     /// let addr = "127.0.0.1".parse().unwrap();
-    /// await!(TcpStream::connect(&addr))
+    /// Ok(await!(TcpStream::connect(&addr))?)
     /// # }
     /// ```
     pub fn connect(addr: &SocketAddr) -> ConnectFuture {
         use self::ConnectFutureState::*;
 
         let inner = match mio::net::TcpStream::connect(addr) {
-            Ok(tcp) => Waiting(TcpStream::new(tcp)),
-            Err(e) => Error(e),
+            Ok(tcp) => Waiting(TcpStream::with_addrs(tcp, None, Some(*addr))),
+            Err(e) => Error(AddrError::new(Operation::Connect, addr, e)),
+        };
+
+        ConnectFuture { inner }
+    }
+
+    /// Connects an existing, unconnected socket to the specified address.
+    ///
+    /// This allows the socket to be configured before connecting, for example
+    /// by binding it to a specific local address with a crate such as `net2`.
+    /// The nonblocking connect is issued right away, and the returned future
+    /// resolves once the stream has connected or an error occurred.
+    ///
+    /// On Windows the socket must already be bound, since the connect is
+    /// issued as an overlapped operation.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// # use std::io;
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn connect_localhost() -> io::Result<TcpStream> {
+    /// let socket = net2::TcpBuilder::new_v4()?
+    ///     .bind("127.0.0.2:0")?
+    ///     .to_tcp_stream()?;
+    /// let addr = "127.0.0.1:8080".parse().unwrap();
+    /// Ok(await!(TcpStream::connect_from_std(socket, &addr))?)
+    /// # }
+    /// ```
+    pub fn connect_from_std(stream: net::TcpStream, addr: &SocketAddr) -> ConnectFuture {
+        use self::ConnectFutureState::*;
+
+        let inner = match mio::net::TcpStream::connect_stream(stream, addr) {
+            Ok(tcp) => Waiting(TcpStream::with_addrs(tcp, None, Some(*addr))),
+            Err(e) => Error(AddrError::new(Operation::Connect, addr, e)),
+        };
+
+        ConnectFuture { inner }
+    }
+
+    /// Connects a socket configured with `socket2` to the specified address.
+    ///
+    /// This is [`connect_from_std`] for a `socket2::Socket`, to set options
+    /// this crate doesn't offer, like `IP_FREEBIND`, before connecting. The
+    /// socket must be an unconnected TCP socket. This requires the `socket2`
+    /// feature.
+    ///
+    /// [`connect_from_std`]: #method.connect_from_std
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// # use std::io;
+    /// use romio::tcp::TcpStream;
+    /// use socket2::{Domain, Socket, Type};
+    ///
+    /// # async fn connect_localhost() -> io::Result<TcpStream> {
+    /// let socket = Socket::new(Domain::ipv4(), Type::stream(), None)?;
+    /// socket.set_recv_buffer_size(1 << 20)?;
+    /// let addr = "127.0.0.1:8080".parse().unwrap();
+    /// Ok(await!(TcpStream::connect_std(socket, &addr))?)
+    /// # }
+    /// ```
+    #[cfg(feature = "socket2")]
+    pub fn connect_std(socket: socket2::Socket, addr: &SocketAddr) -> ConnectFuture {
+        TcpStream::connect_from_std(socket.into_tcp_stream(), addr)
+    }
+
+    /// Create a new TCP stream connected to `remote`, originating from the
+    /// `local` address.
+    ///
+    /// The socket is bound to `local` before the connect is issued, which is
+    /// useful on multi-homed hosts to pick the interface a connection goes
+    /// out of. A port of `0` lets the OS pick an ephemeral port.
+    ///
+    /// If binding fails, the error keeps the kind reported by the OS, usually
+    /// `AddrInUse` or `AddrNotAvailable`, and its message names the local
+    /// address, which tells it apart from an error connecting. To set further
+    /// options before connecting, use [`TcpStreamBuilder::local_addr`].
+    ///
+    /// [`TcpStreamBuilder::local_addr`]: struct.TcpStreamBuilder.html#method.local_addr
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// # use std::io;
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn connect_localhost() -> io::Result<TcpStream> {
+    /// let local = "127.0.0.2:0".parse().unwrap();
+    /// let remote = "127.0.0.1:8080".parse().unwrap();
+    /// Ok(await!(TcpStream::connect_bound(&local, &remote))?)
+    /// # }
+    /// ```
+    pub fn connect_bound(local: &SocketAddr, remote: &SocketAddr) -> ConnectFuture {
+        let connect_error = |e| AddrError::new(Operation::Connect, remote, e);
+        let builder = match local {
+            SocketAddr::V4(..) => net2::TcpBuilder::new_v4(),
+            SocketAddr::V6(..) => net2::TcpBuilder::new_v6(),
+        };
+        let bind = builder.map_err(connect_error).and_then(|builder| {
+            bind_local(&builder, local)?;
+            builder.to_tcp_stream().map_err(connect_error)
+        });
+
+        match bind {
+            Ok(stream) => TcpStream::connect_from_std(stream, remote),
+            Err(e) => ConnectFuture::error(e),
+        }
+    }
+
+    /// Connects to the first of several addresses which accepts the
+    /// connection.
+    ///
+    /// The addresses are tried in order, as described by RFC 8305 ("Happy
+    /// Eyeballs"): if an attempt hasn't completed after a short delay, the
+    /// next one is started in parallel rather than waiting for the first one
+    /// to time out, and a failed attempt starts the next one right away. The
+    /// first connection established wins and the remaining attempts are
+    /// aborted. The delay defaults to 250 milliseconds, and can be changed with
+    /// [`ConnectMulti::attempt_delay`].
+    ///
+    /// If every attempt fails, the returned error lists the failure for each
+    /// address.
+    ///
+    /// [`ConnectMulti::attempt_delay`]: struct.ConnectMulti.html#method.attempt_delay
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// # use std::io;
+    /// use romio::resolver;
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn connect_rust_lang() -> io::Result<TcpStream> {
+    /// let addrs = await!(resolver::resolve("www.rust-lang.org", 443))?;
+    /// await!(TcpStream::connect_multi(&addrs))
+    /// # }
+    /// ```
+    pub fn connect_multi(addrs: &[SocketAddr]) -> ConnectMulti {
+        ConnectMulti::new(addrs)
+    }
+
+    /// Resolves `host` and connects to the first of its addresses which
+    /// accepts the connection, as described by RFC 8305 ("Happy Eyeballs").
+    ///
+    /// The host is resolved with [`resolver::resolve`], then the addresses are
+    /// tried like with [`connect_multi`], alternating between IPv6 and IPv4
+    /// and starting with IPv6. This way a host whose IPv6 connectivity is
+    /// broken is still reached over IPv4 after the attempt delay, rather than
+    /// after the IPv6 attempt times out. The attempts which lose the race are
+    /// aborted and their sockets closed, as is every attempt if the returned
+    /// future is dropped.
+    ///
+    /// [`resolver::resolve`]: ../resolver/fn.resolve.html
+    /// [`connect_multi`]: #method.connect_multi
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// # use std::io;
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn connect_rust_lang() -> io::Result<TcpStream> {
+    /// await!(TcpStream::connect_happy_eyeballs("www.rust-lang.org", 443))
+    /// # }
+    /// ```
+    pub fn connect_happy_eyeballs(host: &str, port: u16) -> ConnectHappyEyeballs {
+        ConnectHappyEyeballs::new(host, port)
+    }
+
+    /// Connects to `addr`, sending `initial_data` along with the connection
+    /// request using TCP Fast Open where possible.
+    ///
+    /// On Linux the data is passed to `sendto` with `MSG_FASTOPEN`, and on
+    /// macOS to `connectx`. If the kernel has a Fast Open cookie for the
+    /// server, the data (or a prefix of it) is carried in the SYN, saving a
+    /// round trip. Otherwise, or on platforms without Fast Open support, this
+    /// falls back to a regular connect followed by a write.
+    ///
+    /// The returned future resolves once the connection is established and
+    /// all of `initial_data` has been written, with the stream and the number
+    /// of bytes which were sent in the SYN.
+    ///
+    /// Data sent in the SYN may be delivered more than once, so it should be
+    /// idempotent for the server.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// # use std::io;
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn connect_localhost() -> io::Result<TcpStream> {
+    /// let addr = "127.0.0.1:8080".parse().unwrap();
+    /// let (stream, _) = await!(TcpStream::connect_fastopen(&addr, b"GET / HTTP/1.0\r\n\r\n"))?;
+    /// # Ok(stream)
+    /// # }
+    /// ```
+    pub fn connect_fastopen<'a>(addr: &SocketAddr, initial_data: &'a [u8]) -> ConnectFastOpen<'a> {
+        ConnectFastOpen::new(addr, initial_data)
+    }
+
+    /// Wraps a socket whose nonblocking connect to `addr` has already been
+    /// issued.
+    pub(crate) fn connecting(stream: net::TcpStream, addr: &SocketAddr) -> ConnectFuture {
+        use self::ConnectFutureState::*;
+
+        let inner = match mio::net::TcpStream::from_stream(stream) {
+            Ok(tcp) => Waiting(TcpStream::with_addrs(tcp, None, Some(*addr))),
+            Err(e) => Error(AddrError::new(Operation::Connect, addr, e)),
         };
 
         ConnectFuture { inner }
     }
 
     pub(crate) fn new(connected: mio::net::TcpStream) -> TcpStream {
-        let io = PollEvented::new(connected);
-        TcpStream { io }
+        TcpStream::with_addrs(connected, None, None)
+    }
+
+    /// Creates a stream whose addresses are already known, so that
+    /// `local_addr` and `peer_addr` don't have to ask the socket.
+    pub(crate) fn with_addrs(
+        connected: mio::net::TcpStream,
+        local_addr: Option<SocketAddr>,
+        peer_addr: Option<SocketAddr>,
+    ) -> TcpStream {
+        // Failing to set the option only means that writing to a closed
+        // connection raises SIGPIPE, which Rust programs ignore by default.
+        #[cfg(unix)]
+        drop(crate::sigpipe::set_nosigpipe(&connected));
+
+        let io = PollEvented::new_ready(connected);
+        TcpStream {
+            io,
+            local_addr,
+            peer_addr,
+        }
+    }
+
+    /// Returns the address the stream was connecting to.
+    ///
+    /// Every stream created by connecting remembers it.
+    pub(super) fn connect_addr(&self) -> SocketAddr {
+        self.peer_addr.expect("stream wasn't created by connecting")
+    }
+
+    /// Waits for the connect issued on the stream to complete, recording the
+    /// local address it was bound to once it has.
+    fn poll_connected(&mut self, lw: &LocalWaker) -> Poll<io::Result<()>> {
+        // Once we've connected, wait for the stream to be writable as that's
+        // when the actual connection has been initiated. Once we're writable
+        // we check for `take_socket_error` to see if the connect actually hit
+        // an error or not. A failed connect may only signal an error or HUP
+        // rather than writability, which wakes us up too.
+        let ready = ready!(self.io.poll_write_ready(lw)?);
+
+        if let Some(e) = self.take_error()? {
+            return Poll::Ready(Err(e));
+        }
+
+        // Without a pending error the stream should be connected now. If it
+        // isn't, the wakeup was spurious and we wait again, unless the socket
+        // was already shut down, in which case the connect failed without the
+        // error being reported.
+        // The socket has to be asked, the address the stream remembers is
+        // only where it is connecting to.
+        let hup = platform::is_hup(&ready);
+        match self.io.get_ref().peer_addr() {
+            Ok(_) => {
+                self.local_addr = self.io.get_ref().local_addr().ok();
+                Poll::Ready(Ok(()))
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::NotConnected && !hup => {
+                self.io.clear_write_ready(lw)?;
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(e)),
+        }
     }
 
     /// Poll the TCP stream's readiness for reading.
@@ -112,103 +433,269 @@ impl TcpStream {
         self.io.poll_write_ready(lw)
     }
 
-    /// Returns the local address that this stream is bound to.
+    /// Waits until the stream is ready for any of the events in `interest`,
+    /// without performing any I/O.
+    ///
+    /// This is the building block for driving the socket with system calls
+    /// this crate doesn't wrap. The returned future resolves to the readiness
+    /// of the stream, which may include more events than requested, and
+    /// always includes closed readiness once the connection is closed.
+    ///
+    /// `interest` is an [`Interest`], or a `mio::Ready` of which only the
+    /// readable and writable bits are used.
+    ///
+    /// Readiness is edge-triggered: the reactor reports a change of readiness
+    /// once, and the stream remembers it until an operation fails with
+    /// `WouldBlock`. Until then the future resolves right away, so the
+    /// operation should be retried until it would block, and run through
+    /// [`try_io`] (or be one of [`try_read`] and [`try_write`]) so that the
+    /// readiness is cleared when it does. Once it is cleared, the future
+    /// resolves as soon as the stream becomes ready again, even if that
+    /// happened right after the failed attempt. Readiness may also be a false
+    /// positive, in which case the first attempt already fails with
+    /// `WouldBlock`. A new stream starts out readable and writable, so that
+    /// data which already arrived is read without waiting for the reactor.
+    ///
+    /// [`Interest`]: ../io/struct.Interest.html
+    /// [`try_io`]: #method.try_io
+    /// [`try_read`]: #method.try_read
+    /// [`try_write`]: #method.try_write
     ///
     /// # Examples
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::io::Interest;
     /// use romio::tcp::TcpStream;
-    /// use std::net::{IpAddr, Ipv4Addr};
+    /// use std::io;
+    ///
+    /// # async fn run (stream: TcpStream) -> io::Result<()> {
+    /// loop {
+    ///     await!(stream.ready(Interest::READABLE))?;
+    ///
+    ///     match stream.try_io(Interest::READABLE, || issue_custom_syscall(&stream)) {
+    ///         Ok(()) => break,
+    ///         Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+    ///         Err(e) => return Err(e),
+    ///     }
+    /// }
+    /// # Ok(())}
+    /// # fn issue_custom_syscall(_: &TcpStream) -> io::Result<()> { Ok(()) }
+    /// ```
+    pub fn ready(&self, interest: impl Into<Interest>) -> ReadyFuture<'_> {
+        ReadyFuture {
+            stream: self,
+            interest: interest.into().to_mio(),
+        }
+    }
+
+    /// Runs the I/O operation `f` on the stream, clearing the readiness for
+    /// `interest` if it fails with `WouldBlock`.
     ///
-    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
-    /// let addr = "127.0.0.1:8080".parse()?;
-    /// let stream = await!(TcpStream::connect(&addr))?;
+    /// Afterwards [`ready`] waits for the reactor to report the stream ready
+    /// again, instead of resolving right away with stale readiness. The
+    /// result of `f` is returned as is, including the `WouldBlock` error.
     ///
-    /// let expected = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
-    /// assert_eq!(stream.local_addr()?.ip(), expected);
+    /// `f` should only perform the I/O operations `interest` stands for, for
+    /// example a `recv` for readable interest, so that a readiness event isn't
+    /// cleared without a matching operation being attempted.
+    ///
+    /// [`ready`]: #method.ready
+    pub fn try_io<R, F>(&self, interest: impl Into<Interest>, f: F) -> io::Result<R>
+    where
+        F: FnOnce() -> io::Result<R>,
+    {
+        let r = f();
+        if is_wouldblock(&r) {
+            self.io.clear_ready(interest.into().to_mio());
+        }
+        r
+    }
+
+    /// Reads from the stream into `buf` with a single nonblocking system call,
+    /// without waiting for readiness.
+    ///
+    /// If no data is available, this fails with `WouldBlock` and clears the
+    /// readable readiness of the stream, so that awaiting
+    /// [`ready(Interest::READABLE)`] afterwards resolves once data arrives.
+    /// Otherwise this behaves like a regular read, returning `Ok(0)` once the
+    /// peer closed its end of the connection.
+    ///
+    /// [`ready(Interest::READABLE)`]: #method.ready
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::io::Interest;
+    /// use romio::tcp::TcpStream;
+    /// use std::io;
+    ///
+    /// # async fn run (stream: TcpStream) -> io::Result<()> {
+    /// let mut buf = [0; 4096];
+    /// let n = loop {
+    ///     await!(stream.ready(Interest::READABLE))?;
+    ///
+    ///     match stream.try_read(&mut buf) {
+    ///         Ok(n) => break n,
+    ///         Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+    ///         Err(e) => return Err(e),
+    ///     }
+    /// };
+    /// println!("read {} bytes", n);
     /// # Ok(())}
     /// ```
-    pub fn local_addr(&self) -> io::Result<SocketAddr> {
-        self.io.get_ref().local_addr()
+    pub fn try_read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.try_io(Interest::READABLE, || self.io.get_ref().read(buf))
     }
 
-    /// Returns the remote address that this stream is connected to.
+    /// Writes `buf` to the stream with a single nonblocking system call,
+    /// without waiting for readiness.
+    ///
+    /// If the send buffer is full, this fails with `WouldBlock` and clears
+    /// the writable readiness of the stream, so that awaiting
+    /// [`ready(Interest::WRITABLE)`] afterwards resolves once there is room
+    /// again.
+    ///
+    /// [`ready(Interest::WRITABLE)`]: #method.ready
+    pub fn try_write(&self, buf: &[u8]) -> io::Result<usize> {
+        self.try_io(Interest::WRITABLE, || send(self, buf))
+    }
+
+    /// Reads some bytes into `buf`, failing with an `ErrorKind::TimedOut`
+    /// error if nothing arrives within `dur`.
+    ///
+    /// This is the async counterpart of `std::net::TcpStream::set_read_timeout`,
+    /// but it applies to this read only. The timeout is driven by the
+    /// reactor's timer and starts running right away, not when the future is
+    /// first polled.
+    ///
+    /// The future resolves to the number of bytes read, where 0 means the peer
+    /// closed its end of the connection, as soon as any data is available. A
+    /// timeout therefore means that nothing was read into `buf`, so no data is
+    /// lost, and the stream can be read from again.
     ///
     /// # Examples
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// #![feature(async_await, await_macro, futures_api)]
     /// use romio::tcp::TcpStream;
-    /// use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+    /// use std::time::Duration;
     ///
     /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
     /// let addr = "127.0.0.1:8080".parse()?;
     /// let stream = await!(TcpStream::connect(&addr))?;
     ///
-    /// let expected = SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 8080);
-    /// assert_eq!(stream.peer_addr()?, SocketAddr::V4(expected));
+    /// let mut buf = [0; 1024];
+    /// let n = await!(stream.read_timeout(&mut buf, Duration::from_secs(5)))?;
+    /// println!("read {} bytes", n);
     /// # Ok(())}
     /// ```
-    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
-        self.io.get_ref().peer_addr()
+    pub fn read_timeout<'a, 'b>(&'a self, buf: &'b mut [u8], dur: Duration) -> ReadTimeout<'a, 'b> {
+        ReadTimeout {
+            stream: self,
+            buf,
+            delay: Delay::new(Instant::now() + dur),
+        }
     }
 
-    /// Shuts down the read, write, or both halves of this connection.
+    /// Writes some bytes from `buf`, failing with an `ErrorKind::TimedOut`
+    /// error if the stream doesn't accept any within `dur`.
+    ///
+    /// This is the async counterpart of
+    /// `std::net::TcpStream::set_write_timeout`, but it applies to this write
+    /// only. Like [`read_timeout`], the future resolves to the number of bytes
+    /// written as soon as the stream accepts any, so a timeout means that
+    /// nothing from `buf` was written.
+    ///
+    /// [`read_timeout`]: #method.read_timeout
+    pub fn write_timeout<'a, 'b>(&'a self, buf: &'b [u8], dur: Duration) -> WriteTimeout<'a, 'b> {
+        WriteTimeout {
+            stream: self,
+            buf,
+            delay: Delay::new(Instant::now() + dur),
+        }
+    }
+
+    pub(crate) fn clear_read_ready(&self, lw: &LocalWaker) -> io::Result<()> {
+        self.io.clear_read_ready(lw)
+    }
+
+    pub(crate) fn clear_write_ready(&self, lw: &LocalWaker) -> io::Result<()> {
+        self.io.clear_write_ready(lw)
+    }
+
+    /// Returns the local address that this stream is bound to.
     ///
-    /// This function will cause all pending and future I/O on the specified
-    /// portions to return immediately with an appropriate value (see the
-    /// documentation of `Shutdown`).
+    /// For a stream created by connecting, or accepted from a `TcpListener`
+    /// bound to a specific address rather than to an unspecified one like
+    /// `0.0.0.0`, this is known without asking the socket, which saves a
+    /// system call per call.
     ///
     /// # Examples
     ///
     /// ```rust
     /// #![feature(async_await, await_macro, futures_api)]
     /// use romio::tcp::TcpStream;
-    /// use std::net::Shutdown;
+    /// use std::net::{IpAddr, Ipv4Addr};
     ///
     /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
     /// let addr = "127.0.0.1:8080".parse()?;
     /// let stream = await!(TcpStream::connect(&addr))?;
     ///
-    /// stream.shutdown(Shutdown::Both)?;
+    /// let expected = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+    /// assert_eq!(stream.local_addr()?.ip(), expected);
     /// # Ok(())}
     /// ```
-    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
-        self.io.get_ref().shutdown(how)
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        match self.local_addr {
+            Some(addr) => Ok(addr),
+            None => self.io.get_ref().local_addr(),
+        }
     }
 
-    /// Gets the value of the `TCP_NODELAY` option on this socket.
+    /// Returns the remote address that this stream is connected to.
     ///
-    /// For more information about this option, see [`set_nodelay`].
+    /// For a stream accepted from a `TcpListener` this is the address returned
+    /// by `accept`, and for a stream created with [`connect`] it is the address
+    /// connected to. Both are remembered, so this doesn't need a system call,
+    /// and keeps working once the peer has disconnected. Otherwise, for
+    /// example for a stream created with `from_raw_fd`, the socket is asked,
+    /// which may fail with `ErrorKind::NotConnected` once the peer has
+    /// disconnected.
     ///
-    /// [`set_nodelay`]: #method.set_nodelay
+    /// [`connect`]: #method.connect
     ///
     /// # Examples
     ///
     /// ```rust
     /// #![feature(async_await, await_macro, futures_api)]
     /// use romio::tcp::TcpStream;
+    /// use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
     ///
     /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
     /// let addr = "127.0.0.1:8080".parse()?;
     /// let stream = await!(TcpStream::connect(&addr))?;
     ///
-    /// stream.set_nodelay(true)?;
-    /// assert_eq!(stream.nodelay()?, true);
+    /// let expected = SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 8080);
+    /// assert_eq!(stream.peer_addr()?, SocketAddr::V4(expected));
     /// # Ok(())}
     /// ```
-    pub fn nodelay(&self) -> io::Result<bool> {
-        self.io.get_ref().nodelay()
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        match self.peer_addr {
+            Some(addr) => Ok(addr),
+            None => self.io.get_ref().peer_addr(),
+        }
     }
 
-    /// Sets the value of the `TCP_NODELAY` option on this socket.
+    /// Returns the value of the `SO_ERROR` option.
     ///
-    /// If set, this option disables the Nagle algorithm. This means that
-    /// segments are always sent as soon as possible, even if there is only a
-    /// small amount of data. When not set, data is buffered until there is a
-    /// sufficient amount to send out, thereby avoiding the frequent sending of
-    /// small packets.
+    /// This retrieves and clears the pending error on the socket, so a
+    /// subsequent call returns `Ok(None)` until another error occurs. A
+    /// failed connect is reported through this option, which is how
+    /// [`connect`] learns about it.
+    ///
+    /// [`connect`]: #method.connect
     ///
     /// # Examples
     ///
@@ -220,45 +707,57 @@ impl TcpStream {
     /// let addr = "127.0.0.1:8080".parse()?;
     /// let stream = await!(TcpStream::connect(&addr))?;
     ///
-    /// stream.set_nodelay(true)?;
+    /// if let Some(err) = stream.take_error()? {
+    ///     println!("Got error: {:?}", err);
+    /// }
     /// # Ok(())}
     /// ```
-    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
-        self.io.get_ref().set_nodelay(nodelay)
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.io.get_ref().take_error()
     }
 
-    /// Gets the value of the `SO_RCVBUF` option on this socket.
-    ///
-    /// For more information about this option, see [`set_recv_buffer_size`].
+    /// Shuts down the read, write, or both halves of this connection.
     ///
-    /// [`set_recv_buffer_size`]: #tymethod.set_recv_buffer_size
+    /// This function will cause all pending and future I/O on the specified
+    /// portions to return immediately with an appropriate value (see the
+    /// documentation of `Shutdown`).
     ///
     /// # Examples
     ///
     /// ```rust
     /// #![feature(async_await, await_macro, futures_api)]
     /// use romio::tcp::TcpStream;
+    /// use std::net::Shutdown;
     ///
     /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
     /// let addr = "127.0.0.1:8080".parse()?;
     /// let stream = await!(TcpStream::connect(&addr))?;
     ///
-    /// stream.set_recv_buffer_size(100);
-    /// assert_eq!(stream.recv_buffer_size()?, 100);
+    /// stream.shutdown(Shutdown::Both)?;
     /// # Ok(())}
     /// ```
-    pub fn recv_buffer_size(&self) -> io::Result<usize> {
-        self.io.get_ref().recv_buffer_size()
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.io.get_ref().shutdown(how)
     }
 
-    /// Sets the value of the `SO_RCVBUF` option on this socket.
+    /// Waits until the peer closed the connection, or the connection failed,
+    /// without reading from it.
     ///
-    /// Changes the size of the operating system's receive buffer associated
-    /// with the socket.
+    /// This is meant for noticing that an idle connection, like one kept in a
+    /// connection pool, went away. Whether the connection is closed is checked
+    /// by peeking at it with `MSG_PEEK` whenever the reactor reports an event
+    /// for it, so no data is consumed, and the peer closing its end is
+    /// noticed on all platforms, whether they report HUP readiness or not.
+    /// Data sent by the peer which wasn't read yet keeps the connection from
+    /// being considered closed.
+    ///
+    /// The future doesn't take the place of a task reading from the stream,
+    /// so both can wait at the same time. It also resolves if the reactor of
+    /// the stream shut down, since the stream can't be used anymore then.
     ///
     /// # Examples
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// #![feature(async_await, await_macro, futures_api)]
     /// use romio::tcp::TcpStream;
     ///
@@ -266,22 +765,62 @@ impl TcpStream {
     /// let addr = "127.0.0.1:8080".parse()?;
     /// let stream = await!(TcpStream::connect(&addr))?;
     ///
-    /// stream.set_recv_buffer_size(100);
+    /// await!(stream.closed());
+    /// println!("the server hung up");
     /// # Ok(())}
     /// ```
-    pub fn set_recv_buffer_size(&self, size: usize) -> io::Result<()> {
-        self.io.get_ref().set_recv_buffer_size(size)
+    pub fn closed(&self) -> Closed<'_> {
+        Closed { stream: self }
     }
 
-    /// Gets the value of the `SO_SNDBUF` option on this socket.
+    /// Checks whether the peer closed the connection, or the connection
+    /// failed.
+    ///
+    /// If it hasn't, the method returns `Poll::Pending` and arranges for the
+    /// current task to receive a notification on the next event of the
+    /// stream, without consuming its readiness. See [`closed`] for details.
+    ///
+    /// [`closed`]: #method.closed
+    pub fn poll_peer_closed(&self, lw: &LocalWaker) -> Poll<()> {
+        // Registering before peeking ensures that a close in between isn't
+        // missed.
+        if self.io.register_watcher(lw).is_err() || self.is_closed() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    /// Returns whether the peer closed the connection, or the connection
+    /// failed, by peeking at it.
+    ///
+    /// No data is consumed, and data which wasn't read yet makes this return
+    /// `false`. See [`closed`] for details.
+    ///
+    /// [`closed`]: #method.closed
+    pub fn is_closed(&self) -> bool {
+        match self.io.get_ref().peek(&mut [0]) {
+            Ok(n) => n == 0,
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => false,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => false,
+            Err(_) => true,
+        }
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
     ///
-    /// For more information about this option, see [`set_send_buffer`].
+    /// The returned `TcpStream` refers to the same connection as this one,
+    /// like `std::net::TcpStream::try_clone`. Both handles read and write the
+    /// same stream of data, and options set on one of them affect the other.
+    /// The socket is closed once all handles are dropped.
     ///
-    /// [`set_send_buffer`]: #tymethod.set_send_buffer
+    /// Each handle is registered with the reactor on its own and is notified
+    /// of readiness independently, so a task waiting on one handle is never
+    /// starved by another task using the other.
     ///
     /// # Examples
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// #![feature(async_await, await_macro, futures_api)]
     /// use romio::tcp::TcpStream;
     ///
@@ -289,15 +828,651 @@ impl TcpStream {
     /// let addr = "127.0.0.1:8080".parse()?;
     /// let stream = await!(TcpStream::connect(&addr))?;
     ///
-    /// stream.set_send_buffer_size(100);
-    /// assert_eq!(stream.send_buffer_size()?, 100);
+    /// let control = stream.try_clone()?;
     /// # Ok(())}
     /// ```
-    pub fn send_buffer_size(&self) -> io::Result<usize> {
-        self.io.get_ref().send_buffer_size()
+    pub fn try_clone(&self) -> io::Result<TcpStream> {
+        let io = self.io.get_ref().try_clone()?;
+        Ok(TcpStream::with_addrs(io, self.local_addr, self.peer_addr))
     }
 
-    /// Sets the value of the `SO_SNDBUF` option on this socket.
+    /// Wraps the stream in a [`BufTcpStream`] with a read buffer of
+    /// `capacity` bytes.
+    ///
+    /// Small reads are then served from the buffer, which is refilled from the
+    /// socket in chunks of up to `capacity` bytes, saving a system call for
+    /// most of them.
+    ///
+    /// [`BufTcpStream`]: struct.BufTcpStream.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_capacity(self, capacity: usize) -> BufTcpStream {
+        BufTcpStream::new(self, capacity)
+    }
+
+    /// Reads into a buffer owned by the returned future, handing it back
+    /// together with the result.
+    ///
+    /// The data is appended to the contents of `buf`, filling its spare
+    /// capacity. If it has none, room for 8 KiB is reserved first. The future
+    /// resolves to the number of bytes read, where 0 means the peer closed its
+    /// end of the connection, and the buffer. On error the buffer is returned
+    /// unchanged.
+    ///
+    /// Since the buffer doesn't need to outlive a borrow, reads can be stored
+    /// in a queue or passed between tasks easily.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use bytes::BytesMut;
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let mut stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// let (res, buf) = await!(stream.read_owned(BytesMut::with_capacity(4096)));
+    /// println!("read {} bytes: {:?}", res?, buf);
+    /// # Ok(())}
+    /// ```
+    pub fn read_owned(&mut self, buf: BytesMut) -> ReadOwned<'_> {
+        ReadOwned {
+            stream: self,
+            buf: Some(buf),
+        }
+    }
+
+    /// Sends up to `len` bytes of `file`, starting at `offset`, over this
+    /// stream.
+    ///
+    /// On Linux, macOS, FreeBSD and DragonFly the data is sent with
+    /// `sendfile(2)` and never has to be copied through userspace. On other
+    /// platforms it is read from the file into a buffer and written to the
+    /// stream. Either way the data is sent whenever the stream is writable,
+    /// and partial sends are resumed where they left off.
+    ///
+    /// The returned future resolves to the number of bytes sent, which is less
+    /// than `len` only if the end of the file was reached. If sending fails
+    /// partway, the [`SendFileError`] tells how many bytes were sent before,
+    /// so the rest of the file can be sent from there.
+    ///
+    /// [`SendFileError`]: struct.SendFileError.html
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpStream;
+    /// use std::fs::File;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let mut stream = await!(TcpStream::connect(&addr))?;
+    /// let file = File::open("index.html")?;
+    /// let len = file.metadata()?.len();
+    /// await!(stream.send_file(&file, 0, len))?;
+    /// # Ok(())}
+    /// ```
+    pub fn send_file<'a, 'b>(
+        &'a mut self,
+        file: &'b File,
+        offset: u64,
+        len: u64,
+    ) -> SendFile<'a, 'b> {
+        SendFile {
+            stream: self,
+            file,
+            offset,
+            remaining: len,
+            sent: 0,
+        }
+    }
+
+    /// Attempts to send up to `len` bytes of `file`, starting at `offset`,
+    /// over this stream.
+    ///
+    /// # Return
+    ///
+    /// On success, returns `Poll::Ready(Ok(num_bytes_sent))`. Fewer than `len`
+    /// bytes may be sent, and `0` is returned at the end of the file. The
+    /// caller is responsible for advancing `offset` to resume sending.
+    ///
+    /// If the stream is not ready for writing, the method returns
+    /// `Poll::Pending` and arranges for the current task to receive a
+    /// notification when the stream becomes writable.
+    pub fn poll_send_file(
+        &self,
+        lw: &LocalWaker,
+        file: &File,
+        offset: u64,
+        len: u64,
+    ) -> Poll<io::Result<usize>> {
+        if len == 0 {
+            return Poll::Ready(Ok(0));
+        }
+
+        ready!(self.io.poll_write_ready(lw)?);
+
+        let r = sendfile::send_file(self.io.get_ref(), file, offset, len);
+
+        if is_wouldblock(&r) {
+            self.io.clear_write_ready(lw)?;
+            Poll::Pending
+        } else {
+            Poll::Ready(r)
+        }
+    }
+
+    /// Sends `buf` as TCP urgent data, with the `MSG_OOB` flag.
+    ///
+    /// TCP only has a single urgent pointer, so if `buf` is longer than one
+    /// byte only its last byte is urgent, and the bytes before it are sent as
+    /// regular data. The peer receives the urgent byte with [`recv_oob`],
+    /// unless it set [`set_oob_inline`].
+    ///
+    /// The returned future resolves to the number of bytes sent.
+    ///
+    /// [`recv_oob`]: #method.recv_oob
+    /// [`set_oob_inline`]: #method.set_oob_inline
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// // Interrupt the peer, like telnet does.
+    /// await!(stream.send_oob(&[0xf2]))?;
+    /// # Ok(())}
+    /// ```
+    #[cfg(unix)]
+    pub fn send_oob<'a, 'b>(&'a self, buf: &'b [u8]) -> SendOob<'a, 'b> {
+        SendOob { stream: self, buf }
+    }
+
+    /// Attempts to send `buf` as TCP urgent data.
+    ///
+    /// On success, returns `Poll::Ready(Ok(num_bytes_sent))`. See
+    /// [`send_oob`] for how urgent data is sent.
+    ///
+    /// If the stream is not ready for writing, the method returns
+    /// `Poll::Pending` and arranges for the current task to receive a
+    /// notification when the stream becomes writable.
+    ///
+    /// [`send_oob`]: #method.send_oob
+    #[cfg(unix)]
+    pub fn poll_send_oob(&self, lw: &LocalWaker, buf: &[u8]) -> Poll<io::Result<usize>> {
+        ready!(self.io.poll_write_ready(lw)?);
+
+        let r = sys::send_oob(self, buf);
+
+        if is_wouldblock(&r) {
+            self.io.clear_write_ready(lw)?;
+            Poll::Pending
+        } else {
+            Poll::Ready(r)
+        }
+    }
+
+    /// Receives the pending TCP urgent byte into `buf`, with the `MSG_OOB`
+    /// flag.
+    ///
+    /// The returned future waits until urgent data arrives and resolves to the
+    /// number of bytes received, which is 1 unless `buf` is empty. Urgent data
+    /// is never returned by regular reads, unless [`set_oob_inline`] is set,
+    /// in which case this fails with an `InvalidInput` error instead.
+    ///
+    /// Waiting for urgent data uses the same task slot as reading, so it
+    /// should happen in the task which reads from the stream, for example by
+    /// selecting over both. On Linux, the arrival of urgent data is signaled
+    /// to the reactor as priority readiness (`EPOLLPRI`), which is tracked
+    /// separately from the regular readable readiness. Other platforms only
+    /// signal it as readable, so a regular read that would block also clears
+    /// the readiness this waits on. There, urgent data should only be waited
+    /// for while the stream isn't read from, or after being told about it
+    /// out of band, for example by `SIGURG`.
+    ///
+    /// [`set_oob_inline`]: #method.set_oob_inline
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// let mut buf = [0; 1];
+    /// await!(stream.recv_oob(&mut buf))?;
+    /// println!("urgent byte: {}", buf[0]);
+    /// # Ok(())}
+    /// ```
+    #[cfg(unix)]
+    pub fn recv_oob<'a, 'b>(&'a self, buf: &'b mut [u8]) -> RecvOob<'a, 'b> {
+        RecvOob { stream: self, buf }
+    }
+
+    /// Attempts to receive the pending TCP urgent byte into `buf`.
+    ///
+    /// On success, returns `Poll::Ready(Ok(num_bytes_read))`. See
+    /// [`recv_oob`] for how urgent data is received.
+    ///
+    /// If no urgent data is pending, the method returns `Poll::Pending` and
+    /// arranges for the current task to receive a notification when urgent
+    /// data arrives.
+    ///
+    /// [`recv_oob`]: #method.recv_oob
+    #[cfg(unix)]
+    pub fn poll_recv_oob(&self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        ready!(self.io.poll_priority_ready(lw)?);
+
+        match sys::recv_oob(self, buf) {
+            // No urgent data is pending, or it didn't arrive yet.
+            Err(ref e)
+                if e.kind() == io::ErrorKind::WouldBlock
+                    || e.raw_os_error() == Some(libc::EINVAL) =>
+            {
+                if self.oob_inline()? {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "urgent data is received inline, because SO_OOBINLINE is set",
+                    )));
+                }
+                self.io.clear_priority_ready(lw)?;
+                Poll::Pending
+            }
+            r => Poll::Ready(r),
+        }
+    }
+
+    /// Gets the value of the `TCP_NODELAY` option on this socket.
+    ///
+    /// For more information about this option, see [`set_nodelay`].
+    ///
+    /// [`set_nodelay`]: #method.set_nodelay
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// stream.set_nodelay(true)?;
+    /// assert_eq!(stream.nodelay()?, true);
+    /// # Ok(())}
+    /// ```
+    pub fn nodelay(&self) -> io::Result<bool> {
+        self.io.get_ref().nodelay()
+    }
+
+    /// Sets the value of the `TCP_NODELAY` option on this socket.
+    ///
+    /// If set, this option disables the Nagle algorithm. This means that
+    /// segments are always sent as soon as possible, even if there is only a
+    /// small amount of data. When not set, data is buffered until there is a
+    /// sufficient amount to send out, thereby avoiding the frequent sending of
+    /// small packets.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// stream.set_nodelay(true)?;
+    /// # Ok(())}
+    /// ```
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.io.get_ref().set_nodelay(nodelay)
+    }
+
+    /// Returns whether the socket is corked.
+    ///
+    /// For more information about this option, see [`set_cork`].
+    ///
+    /// [`set_cork`]: #method.set_cork
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+    pub fn cork(&self) -> io::Result<bool> {
+        sys::cork(self)
+    }
+
+    /// Sets the value of the `TCP_CORK` option on Linux, or `TCP_NOPUSH` on
+    /// macOS and FreeBSD, on this socket.
+    ///
+    /// While the socket is corked, the kernel holds back partial segments, so
+    /// that several small writes, like the header and body of a response, go
+    /// out in as few segments as possible. Data is only sent once a full
+    /// segment is queued or the socket is uncorked, so a corked socket has to
+    /// be uncorked for the last of the data to actually be sent. On Linux
+    /// corked data is also sent after 200 milliseconds at the latest.
+    ///
+    /// Unlike disabling [`set_nodelay`], this holds back data even if nothing
+    /// is in flight. The two options can be combined: with `TCP_NODELAY` set,
+    /// uncorking sends the held back data right away rather than waiting for
+    /// outstanding acknowledgements. On Linux, setting `TCP_NODELAY` while
+    /// the socket is corked also sends the pending data, but leaves it
+    /// corked.
+    ///
+    /// To make sure the socket is uncorked again even if writing fails, use
+    /// [`corked`].
+    ///
+    /// [`set_nodelay`]: #method.set_nodelay
+    /// [`corked`]: #method.corked
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use futures::prelude::*;
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let mut stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// stream.set_cork(true)?;
+    /// await!(stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n"))?;
+    /// await!(stream.write_all(b"ok"))?;
+    /// // uncorking sends whatever is still held back
+    /// stream.set_cork(false)?;
+    /// # Ok(())}
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+    pub fn set_cork(&self, cork: bool) -> io::Result<()> {
+        sys::set_cork(self, cork)
+    }
+
+    /// Corks the socket until the returned guard is dropped.
+    ///
+    /// The guard dereferences to the stream, so it can be written to as
+    /// usual. Dropping it uncorks the socket, which sends the held back data,
+    /// even when the writes are abandoned early because of an error. Errors
+    /// uncorking the socket on drop are ignored, use [`Corked::uncork`] to
+    /// handle them. See [`set_cork`] for how corking works.
+    ///
+    /// [`Corked::uncork`]: struct.Corked.html#method.uncork
+    /// [`set_cork`]: #method.set_cork
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use futures::prelude::*;
+    /// use romio::tcp::TcpStream;
+    /// use std::fs::File;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let mut stream = await!(TcpStream::connect(&addr))?;
+    /// let file = File::open("index.html")?;
+    /// let len = file.metadata()?.len();
+    ///
+    /// // the header goes out in the same segment as the start of the body
+    /// let mut corked = stream.corked()?;
+    /// let header = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", len);
+    /// await!(corked.write_all(header.as_bytes()))?;
+    /// await!(corked.send_file(&file, 0, len))?;
+    /// corked.uncork()?;
+    /// # Ok(())}
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+    pub fn corked(&mut self) -> io::Result<Corked<'_>> {
+        self.set_cork(true)?;
+        Ok(Corked { stream: Some(self) })
+    }
+
+    /// Gets the value of the `TCP_QUICKACK` option on this socket.
+    ///
+    /// For more information about this option, see [`set_quickack`].
+    ///
+    /// [`set_quickack`]: #method.set_quickack
+    pub fn quickack(&self) -> io::Result<bool> {
+        quickack(self)
+    }
+
+    /// Sets the value of the `TCP_QUICKACK` option on this socket.
+    ///
+    /// If set, incoming data is acknowledged right away instead of the ACK
+    /// being delayed in the hope of piggybacking it on a response.
+    ///
+    /// The option isn't permanent: the kernel may switch back to delayed ACKs
+    /// as the connection proceeds, so callers relying on it should set it again
+    /// after every read.
+    ///
+    /// This option is only supported on Linux, elsewhere an error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use futures::prelude::*;
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let mut stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// let mut buf = [0; 1024];
+    /// loop {
+    ///     let n = await!(stream.read(&mut buf))?;
+    ///     if n == 0 {
+    ///         break;
+    ///     }
+    ///     // the kernel may have turned delayed ACKs back on
+    ///     stream.set_quickack(true)?;
+    /// }
+    /// # Ok(())}
+    /// ```
+    pub fn set_quickack(&self, quickack: bool) -> io::Result<()> {
+        set_quickack(self, quickack)
+    }
+
+    /// Gets the value of the `TCP_CONGESTION` option on this socket.
+    ///
+    /// This is the name of the congestion control algorithm in use for the
+    /// connection, like `cubic`. For more information about this option, see
+    /// [`set_congestion`].
+    ///
+    /// [`set_congestion`]: #method.set_congestion
+    #[cfg(target_os = "linux")]
+    pub fn congestion(&self) -> io::Result<String> {
+        sys::congestion(self)
+    }
+
+    /// Sets the value of the `TCP_CONGESTION` option on this socket.
+    ///
+    /// This selects the congestion control algorithm used for the connection,
+    /// by the name the kernel knows it by, like `bbr`. Algorithms which aren't
+    /// available fail with the error returned by the kernel, `ENOENT` if the
+    /// module isn't loaded, and unprivileged processes can only choose those
+    /// listed in `net.ipv4.tcp_allowed_congestion_control`.
+    ///
+    /// This option is only supported on Linux.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// stream.set_congestion("bbr")?;
+    /// assert_eq!(stream.congestion()?, "bbr");
+    /// # Ok(())}
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn set_congestion(&self, algorithm: &str) -> io::Result<()> {
+        sys::set_congestion(self, algorithm)
+    }
+
+    /// Gets the value of the `TCP_USER_TIMEOUT` option on this socket.
+    ///
+    /// A timeout of zero means that the system default is used. For more
+    /// information about this option, see [`set_user_timeout`].
+    ///
+    /// [`set_user_timeout`]: #method.set_user_timeout
+    #[cfg(target_os = "linux")]
+    pub fn user_timeout(&self) -> io::Result<Duration> {
+        sys::user_timeout(self)
+    }
+
+    /// Sets the value of the `TCP_USER_TIMEOUT` option on this socket.
+    ///
+    /// This bounds how long transmitted data may remain unacknowledged before
+    /// the kernel gives up on the connection and closes it, failing pending
+    /// and later operations with `TimedOut`. Without it, a peer which
+    /// vanished while data was in flight is only detected once the
+    /// retransmissions run out, which takes about 15 minutes by default.
+    /// The timeout is rounded up to whole milliseconds, and zero restores the
+    /// system default.
+    ///
+    /// When [keepalive] is enabled as well, the user timeout also overrides
+    /// the number of probes set with `set_keepalive_params`: an idle
+    /// connection is closed on the first unanswered probe sent once the
+    /// timeout has passed since the peer was last heard from. To let a few
+    /// probes go unanswered, the timeout should be larger than the keepalive
+    /// idle time plus a few probe intervals.
+    ///
+    /// This option is only supported on Linux.
+    ///
+    /// [keepalive]: #method.set_keepalive
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpStream;
+    /// use std::time::Duration;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// stream.set_user_timeout(Duration::from_secs(30))?;
+    /// assert_eq!(stream.user_timeout()?, Duration::from_secs(30));
+    /// # Ok(())}
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn set_user_timeout(&self, dur: Duration) -> io::Result<()> {
+        sys::set_user_timeout(self, dur)
+    }
+
+    /// Returns statistics about the connection, like its round trip time and
+    /// congestion window, read from the `TCP_INFO` option.
+    ///
+    /// This is only supported on Linux.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// let info = stream.tcp_info()?;
+    /// println!("rtt: {:?}, cwnd: {}", info.rtt, info.snd_cwnd);
+    /// # Ok(())}
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn tcp_info(&self) -> io::Result<TcpInfo> {
+        super::info::tcp_info(self)
+    }
+
+    /// Gets the value of the `SO_RCVBUF` option on this socket.
+    ///
+    /// For more information about this option, see [`set_recv_buffer_size`].
+    ///
+    /// [`set_recv_buffer_size`]: #tymethod.set_recv_buffer_size
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// stream.set_recv_buffer_size(100);
+    /// assert_eq!(stream.recv_buffer_size()?, 100);
+    /// # Ok(())}
+    /// ```
+    pub fn recv_buffer_size(&self) -> io::Result<usize> {
+        self.io.get_ref().recv_buffer_size()
+    }
+
+    /// Sets the value of the `SO_RCVBUF` option on this socket.
+    ///
+    /// Changes the size of the operating system's receive buffer associated
+    /// with the socket.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// stream.set_recv_buffer_size(100);
+    /// # Ok(())}
+    /// ```
+    pub fn set_recv_buffer_size(&self, size: usize) -> io::Result<()> {
+        self.io.get_ref().set_recv_buffer_size(size)
+    }
+
+    /// Gets the value of the `SO_SNDBUF` option on this socket.
+    ///
+    /// For more information about this option, see [`set_send_buffer`].
+    ///
+    /// [`set_send_buffer`]: #tymethod.set_send_buffer
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// stream.set_send_buffer_size(100);
+    /// assert_eq!(stream.send_buffer_size()?, 100);
+    /// # Ok(())}
+    /// ```
+    pub fn send_buffer_size(&self) -> io::Result<usize> {
+        self.io.get_ref().send_buffer_size()
+    }
+
+    /// Sets the value of the `SO_SNDBUF` option on this socket.
     ///
     /// Changes the size of the operating system's send buffer associated with
     /// the socket.
@@ -376,6 +1551,190 @@ impl TcpStream {
         self.io.get_ref().set_keepalive(keepalive)
     }
 
+    /// Returns the keepalive parameters of this socket, or `None` if keepalive
+    /// messages are disabled.
+    ///
+    /// Parameters which can't be read on the current platform are reported
+    /// as `None`. For more information about them, see
+    /// [`set_keepalive_params`].
+    ///
+    /// [`set_keepalive_params`]: #method.set_keepalive_params
+    pub fn keepalive_params(&self) -> io::Result<Option<KeepaliveParams>> {
+        let idle = match self.keepalive()? {
+            Some(idle) => idle,
+            None => return Ok(None),
+        };
+        let (interval, retries) = keepalive_probes(self)?;
+
+        Ok(Some(KeepaliveParams {
+            idle,
+            interval,
+            retries,
+        }))
+    }
+
+    /// Enables keepalive messages on this socket and tunes how quickly a dead
+    /// peer is detected.
+    ///
+    /// Once the connection has been idle for `idle`, a probe is sent every
+    /// `interval`, and the connection is dropped after `retries` probes went
+    /// unanswered. A dead peer is thus detected after about
+    /// `idle + interval * retries`, compared to more than two hours with the
+    /// defaults of most systems.
+    ///
+    /// The parameters are set with the `TCP_KEEPIDLE` (`TCP_KEEPALIVE` on
+    /// macOS), `TCP_KEEPINTVL` and `TCP_KEEPCNT` options on Linux, macOS and
+    /// FreeBSD. They take whole seconds, so the durations are rounded up, and
+    /// zero values are raised to the smallest value allowed. On other
+    /// platforms only `idle` is honored, like with [`set_keepalive`], while
+    /// the interval and number of probes keep the system defaults.
+    ///
+    /// [`set_keepalive`]: #method.set_keepalive
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpStream;
+    /// use std::time::Duration;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// // detect a dropped connection within about 30 seconds
+    /// stream.set_keepalive_params(Duration::from_secs(15), Duration::from_secs(5), 3)?;
+    /// # Ok(())}
+    /// ```
+    pub fn set_keepalive_params(
+        &self,
+        idle: Duration,
+        interval: Duration,
+        retries: u32,
+    ) -> io::Result<()> {
+        self.set_keepalive(Some(idle))?;
+        set_keepalive_probes(self, interval, retries)
+    }
+
+    /// Returns the name of the network interface this stream is bound to, if
+    /// any.
+    ///
+    /// For more information about this option, see [`set_device`].
+    ///
+    /// [`set_device`]: #method.set_device
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn device(&self) -> io::Result<Option<String>> {
+        crate::device::device(self)
+    }
+
+    /// Binds this stream to the network interface named `device`, or removes
+    /// the binding if `None`.
+    ///
+    /// Packets are sent out of that interface regardless of the routing table,
+    /// and only packets received on it are accepted. To make the handshake go
+    /// through the interface as well, bind the socket before connecting with
+    /// [`TcpStreamBuilder::device`].
+    ///
+    /// This uses `SO_BINDTODEVICE` on Linux, which usually requires the
+    /// `CAP_NET_RAW` capability, and `IP_BOUND_IF` on macOS. Names longer than
+    /// the platform allows are rejected with an `InvalidInput` error.
+    ///
+    /// [`TcpStreamBuilder::device`]: struct.TcpStreamBuilder.html#method.device
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn set_device(&self, device: Option<&str>) -> io::Result<()> {
+        crate::device::set_device(self, device)
+    }
+
+    /// Gets the value of the `SO_MARK` option on this socket.
+    ///
+    /// For more information about this option, see [`set_mark`].
+    ///
+    /// [`set_mark`]: #method.set_mark
+    #[cfg(target_os = "linux")]
+    pub fn mark(&self) -> io::Result<u32> {
+        crate::mark::mark(self)
+    }
+
+    /// Sets the value of the `SO_MARK` option on this socket.
+    ///
+    /// The mark is attached to the packets sent from the socket, so they can
+    /// be routed with `ip rule fwmark` or matched by packet filters. To have
+    /// the handshake routed by the mark as well, set it before connecting with
+    /// [`TcpStreamBuilder::mark`].
+    ///
+    /// Setting the mark requires the `CAP_NET_ADMIN` capability, otherwise an
+    /// error of kind `PermissionDenied` is returned. This option is only
+    /// supported on Linux.
+    ///
+    /// [`TcpStreamBuilder::mark`]: struct.TcpStreamBuilder.html#method.mark
+    #[cfg(target_os = "linux")]
+    pub fn set_mark(&self, mark: u32) -> io::Result<()> {
+        crate::mark::set_mark(self, mark)
+    }
+
+    /// Gets the value of the `SO_PRIORITY` option on this socket.
+    ///
+    /// For more information about this option, see [`set_priority`].
+    ///
+    /// [`set_priority`]: #method.set_priority
+    pub fn priority(&self) -> io::Result<u32> {
+        crate::priority::priority(self)
+    }
+
+    /// Sets the value of the `SO_PRIORITY` option on this socket.
+    ///
+    /// The priority selects the band of a queueing discipline like `prio`
+    /// which the packets sent from the socket are queued in. Priorities from 0
+    /// to 6 can be set by anyone, higher ones require the `CAP_NET_ADMIN`
+    /// capability, otherwise an error of kind `PermissionDenied` is returned.
+    ///
+    /// This option is only supported on Linux, elsewhere an error of kind
+    /// `Other` is returned.
+    pub fn set_priority(&self, priority: u32) -> io::Result<()> {
+        crate::priority::set_priority(self, priority)
+    }
+
+    /// Gets the value of the `TCP_MAXSEG` option on this socket.
+    ///
+    /// On a connected stream this is the maximum segment size in use for the
+    /// connection, as negotiated with the peer during the handshake.
+    ///
+    /// For more information about this option, see [`set_mss`].
+    ///
+    /// [`set_mss`]: #method.set_mss
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// println!("sending segments of up to {} bytes", stream.mss()?);
+    /// # Ok(())}
+    /// ```
+    #[cfg(unix)]
+    pub fn mss(&self) -> io::Result<u32> {
+        mss(self)
+    }
+
+    /// Sets the value of the `TCP_MAXSEG` option on this socket.
+    ///
+    /// This limits the size of the segments sent on the connection. The limit
+    /// is announced to the peer in the handshake, so it only fully takes
+    /// effect if it is set before connecting. Use
+    /// [`TcpStreamBuilder::mss`] for that, setting it on a connected stream
+    /// has little to no effect.
+    ///
+    /// [`TcpStreamBuilder::mss`]: struct.TcpStreamBuilder.html#method.mss
+    #[cfg(unix)]
+    pub fn set_mss(&self, mss: u32) -> io::Result<()> {
+        set_mss(self, mss)
+    }
+
     /// Gets the value of the `IP_TTL` option for this socket.
     ///
     /// For more information about this option, see [`set_ttl`].
@@ -422,6 +1781,67 @@ impl TcpStream {
         self.io.get_ref().set_ttl(ttl)
     }
 
+    /// Gets the value of the `IP_TOS` option for this socket.
+    ///
+    /// For more information about this option, see [`set_tos`].
+    ///
+    /// [`set_tos`]: #method.set_tos
+    #[cfg(unix)]
+    pub fn tos(&self) -> io::Result<u8> {
+        sys::get_ip_option(self, sys::IpOption::Tos)
+    }
+
+    /// Sets the value for the `IP_TOS` option on this socket.
+    ///
+    /// This value sets the type-of-service field of every packet sent from
+    /// this socket, which holds the DSCP and ECN bits. It is only available
+    /// on IPv4 sockets, for IPv6 sockets use [`set_traffic_class`] instead.
+    /// An error of kind `InvalidInput` is returned for IPv6 sockets.
+    ///
+    /// [`set_traffic_class`]: #method.set_traffic_class
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// // DSCP class selector 6, for network control traffic
+    /// stream.set_tos(48 << 2)?;
+    /// # Ok(())}
+    /// ```
+    #[cfg(unix)]
+    pub fn set_tos(&self, tos: u8) -> io::Result<()> {
+        sys::set_ip_option(self, sys::IpOption::Tos, tos)
+    }
+
+    /// Gets the value of the `IPV6_TCLASS` option for this socket.
+    ///
+    /// For more information about this option, see [`set_traffic_class`].
+    ///
+    /// [`set_traffic_class`]: #method.set_traffic_class
+    #[cfg(unix)]
+    pub fn traffic_class(&self) -> io::Result<u8> {
+        sys::get_ip_option(self, sys::IpOption::TrafficClass)
+    }
+
+    /// Sets the value for the `IPV6_TCLASS` option on this socket.
+    ///
+    /// This is the IPv6 equivalent of [`set_tos`], setting the traffic class
+    /// field of every packet sent from this socket. It is only available on
+    /// IPv6 sockets, an error of kind `InvalidInput` is returned for IPv4
+    /// sockets.
+    ///
+    /// [`set_tos`]: #method.set_tos
+    #[cfg(unix)]
+    pub fn set_traffic_class(&self, class: u8) -> io::Result<()> {
+        sys::set_ip_option(self, sys::IpOption::TrafficClass, class)
+    }
+
     /// Reads the linger duration for this socket by getting the `SO_LINGER`
     /// option.
     ///
@@ -477,11 +1897,52 @@ impl TcpStream {
     pub fn set_linger(&self, dur: Option<Duration>) -> io::Result<()> {
         self.io.get_ref().set_linger(dur)
     }
+
+    /// Gets the value of the `SO_OOBINLINE` option on this socket.
+    ///
+    /// For more information about this option, see [`set_oob_inline`].
+    ///
+    /// [`set_oob_inline`]: #method.set_oob_inline
+    #[cfg(unix)]
+    pub fn oob_inline(&self) -> io::Result<bool> {
+        sys::oob_inline(self)
+    }
+
+    /// Sets the value of the `SO_OOBINLINE` option on this socket.
+    ///
+    /// If set, TCP urgent data is left in the regular stream, where regular
+    /// reads return it in order, instead of being received with
+    /// [`recv_oob`].
+    ///
+    /// [`recv_oob`]: #method.recv_oob
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::tcp::TcpStream;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let addr = "127.0.0.1:8080".parse()?;
+    /// let stream = await!(TcpStream::connect(&addr))?;
+    ///
+    /// stream.set_oob_inline(true)?;
+    /// assert_eq!(stream.oob_inline()?, true);
+    /// # Ok(())}
+    /// ```
+    #[cfg(unix)]
+    pub fn set_oob_inline(&self, oob_inline: bool) -> io::Result<()> {
+        sys::set_oob_inline(self, oob_inline)
+    }
 }
 
 // ===== impl Read / Write =====
 
 impl AsyncRead for TcpStream {
+    unsafe fn initializer(&self) -> Initializer {
+        <&TcpStream>::initializer(&self)
+    }
+
     fn poll_read(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
         <&TcpStream>::poll_read(&mut &*self, lw, buf)
     }
@@ -516,6 +1977,11 @@ impl AsyncWrite for TcpStream {
 // ===== impl Read / Write for &'a =====
 
 impl<'a> AsyncRead for &'a TcpStream {
+    unsafe fn initializer(&self) -> Initializer {
+        // The socket only ever writes to the buffers it reads into.
+        Initializer::nop()
+    }
+
     fn poll_read(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
         (&self.io).poll_read(lw, buf)
     }
@@ -525,6 +1991,12 @@ impl<'a> AsyncRead for &'a TcpStream {
         lw: &LocalWaker,
         bufs: &mut [&mut IoVec],
     ) -> Poll<io::Result<usize>> {
+        // Without any buffers there is nothing to wait for, and a zero-length
+        // read would be mistaken for end of stream.
+        if bufs.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
         ready!(self.poll_read_ready(lw)?);
 
         let r = self.io.get_ref().read_bufs(bufs);
@@ -540,23 +2012,39 @@ impl<'a> AsyncRead for &'a TcpStream {
 
 impl<'a> AsyncWrite for &'a TcpStream {
     fn poll_write(&mut self, lw: &LocalWaker, buf: &[u8]) -> Poll<io::Result<usize>> {
-        (&self.io).poll_write(lw, buf)
+        ready!(self.poll_write_ready(lw)?);
+
+        let r = send(self, buf);
+
+        if is_wouldblock(&r) {
+            self.io.clear_write_ready(lw)?;
+            Poll::Pending
+        } else {
+            Poll::Ready(r)
+        }
     }
 
     fn poll_vectored_write(&mut self, lw: &LocalWaker, bufs: &[&IoVec]) -> Poll<io::Result<usize>> {
+        if bufs.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
         ready!(self.poll_write_ready(lw)?);
 
-        let r = self.io.get_ref().write_bufs(bufs);
+        let r = send_bufs(self, bufs);
 
         if is_wouldblock(&r) {
             self.io.clear_write_ready(lw)?;
+            Poll::Pending
+        } else {
+            Poll::Ready(r)
         }
-
-        return Poll::Ready(r);
     }
 
-    fn poll_flush(&mut self, lw: &LocalWaker) -> Poll<io::Result<()>> {
-        (&self.io).poll_flush(lw)
+    fn poll_flush(&mut self, _: &LocalWaker) -> Poll<io::Result<()>> {
+        // Writes go to the kernel right away, there is nothing to flush, nor
+        // to wait for.
+        Poll::Ready(Ok(()))
     }
 
     fn poll_close(&mut self, lw: &LocalWaker) -> Poll<io::Result<()>> {
@@ -570,45 +2058,374 @@ impl fmt::Debug for TcpStream {
     }
 }
 
+/// The future returned by `TcpStream::ready`, which resolves to the readiness
+/// of the stream once it is ready for any of the requested events.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct ReadyFuture<'a> {
+    stream: &'a TcpStream,
+    interest: mio::Ready,
+}
+
+impl<'a> Future for ReadyFuture<'a> {
+    type Output = io::Result<Readiness>;
+
+    fn poll(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<Readiness>> {
+        let ready = ready!(self.stream.io.poll_ready(self.interest, lw))?;
+        Poll::Ready(Ok(Readiness::from_mio(ready)))
+    }
+}
+
+/// The future returned by `TcpStream::closed`, which resolves once the peer
+/// closed the connection.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct Closed<'a> {
+    stream: &'a TcpStream,
+}
+
+impl<'a> Future for Closed<'a> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<()> {
+        self.stream.poll_peer_closed(lw)
+    }
+}
+
+/// A guard which keeps a `TcpStream` corked, returned by `TcpStream::corked`.
+///
+/// The socket is uncorked when the guard is dropped.
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+#[derive(Debug)]
+pub struct Corked<'a> {
+    /// `None` once uncorked explicitly.
+    stream: Option<&'a mut TcpStream>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+impl<'a> Corked<'a> {
+    /// Uncorks the socket, sending the held back data, and reports whether
+    /// that succeeded.
+    pub fn uncork(mut self) -> io::Result<()> {
+        self.stream.take().unwrap().set_cork(false)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+impl<'a> Deref for Corked<'a> {
+    type Target = TcpStream;
+
+    fn deref(&self) -> &TcpStream {
+        self.stream.as_ref().unwrap()
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+impl<'a> DerefMut for Corked<'a> {
+    fn deref_mut(&mut self) -> &mut TcpStream {
+        self.stream.as_mut().unwrap()
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+impl<'a> Drop for Corked<'a> {
+    fn drop(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            drop(stream.set_cork(false));
+        }
+    }
+}
+
+/// The keepalive parameters of a `TcpStream`, as returned by
+/// `TcpStream::keepalive_params`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepaliveParams {
+    /// How long the connection has to be idle before the first probe is sent.
+    pub idle: Duration,
+
+    /// The time between two probes, or `None` if it can't be read on this
+    /// platform.
+    pub interval: Option<Duration>,
+
+    /// How many unanswered probes are sent before the connection is dropped,
+    /// or `None` if it can't be read on this platform.
+    pub retries: Option<u32>,
+}
+
+/// The future returned by `TcpStream::read_owned`, which resolves to the
+/// result of the read and the buffer.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct ReadOwned<'a> {
+    stream: &'a mut TcpStream,
+    buf: Option<BytesMut>,
+}
+
+impl<'a> Future for ReadOwned<'a> {
+    type Output = (io::Result<usize>, BytesMut);
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        let ReadOwned { stream, buf } = &mut *self;
+        let res = {
+            let buf = buf.as_mut().expect("can't poll ReadOwned twice");
+            if buf.len() == buf.capacity() {
+                buf.reserve(READ_OWNED_CHUNK);
+            }
+
+            let len = buf.len();
+            unsafe {
+                buf.set_len(buf.capacity());
+                stream.initializer().initialize(&mut buf[len..]);
+
+                let res = stream.poll_read(lw, &mut buf[len..]);
+
+                let n = match res {
+                    Poll::Ready(Ok(n)) => n,
+                    _ => 0,
+                };
+                buf.set_len(len + n);
+
+                res
+            }
+        };
+
+        match res {
+            Poll::Ready(res) => Poll::Ready((res, buf.take().unwrap())),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Binds `socket` to `local` before connecting, naming the address if that
+/// fails.
+pub(super) fn bind_local(socket: &net2::TcpBuilder, local: &SocketAddr) -> Result<(), AddrError> {
+    match socket.bind(local) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(AddrError::new(Operation::Bind, local, e)),
+    }
+}
+
+/// The future returned by `TcpStream::read_timeout`.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct ReadTimeout<'a, 'b> {
+    stream: &'a TcpStream,
+    buf: &'b mut [u8],
+    delay: Delay,
+}
+
+impl<'a, 'b> Future for ReadTimeout<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<usize>> {
+        let ReadTimeout { stream, buf, delay } = &mut *self;
+        if let Poll::Ready(res) = (&mut &**stream).poll_read(lw, buf) {
+            return Poll::Ready(res);
+        }
+        poll_timed_out(delay, lw)
+    }
+}
+
+/// The future returned by `TcpStream::write_timeout`.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct WriteTimeout<'a, 'b> {
+    stream: &'a TcpStream,
+    buf: &'b [u8],
+    delay: Delay,
+}
+
+impl<'a, 'b> Future for WriteTimeout<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<usize>> {
+        let WriteTimeout { stream, buf, delay } = &mut *self;
+        if let Poll::Ready(res) = (&mut &**stream).poll_write(lw, buf) {
+            return Poll::Ready(res);
+        }
+        poll_timed_out(delay, lw)
+    }
+}
+
+/// Fails with `ErrorKind::TimedOut` once `delay` elapsed.
+fn poll_timed_out(delay: &mut Delay, lw: &LocalWaker) -> Poll<io::Result<usize>> {
+    ready!(Pin::new(delay).poll(lw))?;
+    Poll::Ready(Err(io::Error::new(
+        io::ErrorKind::TimedOut,
+        "operation timed out",
+    )))
+}
+
+/// The future returned by `TcpStream::send_file`.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct SendFile<'a, 'b> {
+    stream: &'a mut TcpStream,
+    file: &'b File,
+    offset: u64,
+    remaining: u64,
+    sent: u64,
+}
+
+/// The error returned by `TcpStream::send_file` when sending fails partway.
+#[derive(Debug)]
+pub struct SendFileError {
+    sent: u64,
+    source: io::Error,
+}
+
+impl<'a, 'b> Future for SendFile<'a, 'b> {
+    type Output = Result<u64, SendFileError>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        let SendFile {
+            stream,
+            file,
+            offset,
+            remaining,
+            sent,
+        } = &mut *self;
+
+        while *remaining > 0 {
+            let n = match ready!(stream.poll_send_file(lw, file, *offset, *remaining)) {
+                Ok(n) => n,
+                Err(source) => {
+                    return Poll::Ready(Err(SendFileError {
+                        sent: *sent,
+                        source,
+                    }));
+                }
+            };
+            if n == 0 {
+                break;
+            }
+            *offset += n as u64;
+            *remaining -= n as u64;
+            *sent += n as u64;
+        }
+
+        Poll::Ready(Ok(*sent))
+    }
+}
+
+impl SendFileError {
+    /// Returns the number of bytes sent before sending failed.
+    pub fn sent(&self) -> u64 {
+        self.sent
+    }
+
+    /// Returns the I/O error which made sending fail.
+    pub fn get_ref(&self) -> &io::Error {
+        &self.source
+    }
+
+    /// Consumes the `SendFileError`, returning the I/O error which made
+    /// sending fail.
+    pub fn into_inner(self) -> io::Error {
+        self.source
+    }
+}
+
+impl fmt::Display for SendFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl Error for SendFileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl From<SendFileError> for io::Error {
+    fn from(e: SendFileError) -> io::Error {
+        e.source
+    }
+}
+
+/// The future returned by `TcpStream::send_oob`.
+#[cfg(unix)]
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct SendOob<'a, 'b> {
+    stream: &'a TcpStream,
+    buf: &'b [u8],
+}
+
+#[cfg(unix)]
+impl<'a, 'b> Future for SendOob<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<usize>> {
+        self.stream.poll_send_oob(lw, self.buf)
+    }
+}
+
+/// The future returned by `TcpStream::recv_oob`.
+#[cfg(unix)]
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct RecvOob<'a, 'b> {
+    stream: &'a TcpStream,
+    buf: &'b mut [u8],
+}
+
+#[cfg(unix)]
+impl<'a, 'b> Future for RecvOob<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<usize>> {
+        let RecvOob { stream, buf } = &mut *self;
+        stream.poll_recv_oob(lw, buf)
+    }
+}
+
+impl ConnectFuture {
+    /// Creates a `ConnectFuture` which fails with `e`.
+    pub(crate) fn error(e: AddrError) -> ConnectFuture {
+        ConnectFuture {
+            inner: ConnectFutureState::Error(e),
+        }
+    }
+
+    /// Resolves to the stream along with the address it connected to.
+    ///
+    /// For a single address that is the address passed in, this exists so
+    /// that all the ways of connecting can be handled alike, see
+    /// [`ConnectMulti::with_addr`].
+    ///
+    /// [`ConnectMulti::with_addr`]: struct.ConnectMulti.html#method.with_addr
+    pub fn with_addr(self) -> ConnectWithAddr<ConnectFuture> {
+        ConnectWithAddr::new(self)
+    }
+}
+
 impl Future for ConnectFuture {
-    type Output = io::Result<TcpStream>;
+    type Output = Result<TcpStream, AddrError>;
 
-    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<TcpStream>> {
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Result<TcpStream, AddrError>> {
         Pin::new(&mut self.inner).poll(lw)
     }
 }
 
 impl ConnectFutureState {
-    fn poll_inner<F>(&mut self, f: F) -> Poll<io::Result<TcpStream>>
-    where
-        F: FnOnce(&mut PollEvented<mio::net::TcpStream>) -> Poll<io::Result<mio::Ready>>,
-    {
-        {
-            let stream = match *self {
-                ConnectFutureState::Waiting(ref mut s) => s,
-                ConnectFutureState::Error(_) => {
-                    let e = match mem::replace(self, ConnectFutureState::Empty) {
-                        ConnectFutureState::Error(e) => e,
-                        _ => panic!(),
-                    };
-                    return Poll::Ready(Err(e));
+    fn poll_inner(&mut self, lw: &LocalWaker) -> Poll<Result<TcpStream, AddrError>> {
+        match *self {
+            ConnectFutureState::Waiting(ref mut stream) => {
+                if let Err(e) = ready!(stream.poll_connected(lw)) {
+                    let addr = stream.connect_addr();
+                    return Poll::Ready(Err(AddrError::new(Operation::Connect, &addr, e)));
                 }
-                ConnectFutureState::Empty => panic!("can't poll TCP stream twice"),
-            };
-
-            // Once we've connected, wait for the stream to be writable as
-            // that's when the actual connection has been initiated. Once we're
-            // writable we check for `take_socket_error` to see if the connect
-            // actually hit an error or not.
-            //
-            // If all that succeeded then we ship everything on up.
-            if let Poll::Pending = f(&mut stream.io)? {
-                return Poll::Pending;
             }
-
-            if let Some(e) = stream.io.get_ref().take_error()? {
+            ConnectFutureState::Error(_) => {
+                let e = match mem::replace(self, ConnectFutureState::Empty) {
+                    ConnectFutureState::Error(e) => e,
+                    _ => panic!(),
+                };
                 return Poll::Ready(Err(e));
             }
+            ConnectFutureState::Empty => panic!("can't poll TCP stream twice"),
         }
 
         match mem::replace(self, ConnectFutureState::Empty) {
@@ -619,23 +2436,288 @@ impl ConnectFutureState {
 }
 
 impl Future for ConnectFutureState {
-    type Output = io::Result<TcpStream>;
+    type Output = Result<TcpStream, AddrError>;
 
-    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<TcpStream>> {
-        self.poll_inner(|io| io.poll_write_ready(lw))
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Result<TcpStream, AddrError>> {
+        self.poll_inner(lw)
     }
 }
 
 #[cfg(unix)]
 mod sys {
     use super::TcpStream;
+    use crate::sockopt::{getsockopt, setsockopt};
+    use std::io;
+    use std::net::SocketAddr;
     use std::os::unix::prelude::*;
+    use std::time::Duration;
+
+    /// The IP header fields which can be set per socket.
+    #[derive(Debug, Clone, Copy)]
+    pub(super) enum IpOption {
+        Tos,
+        TrafficClass,
+    }
+
+    impl IpOption {
+        /// Returns the level and name of the socket option, or an error if it
+        /// doesn't apply to the address family of `stream`.
+        fn resolve(self, stream: &TcpStream) -> io::Result<(libc::c_int, libc::c_int)> {
+            match (self, stream.local_addr()?) {
+                (IpOption::Tos, SocketAddr::V4(..)) => Ok((libc::IPPROTO_IP, libc::IP_TOS)),
+                (IpOption::TrafficClass, SocketAddr::V6(..)) => {
+                    Ok((libc::IPPROTO_IPV6, libc::IPV6_TCLASS))
+                }
+                (IpOption::Tos, SocketAddr::V6(..)) => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "IP_TOS is only available on IPv4 sockets, use the traffic class instead",
+                )),
+                (IpOption::TrafficClass, SocketAddr::V4(..)) => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "IPV6_TCLASS is only available on IPv6 sockets, use the TOS instead",
+                )),
+            }
+        }
+    }
+
+    pub(super) fn get_ip_option(stream: &TcpStream, option: IpOption) -> io::Result<u8> {
+        let (level, name) = option.resolve(stream)?;
+        Ok(getsockopt(stream, level, name)? as u8)
+    }
+
+    pub(super) fn set_ip_option(stream: &TcpStream, option: IpOption, value: u8) -> io::Result<()> {
+        let (level, name) = option.resolve(stream)?;
+        setsockopt(stream, level, name, libc::c_int::from(value))
+    }
+
+    /// Returns the interval between keepalive probes and the number of probes
+    /// sent before the connection is dropped.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+    pub(super) fn keepalive_probes(
+        stream: &TcpStream,
+    ) -> io::Result<(Option<Duration>, Option<u32>)> {
+        let interval = getsockopt(stream, libc::IPPROTO_TCP, libc::TCP_KEEPINTVL)?;
+        let retries = getsockopt(stream, libc::IPPROTO_TCP, libc::TCP_KEEPCNT)?;
+        Ok((
+            Some(Duration::from_secs(interval as u64)),
+            Some(retries as u32),
+        ))
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "freebsd")))]
+    pub(super) fn keepalive_probes(_: &TcpStream) -> io::Result<(Option<Duration>, Option<u32>)> {
+        Ok((None, None))
+    }
+
+    /// Sets the interval between keepalive probes and the number of probes
+    /// sent before the connection is dropped.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+    pub(super) fn set_keepalive_probes(
+        stream: &TcpStream,
+        interval: Duration,
+        retries: u32,
+    ) -> io::Result<()> {
+        use std::cmp;
+
+        // Both options take whole seconds and reject 0.
+        let secs = interval.as_secs() + if interval.subsec_nanos() > 0 { 1 } else { 0 };
+        let secs = cmp::max(cmp::min(secs, libc::c_int::max_value() as u64), 1);
+        let retries = cmp::max(cmp::min(retries, libc::c_int::max_value() as u32), 1);
+
+        setsockopt(
+            stream,
+            libc::IPPROTO_TCP,
+            libc::TCP_KEEPINTVL,
+            secs as libc::c_int,
+        )?;
+        setsockopt(
+            stream,
+            libc::IPPROTO_TCP,
+            libc::TCP_KEEPCNT,
+            retries as libc::c_int,
+        )
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "freebsd")))]
+    pub(super) fn set_keepalive_probes(_: &TcpStream, _: Duration, _: u32) -> io::Result<()> {
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    const CORK: libc::c_int = libc::TCP_CORK;
+    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+    const CORK: libc::c_int = libc::TCP_NOPUSH;
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+    pub(super) fn cork(stream: &TcpStream) -> io::Result<bool> {
+        Ok(getsockopt(stream, libc::IPPROTO_TCP, CORK)? != 0)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+    pub(super) fn set_cork(stream: &TcpStream, cork: bool) -> io::Result<()> {
+        setsockopt(stream, libc::IPPROTO_TCP, CORK, cork as libc::c_int)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) fn quickack(stream: &TcpStream) -> io::Result<bool> {
+        Ok(getsockopt(stream, libc::IPPROTO_TCP, libc::TCP_QUICKACK)? != 0)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) fn set_quickack(stream: &TcpStream, quickack: bool) -> io::Result<()> {
+        setsockopt(
+            stream,
+            libc::IPPROTO_TCP,
+            libc::TCP_QUICKACK,
+            quickack as libc::c_int,
+        )
+    }
+
+    /// The size of the buffer `TCP_CONGESTION` is read into, `TCP_CA_NAME_MAX`
+    /// in the kernel.
+    #[cfg(target_os = "linux")]
+    const CONGESTION_NAME_MAX: usize = 16;
+
+    #[cfg(target_os = "linux")]
+    pub(super) fn congestion(stream: &TcpStream) -> io::Result<String> {
+        let mut buf = [0u8; CONGESTION_NAME_MAX];
+        let mut len = buf.len() as libc::socklen_t;
+        let r = unsafe {
+            libc::getsockopt(
+                stream.as_raw_fd(),
+                libc::IPPROTO_TCP,
+                libc::TCP_CONGESTION,
+                buf.as_mut_ptr() as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if r < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // The name is padded with nul bytes up to the length returned.
+        let name = &buf[..len as usize];
+        let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+        Ok(String::from_utf8_lossy(&name[..end]).into_owned())
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) fn set_congestion(stream: &TcpStream, algorithm: &str) -> io::Result<()> {
+        if algorithm.as_bytes().contains(&0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "congestion control algorithm contains a nul byte",
+            ));
+        }
+
+        let r = unsafe {
+            libc::setsockopt(
+                stream.as_raw_fd(),
+                libc::IPPROTO_TCP,
+                libc::TCP_CONGESTION,
+                algorithm.as_ptr() as *const libc::c_void,
+                algorithm.len() as libc::socklen_t,
+            )
+        };
+        if r < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub(super) fn send_oob(stream: &TcpStream, buf: &[u8]) -> io::Result<usize> {
+        crate::sigpipe::send_with_flags(stream, buf, libc::MSG_OOB)
+    }
+
+    pub(super) fn recv_oob(stream: &TcpStream, buf: &mut [u8]) -> io::Result<usize> {
+        let r = unsafe {
+            libc::recv(
+                stream.as_raw_fd(),
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+                libc::MSG_OOB,
+            )
+        };
+        if r < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(r as usize)
+    }
+
+    pub(super) fn oob_inline(stream: &TcpStream) -> io::Result<bool> {
+        Ok(getsockopt(stream, libc::SOL_SOCKET, libc::SO_OOBINLINE)? != 0)
+    }
+
+    pub(super) fn set_oob_inline(stream: &TcpStream, oob_inline: bool) -> io::Result<()> {
+        setsockopt(
+            stream,
+            libc::SOL_SOCKET,
+            libc::SO_OOBINLINE,
+            oob_inline as libc::c_int,
+        )
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) fn user_timeout(stream: &TcpStream) -> io::Result<Duration> {
+        let millis = getsockopt(stream, libc::IPPROTO_TCP, libc::TCP_USER_TIMEOUT)?;
+        Ok(Duration::from_millis(millis as u64))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) fn set_user_timeout(stream: &TcpStream, dur: Duration) -> io::Result<()> {
+        use std::cmp;
+
+        let millis = dur.as_secs() * 1000 + (u64::from(dur.subsec_nanos()) + 999_999) / 1_000_000;
+        let millis = cmp::min(millis, libc::c_int::max_value() as u64);
+        setsockopt(
+            stream,
+            libc::IPPROTO_TCP,
+            libc::TCP_USER_TIMEOUT,
+            millis as libc::c_int,
+        )
+    }
+
+    pub(crate) fn mss(socket: &impl AsRawFd) -> io::Result<u32> {
+        Ok(getsockopt(socket, libc::IPPROTO_TCP, libc::TCP_MAXSEG)? as u32)
+    }
+
+    pub(crate) fn set_mss(socket: &impl AsRawFd, mss: u32) -> io::Result<()> {
+        setsockopt(
+            socket,
+            libc::IPPROTO_TCP,
+            libc::TCP_MAXSEG,
+            mss as libc::c_int,
+        )
+    }
 
     impl AsRawFd for TcpStream {
         fn as_raw_fd(&self) -> RawFd {
             self.io.get_ref().as_raw_fd()
         }
     }
+
+    impl IntoRawFd for TcpStream {
+        /// Consumes the stream, returning its file descriptor.
+        ///
+        /// The socket is deregistered from the reactor, and the caller becomes
+        /// responsible for closing it. It stays in nonblocking mode.
+        fn into_raw_fd(self) -> RawFd {
+            self.io.detach().into_raw_fd()
+        }
+    }
+
+    impl FromRawFd for TcpStream {
+        /// Creates a `TcpStream` from a raw file descriptor, taking ownership of
+        /// it.
+        ///
+        /// # Safety
+        ///
+        /// `fd` must be an open file descriptor for a connected TCP stream in nonblocking
+        /// mode, which isn't owned by anything else.
+        unsafe fn from_raw_fd(fd: RawFd) -> TcpStream {
+            TcpStream::new(mio::net::TcpStream::from_raw_fd(fd))
+        }
+    }
 }
 
 fn is_wouldblock<T>(r: &io::Result<T>) -> bool {
@@ -644,3 +2726,55 @@ fn is_wouldblock<T>(r: &io::Result<T>) -> bool {
         Err(ref e) => e.kind() == io::ErrorKind::WouldBlock,
     }
 }
+
+/// Writes `buf` to `stream`, without raising `SIGPIPE` if the peer is gone.
+#[cfg(unix)]
+fn send(stream: &TcpStream, buf: &[u8]) -> io::Result<usize> {
+    crate::sigpipe::send(stream, buf)
+}
+
+#[cfg(not(unix))]
+fn send(stream: &TcpStream, buf: &[u8]) -> io::Result<usize> {
+    use std::io::Write;
+
+    stream.io.get_ref().write(buf)
+}
+
+/// Writes `bufs` to `stream`, without raising `SIGPIPE` if the peer is gone.
+#[cfg(unix)]
+fn send_bufs(stream: &TcpStream, bufs: &[&IoVec]) -> io::Result<usize> {
+    crate::sigpipe::send_bufs(stream, bufs)
+}
+
+#[cfg(not(unix))]
+fn send_bufs(stream: &TcpStream, bufs: &[&IoVec]) -> io::Result<usize> {
+    stream.io.get_ref().write_bufs(bufs)
+}
+
+#[cfg(not(unix))]
+fn keepalive_probes(_: &TcpStream) -> io::Result<(Option<Duration>, Option<u32>)> {
+    Ok((None, None))
+}
+
+#[cfg(not(unix))]
+fn set_keepalive_probes(_: &TcpStream, _: Duration, _: u32) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn quickack(_: &TcpStream) -> io::Result<bool> {
+    Err(quickack_unsupported())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_quickack(_: &TcpStream, _: bool) -> io::Result<()> {
+    Err(quickack_unsupported())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn quickack_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Other,
+        "TCP_QUICKACK is only supported on Linux",
+    )
+}
@@ -0,0 +1,102 @@
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+use futures::task::LocalWaker;
+use futures::{Future, Poll};
+
+use crate::reactor::Delay;
+
+/// A token bucket limiting the rate at which a listener accepts connections.
+pub(super) struct Throttle {
+    /// The number of connections accepted per second, or `None` if unlimited.
+    rate: Option<u32>,
+
+    /// The maximum number of tokens in the bucket.
+    burst: u32,
+
+    /// The tokens currently in the bucket, each allowing one accept.
+    tokens: f64,
+
+    /// When the bucket was last refilled.
+    refilled: Instant,
+
+    /// Completes once the next token is available.
+    delay: Option<Delay>,
+}
+
+impl Throttle {
+    pub(super) fn set_rate(&mut self, rate: Option<u32>) {
+        self.rate = rate;
+        self.tokens = f64::from(self.burst);
+        self.refilled = Instant::now();
+        self.delay = None;
+    }
+
+    pub(super) fn set_burst(&mut self, burst: u32) {
+        self.burst = burst.max(1);
+        self.tokens = self.tokens.min(f64::from(self.burst));
+    }
+
+    /// Returns `Ready` once a connection may be accepted.
+    pub(super) fn poll_ready(&mut self, lw: &LocalWaker) -> Poll<io::Result<()>> {
+        let rate = match self.rate {
+            Some(rate) => f64::from(rate),
+            None => return Poll::Ready(Ok(())),
+        };
+
+        loop {
+            let now = Instant::now();
+            let elapsed = now - self.refilled;
+            let elapsed = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) * 1e-9;
+            self.tokens = (self.tokens + elapsed * rate).min(f64::from(self.burst));
+            self.refilled = now;
+
+            if self.tokens >= 1.0 {
+                self.delay = None;
+                return Poll::Ready(Ok(()));
+            }
+
+            if self.delay.is_none() {
+                let wait = (1.0 - self.tokens) / rate;
+                let wait = Duration::from_nanos((wait * 1e9).ceil() as u64);
+                self.delay = Some(Delay::new(now + wait));
+            }
+
+            match Pin::new(self.delay.as_mut().unwrap()).poll(lw) {
+                Poll::Ready(Ok(())) => self.delay = None,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+
+    /// Takes a token for an accepted connection.
+    pub(super) fn consume(&mut self) {
+        if self.rate.is_some() {
+            self.tokens -= 1.0;
+        }
+    }
+}
+
+impl Default for Throttle {
+    fn default() -> Throttle {
+        Throttle {
+            rate: None,
+            burst: 1,
+            tokens: 1.0,
+            refilled: Instant::now(),
+            delay: None,
+        }
+    }
+}
+
+impl fmt::Debug for Throttle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Throttle")
+            .field("rate", &self.rate)
+            .field("burst", &self.burst)
+            .finish()
+    }
+}
@@ -0,0 +1,240 @@
+//! Async timers.
+//!
+//! This module contains a [`Delay`] future which completes at a given instant,
+//! an [`Interval`] stream which yields periodically, and a [`Timeout`]
+//! combinator which fails an I/O future with `ErrorKind::TimedOut` if it
+//! doesn't complete in time. All of them are driven by the reactor's poll loop
+//! rather than by a thread per timer, so they compose with socket I/O in the
+//! same task.
+//!
+//! [`Delay`]: struct.Delay.html
+//! [`Interval`]: struct.Interval.html
+//! [`Timeout`]: struct.Timeout.html
+//!
+//! # Example
+//!
+//! ```no_run
+//! #![feature(async_await, await_macro, futures_api)]
+//! use futures::prelude::*;
+//! use romio::timer::TimeoutExt;
+//! use romio::TcpStream;
+//! use std::time::Duration;
+//!
+//! async fn read_greeting(stream: &mut TcpStream) -> std::io::Result<Vec<u8>> {
+//!     let mut buf = vec![0; 1024];
+//!     let n = await!(stream.read(&mut buf).timeout(Duration::from_secs(5)))?;
+//!     buf.truncate(n);
+//!     Ok(buf)
+//! }
+//! ```
+
+use std::io;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+use futures::stream::Stream;
+use futures::task::LocalWaker;
+use futures::{Future, Poll};
+
+pub use crate::reactor::Delay;
+
+/// A stream which yields at a fixed period.
+///
+/// Each item is the instant at which the tick was due. If the stream isn't
+/// polled for longer than a period, ticks are missed. An interval created with
+/// [`new`] then yields the missed ticks right away to catch up, while one
+/// created with [`new_skip`] drops them and continues at the next multiple of
+/// the period.
+///
+/// The stream ends only if the reactor is gone.
+///
+/// [`new`]: #method.new
+/// [`new_skip`]: #method.new_skip
+///
+/// # Examples
+///
+/// ```no_run
+/// #![feature(async_await, await_macro, futures_api)]
+/// use futures::prelude::*;
+/// use romio::timer::Interval;
+/// use std::time::Duration;
+///
+/// # async fn run() {
+/// let mut heartbeat = Interval::new(Duration::from_secs(1));
+///
+/// while let Some(tick) = await!(heartbeat.next()) {
+///     println!("heartbeat at {:?}", tick);
+/// }
+/// # }
+/// ```
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct Interval {
+    delay: Delay,
+    period: Duration,
+    skip: bool,
+}
+
+impl Interval {
+    /// Creates a new `Interval` which yields every `period`, starting one
+    /// `period` from now, and catches up on missed ticks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn new(period: Duration) -> Interval {
+        Interval::with_policy(period, false)
+    }
+
+    /// Creates a new `Interval` which yields every `period`, starting one
+    /// `period` from now, and skips missed ticks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn new_skip(period: Duration) -> Interval {
+        Interval::with_policy(period, true)
+    }
+
+    fn with_policy(period: Duration, skip: bool) -> Interval {
+        assert!(period > Duration::from_millis(0), "interval period must be non-zero");
+
+        Interval {
+            delay: Delay::new(Instant::now() + period),
+            period,
+            skip,
+        }
+    }
+
+    /// Returns the period of this interval.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+}
+
+impl Stream for Interval {
+    type Item = Instant;
+
+    fn poll_next(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Option<Instant>> {
+        match Pin::new(&mut self.delay).poll(lw) {
+            Poll::Ready(Ok(())) => {}
+            Poll::Ready(Err(_)) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+        }
+
+        let tick = self.delay.deadline();
+        let mut next = tick + self.period;
+
+        if self.skip {
+            let now = Instant::now();
+            while next <= now {
+                next += self.period;
+            }
+        }
+
+        self.delay.reset(next);
+        Poll::Ready(Some(tick))
+    }
+}
+
+/// A future which resolves with an `ErrorKind::TimedOut` error if the wrapped
+/// future doesn't complete before a deadline.
+///
+/// This is created by the [`TimeoutExt::timeout`] method, or with
+/// [`Timeout::new`].
+///
+/// [`TimeoutExt::timeout`]: trait.TimeoutExt.html#method.timeout
+/// [`Timeout::new`]: #method.new
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct Timeout<F> {
+    future: F,
+    delay: Delay,
+}
+
+impl<F> Timeout<F> {
+    /// Wraps `future`, failing it if it doesn't complete within `dur`.
+    ///
+    /// The time starts running right away, not when the future is first
+    /// polled.
+    pub fn new(future: F, dur: Duration) -> Timeout<F> {
+        Timeout::new_at(future, Instant::now() + dur)
+    }
+
+    /// Wraps `future`, failing it if it doesn't complete before `deadline`.
+    pub fn new_at(future: F, deadline: Instant) -> Timeout<F> {
+        Timeout {
+            future,
+            delay: Delay::new(deadline),
+        }
+    }
+
+    /// Consumes the timeout, returning the wrapped future.
+    pub fn into_inner(self) -> F {
+        self.future
+    }
+}
+
+impl<F, T, E> Future for Timeout<F>
+where
+    F: Future<Output = Result<T, E>>,
+    E: Into<io::Error>,
+{
+    type Output = io::Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<T>> {
+        // The wrapped future is pinned along with the timeout, while the delay
+        // is never moved out.
+        let future = unsafe { self.as_mut().map_unchecked_mut(|t| &mut t.future) };
+        if let Poll::Ready(res) = future.poll(lw) {
+            return Poll::Ready(res.map_err(Into::into));
+        }
+
+        let delay = unsafe { self.map_unchecked_mut(|t| &mut t.delay) };
+        match delay.poll(lw) {
+            Poll::Ready(Ok(())) => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "operation timed out",
+            ))),
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// An extension trait adding timeouts to futures which resolve to a `Result`
+/// whose error converts into an `io::Error`.
+pub trait TimeoutExt: Future + Sized {
+    /// Fails this future with an `ErrorKind::TimedOut` error if it doesn't
+    /// complete within `dur`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::timer::TimeoutExt;
+    /// use romio::TcpStream;
+    /// use std::time::Duration;
+    ///
+    /// # async fn run() -> std::io::Result<()> {
+    /// let addr = "127.0.0.1:8080".parse().unwrap();
+    /// let stream = await!(TcpStream::connect(&addr).timeout(Duration::from_secs(3)))?;
+    /// # Ok(()) }
+    /// ```
+    fn timeout(self, dur: Duration) -> Timeout<Self> {
+        Timeout::new(self, dur)
+    }
+
+    /// Fails this future with an `ErrorKind::TimedOut` error if it doesn't
+    /// complete before `deadline`.
+    fn timeout_at(self, deadline: Instant) -> Timeout<Self> {
+        Timeout::new_at(self, deadline)
+    }
+}
+
+impl<F, T, E> TimeoutExt for F
+where
+    F: Future<Output = Result<T, E>>,
+    E: Into<io::Error>,
+{
+}
@@ -0,0 +1,257 @@
+//! Shutdown signals for streams.
+//!
+//! The [`trigger`] function creates a [`Trigger`] and a [`Tripwire`]. The
+//! tripwire is a future which completes once the trigger was cancelled, and
+//! can be cloned to share one signal between any number of tasks. The
+//! [`UntilExt::until`] combinator ends a stream once its tripwire went off,
+//! for example to stop accepting connections while keeping the listener
+//! around.
+//!
+//! [`trigger`]: fn.trigger.html
+//! [`Trigger`]: struct.Trigger.html
+//! [`Tripwire`]: struct.Tripwire.html
+//! [`UntilExt::until`]: trait.UntilExt.html#method.until
+//!
+//! # Example
+//!
+//! ```no_run
+//! #![feature(async_await, await_macro, futures_api)]
+//! use futures::prelude::*;
+//! use romio::trigger::UntilExt;
+//! use romio::TcpListener;
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let listener = TcpListener::bind(&"127.0.0.1:7878".parse().unwrap())?;
+//! let (trigger, tripwire) = romio::trigger();
+//!
+//! // calling `trigger.cancel()` from anywhere ends the loop
+//! let mut incoming = listener.incoming_with_addr().until(tripwire);
+//! while let Some(accepted) = await!(incoming.next()) {
+//!     let (mut stream, _) = accepted?;
+//!     await!(stream.write_all(b"Good morrow!\n"))?;
+//! }
+//!
+//! println!("no longer accepting on {}", listener.local_addr()?);
+//! # Ok(()) }
+//! ```
+
+use std::fmt;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+use std::sync::{Arc, Mutex};
+
+use futures::stream::Stream;
+use futures::task::{LocalWaker, Waker};
+use futures::{Future, Poll};
+use slab::Slab;
+
+/// Creates a new `Trigger` and the `Tripwire` it sets off.
+pub fn trigger() -> (Trigger, Tripwire) {
+    let inner = Arc::new(Inner {
+        cancelled: AtomicBool::new(false),
+        wakers: Mutex::new(Slab::new()),
+    });
+    let trigger = Trigger {
+        inner: inner.clone(),
+    };
+    (trigger, Tripwire { inner, key: None })
+}
+
+/// Sets off the `Tripwire`s created along with it.
+///
+/// This is created by the [`trigger`] function. Dropping it without calling
+/// [`cancel`] leaves the tripwires waiting forever.
+///
+/// [`trigger`]: fn.trigger.html
+/// [`cancel`]: #method.cancel
+pub struct Trigger {
+    inner: Arc<Inner>,
+}
+
+/// A future which completes once its `Trigger` was cancelled.
+///
+/// Clones share the same trigger, so one call to `Trigger::cancel` completes
+/// all of them, whether they were polled before or only are afterwards.
+#[must_use = "futures do nothing unless polled"]
+pub struct Tripwire {
+    inner: Arc<Inner>,
+    /// The slot of this tripwire's waker in `Inner::wakers`, once it was
+    /// polled.
+    key: Option<usize>,
+}
+
+/// A stream which ends once a `Tripwire` went off.
+///
+/// This is created by the [`UntilExt::until`] method.
+///
+/// [`UntilExt::until`]: trait.UntilExt.html#method.until
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct Until<S> {
+    stream: S,
+    /// `None` once the tripwire went off.
+    tripwire: Option<Tripwire>,
+}
+
+/// The state shared by a `Trigger` and its `Tripwire`s.
+struct Inner {
+    cancelled: AtomicBool,
+    /// The tasks waiting on a tripwire, one slot for each tripwire which was
+    /// polled. A slot is emptied when its task is woken up, and removed when
+    /// its tripwire is dropped.
+    wakers: Mutex<Slab<Option<Waker>>>,
+}
+
+// ===== impl Trigger =====
+
+impl Trigger {
+    /// Sets off the tripwires, waking up all tasks waiting on them.
+    ///
+    /// Streams wrapped with `until` end the next time they are polled, without
+    /// waiting for their next item. Cancelling more than once has no effect.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, SeqCst);
+
+        let mut wakers = self.inner.wakers.lock().unwrap();
+        for (_, waker) in wakers.iter_mut() {
+            if let Some(waker) = waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Returns `true` if the trigger was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(SeqCst)
+    }
+}
+
+impl fmt::Debug for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Trigger")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+// ===== impl Tripwire =====
+
+impl Tripwire {
+    /// Returns `true` if the trigger was cancelled.
+    pub fn is_tripped(&self) -> bool {
+        self.inner.cancelled.load(SeqCst)
+    }
+}
+
+impl Future for Tripwire {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<()> {
+        if self.is_tripped() {
+            return Poll::Ready(());
+        }
+
+        let Tripwire { inner, key } = &mut *self;
+        let mut wakers = inner.wakers.lock().unwrap();
+        match *key {
+            Some(key) => match &mut wakers[key] {
+                Some(waker) if lw.will_wake_nonlocal(waker) => {}
+                slot => *slot = Some(lw.clone().into_waker()),
+            },
+            None => *key = Some(wakers.insert(Some(lw.clone().into_waker()))),
+        }
+        drop(wakers);
+
+        // Check again, the trigger may have been cancelled before the waker
+        // was registered.
+        if self.is_tripped() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Clone for Tripwire {
+    fn clone(&self) -> Tripwire {
+        // The clone gets a slot of its own once it is polled.
+        Tripwire {
+            inner: self.inner.clone(),
+            key: None,
+        }
+    }
+}
+
+impl Drop for Tripwire {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            self.inner.wakers.lock().unwrap().remove(key);
+        }
+    }
+}
+
+impl fmt::Debug for Tripwire {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tripwire")
+            .field("tripped", &self.is_tripped())
+            .finish()
+    }
+}
+
+// ===== impl Until =====
+
+impl<S> Until<S> {
+    /// Wraps `stream`, ending it once `tripwire` went off.
+    pub fn new(stream: S, tripwire: Tripwire) -> Until<S> {
+        Until {
+            stream,
+            tripwire: Some(tripwire),
+        }
+    }
+
+    /// Returns a reference to the wrapped stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Returns a mutable reference to the wrapped stream.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Consumes the `Until`, returning the wrapped stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: Stream + Unpin> Stream for Until<S> {
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Option<S::Item>> {
+        let tripped = match &mut self.tripwire {
+            Some(tripwire) => Pin::new(tripwire).poll(lw).is_ready(),
+            None => return Poll::Ready(None),
+        };
+        if tripped {
+            self.tripwire = None;
+            return Poll::Ready(None);
+        }
+
+        Pin::new(&mut self.stream).poll_next(lw)
+    }
+}
+
+/// An extension trait ending streams on a `Tripwire`.
+pub trait UntilExt: Stream + Sized {
+    /// Ends this stream once `tripwire` went off.
+    ///
+    /// The tripwire is checked before every item, so an item which is ready
+    /// when the trigger was cancelled isn't yielded anymore. The stream itself
+    /// is left alone and can be taken back with `into_inner`.
+    fn until(self, tripwire: Tripwire) -> Until<Self> {
+        Until::new(self, tripwire)
+    }
+}
+
+impl<S: Stream> UntilExt for S {}
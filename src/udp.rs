@@ -13,6 +13,8 @@
 
 use std::fmt;
 use std::io;
+#[cfg(target_os = "linux")]
+use std::net::IpAddr;
 use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::pin::Pin;
 
@@ -20,7 +22,9 @@ use futures::task::LocalWaker;
 use futures::Future;
 use futures::{ready, Poll};
 use mio;
+use net2;
 
+use crate::io::{AddrError, Operation};
 use crate::reactor::PollEvented;
 
 /// A UDP socket.
@@ -48,12 +52,15 @@ impl UdpSocket {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn bind(addr: &SocketAddr) -> io::Result<UdpSocket> {
-        mio::net::UdpSocket::bind(addr).map(UdpSocket::new)
+    pub fn bind(addr: &SocketAddr) -> Result<UdpSocket, AddrError> {
+        match mio::net::UdpSocket::bind(addr) {
+            Ok(socket) => Ok(UdpSocket::new(socket)),
+            Err(e) => Err(AddrError::new(Operation::Bind, addr, e)),
+        }
     }
 
     fn new(socket: mio::net::UdpSocket) -> UdpSocket {
-        let io = PollEvented::new(socket);
+        let io = PollEvented::new_ready(socket);
         UdpSocket { io: io }
     }
 
@@ -183,6 +190,311 @@ impl UdpSocket {
         }
     }
 
+    /// Receives data from the socket without removing it from the queue. On
+    /// success, returns the number of bytes read.
+    ///
+    /// The next call to `recv_from` or `peek` returns the same datagram again.
+    /// Like for `recv_from`, the rest of the datagram is discarded if `buf`
+    /// is too small to hold it, but only in this copy.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(futures_api, async_await, await_macro)]
+    /// # use std::error::Error;
+    /// use romio::udp::UdpSocket;
+    ///
+    /// # async fn peek_data() -> Result<(), Box<dyn Error + 'static>> {
+    /// let addr = "127.0.0.1:0".parse()?;
+    /// let mut socket = UdpSocket::bind(&addr)?;
+    /// let mut header = [0; 4];
+    ///
+    /// await!(socket.peek(&mut header))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(unix)]
+    pub fn peek<'a, 'b>(&'a mut self, buf: &'b mut [u8]) -> Peek<'a, 'b> {
+        Peek { buf, socket: self }
+    }
+
+    /// Receives data from the socket without removing it from the queue. On
+    /// success, returns the number of bytes read and the address from whence
+    /// the data came.
+    ///
+    /// The next call to `recv_from` or `peek_from` returns the same datagram
+    /// again.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(futures_api, async_await, await_macro)]
+    /// # use std::error::Error;
+    /// use romio::udp::UdpSocket;
+    ///
+    /// # async fn peek_data() -> Result<(), Box<dyn Error + 'static>> {
+    /// let addr = "127.0.0.1:0".parse()?;
+    /// let mut socket = UdpSocket::bind(&addr)?;
+    /// let mut buf = vec![0; 1024];
+    ///
+    /// let (_, from) = await!(socket.peek_from(&mut buf))?;
+    /// let (len, _) = await!(socket.recv_from(&mut buf))?;
+    /// println!("received {} bytes from {}", len, from);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(unix)]
+    pub fn peek_from<'a, 'b>(&'a mut self, buf: &'b mut [u8]) -> PeekFrom<'a, 'b> {
+        PeekFrom { buf, socket: self }
+    }
+
+    /// Receives data from the socket without removing it from the queue.
+    ///
+    /// # Return
+    ///
+    /// On success, returns `Ok(Poll::Ready(num_bytes_read))`.
+    ///
+    /// If the socket is not ready for receiving, the method returns
+    /// `Ok(Poll::Pending)` and arranges for the current task to receive a
+    /// notification when the socket becomes readable.
+    #[cfg(unix)]
+    pub fn poll_peek(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        ready!(self.io.poll_read_ready(lw)?);
+
+        match sys::peek(self.io.get_ref(), buf) {
+            Ok(n) => Poll::Ready(Ok(n)),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.io.clear_read_ready(lw)?;
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+
+    /// Receives data from the socket without removing it from the queue.
+    ///
+    /// # Return
+    ///
+    /// On success, returns `Ok(Poll::Ready((num_bytes_read, source_addr)))`.
+    ///
+    /// If the socket is not ready for receiving, the method returns
+    /// `Ok(Poll::Pending)` and arranges for the current task to receive a
+    /// notification when the socket becomes readable.
+    #[cfg(unix)]
+    pub fn poll_peek_from(
+        &mut self,
+        lw: &LocalWaker,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<(usize, SocketAddr)>> {
+        ready!(self.io.poll_read_ready(lw)?);
+
+        match sys::peek_from(self.io.get_ref(), buf) {
+            Ok(n) => Poll::Ready(Ok(n)),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.io.clear_read_ready(lw)?;
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+
+    /// Receives several datagrams from the socket at once, one into each of
+    /// the given buffers.
+    ///
+    /// On success, returns the number of bytes read and the address from whence
+    /// the data came for each datagram that was received, in the order of
+    /// `bufs`. At least one datagram is received, and at most `bufs.len()`.
+    ///
+    /// On Linux this uses a single `recvmmsg` system call. On other platforms
+    /// it receives the datagrams which are already queued on the socket with
+    /// repeated calls to `recv_from`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(futures_api, async_await, await_macro)]
+    /// # use std::error::Error;
+    /// use romio::udp::UdpSocket;
+    ///
+    /// # async fn recv_data() -> Result<(), Box<dyn Error + 'static>> {
+    /// let addr = "127.0.0.1:0".parse()?;
+    /// let mut socket = UdpSocket::bind(&addr)?;
+    /// let mut bufs = vec![vec![0; 1500]; 32];
+    /// let mut bufs: Vec<&mut [u8]> = bufs.iter_mut().map(|buf| &mut buf[..]).collect();
+    ///
+    /// for (len, addr) in await!(socket.recv_mmsg(&mut bufs))? {
+    ///     println!("received {} bytes from {}", len, addr);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn recv_mmsg<'a, 'b, 'c>(
+        &'a mut self,
+        bufs: &'b mut [&'c mut [u8]],
+    ) -> RecvMmsg<'a, 'b, 'c> {
+        RecvMmsg { bufs, socket: self }
+    }
+
+    /// Receives several datagrams from the socket at once, one into each of
+    /// the given buffers.
+    ///
+    /// # Return
+    ///
+    /// On success, returns `Ok(Poll::Ready(datagrams))`, with the number of
+    /// bytes read and the source address of each datagram received.
+    ///
+    /// If the socket is not ready for receiving, the method returns
+    /// `Ok(Poll::Pending)` and arranges for the current task to receive a
+    /// notification when the socket becomes readable.
+    pub fn poll_recv_mmsg(
+        &mut self,
+        lw: &LocalWaker,
+        bufs: &mut [&mut [u8]],
+    ) -> Poll<io::Result<Vec<(usize, SocketAddr)>>> {
+        if bufs.is_empty() {
+            return Poll::Ready(Ok(Vec::new()));
+        }
+
+        ready!(self.io.poll_read_ready(lw)?);
+
+        match mmsg::recv_mmsg(self.io.get_ref(), bufs) {
+            Ok(received) => Poll::Ready(Ok(received)),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.io.clear_read_ready(lw)?;
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+
+    /// Receives data from the socket, along with the address it was sent to.
+    ///
+    /// On success, returns the number of bytes read, the address from whence
+    /// the data came, and the destination address of the datagram. On a
+    /// socket bound to a wildcard address like `0.0.0.0` this tells which of
+    /// the local addresses the datagram arrived on, so a reply can be sent
+    /// from the same one with [`send_to_from`]. For broadcast and multicast
+    /// datagrams it is the broadcast or multicast address.
+    ///
+    /// The destination address is only received once it was enabled with
+    /// [`set_pktinfo`], otherwise it is `None`. The datagram is received
+    /// either way.
+    ///
+    /// This is only supported on Linux.
+    ///
+    /// [`send_to_from`]: #method.send_to_from
+    /// [`set_pktinfo`]: #method.set_pktinfo
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(futures_api, async_await, await_macro)]
+    /// # use std::error::Error;
+    /// use romio::udp::UdpSocket;
+    ///
+    /// # async fn echo() -> Result<(), Box<dyn Error + 'static>> {
+    /// let addr = "0.0.0.0:7878".parse()?;
+    /// let mut socket = UdpSocket::bind(&addr)?;
+    /// socket.set_pktinfo(true)?;
+    /// let mut buf = vec![0; 1024];
+    ///
+    /// loop {
+    ///     let (len, from, to) = await!(socket.recv_from_with_dst(&mut buf))?;
+    ///     let to = to.expect("pktinfo is enabled");
+    ///     await!(socket.send_to_from(&buf[..len], &from, &to))?;
+    /// }
+    /// # }
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn recv_from_with_dst<'a, 'b>(&'a mut self, buf: &'b mut [u8]) -> RecvFromWithDst<'a, 'b> {
+        RecvFromWithDst { buf, socket: self }
+    }
+
+    /// Receives data from the socket, along with the address it was sent to.
+    ///
+    /// # Return
+    ///
+    /// On success, returns `Ok(Poll::Ready((num_bytes_read, source_addr,
+    /// destination_addr)))`. The destination address is `None` unless it was
+    /// enabled with `set_pktinfo`.
+    ///
+    /// If the socket is not ready for receiving, the method returns
+    /// `Ok(Poll::Pending)` and arranges for the current task to receive a
+    /// notification when the socket becomes readable.
+    #[cfg(target_os = "linux")]
+    pub fn poll_recv_from_with_dst(
+        &mut self,
+        lw: &LocalWaker,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<(usize, SocketAddr, Option<IpAddr>)>> {
+        ready!(self.io.poll_read_ready(lw)?);
+
+        match pktinfo::recv_from_with_dst(self.io.get_ref(), buf) {
+            Ok(received) => Poll::Ready(Ok(received)),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.io.clear_read_ready(lw)?;
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+
+    /// Sends data on the socket to the given address, from the local address
+    /// `source`. On success, returns the number of bytes written.
+    ///
+    /// This is the counterpart of [`recv_from_with_dst`], for replying to a
+    /// datagram from the address it was sent to. `source` must be one of the
+    /// local addresses, or the wildcard address to let the system choose, and
+    /// of the same IP version as `target`.
+    ///
+    /// This is only supported on Linux.
+    ///
+    /// [`recv_from_with_dst`]: #method.recv_from_with_dst
+    #[cfg(target_os = "linux")]
+    pub fn send_to_from<'a, 'b>(
+        &'a mut self,
+        buf: &'b [u8],
+        target: &'b SocketAddr,
+        source: &'b IpAddr,
+    ) -> SendToFrom<'a, 'b> {
+        SendToFrom {
+            buf,
+            target,
+            source,
+            socket: self,
+        }
+    }
+
+    /// Sends data on the socket to the given address, from the local address
+    /// `source`.
+    ///
+    /// # Return
+    ///
+    /// On success, returns `Ok(Poll::Ready(num_bytes_written))`.
+    ///
+    /// If the socket is not ready for writing, the method returns
+    /// `Ok(Poll::Pending)` and arranges for the current task to receive a
+    /// notification when the socket becomes writable.
+    #[cfg(target_os = "linux")]
+    pub fn poll_send_to_from(
+        &mut self,
+        lw: &LocalWaker,
+        buf: &[u8],
+        target: &SocketAddr,
+        source: &IpAddr,
+    ) -> Poll<io::Result<usize>> {
+        ready!(self.io.poll_write_ready(lw)?);
+
+        match pktinfo::send_to_from(self.io.get_ref(), buf, target, source) {
+            Ok(n) => Poll::Ready(Ok(n)),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.io.clear_write_ready(lw)?;
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+
     /// Check the UDP socket's read readiness state.
     ///
     /// If the socket is not ready for receiving then `Poll::Pending` is
@@ -301,6 +613,137 @@ impl UdpSocket {
         self.io.get_ref().set_ttl(ttl)
     }
 
+    /// Returns the name of the network interface this socket is bound to, if
+    /// any.
+    ///
+    /// For more information about this option, see [`set_device`].
+    ///
+    /// [`set_device`]: #method.set_device
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn device(&self) -> io::Result<Option<String>> {
+        crate::device::device(self.io.get_ref())
+    }
+
+    /// Binds this socket to the network interface named `device`, or removes
+    /// the binding if `None`.
+    ///
+    /// Only datagrams received on that interface are delivered to the socket,
+    /// and datagrams it sends always leave through it. This uses
+    /// `SO_BINDTODEVICE` on Linux, which usually requires the `CAP_NET_RAW`
+    /// capability, and `IP_BOUND_IF` on macOS. Names longer than the platform
+    /// allows are rejected with an `InvalidInput` error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::udp::UdpSocket;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let socket = UdpSocket::bind(&"0.0.0.0:5353".parse()?)?;
+    /// socket.set_device(Some("eth0"))?;
+    /// assert_eq!(socket.device()?, Some("eth0".to_string()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn set_device(&self, device: Option<&str>) -> io::Result<()> {
+        crate::device::set_device(self.io.get_ref(), device)
+    }
+
+    /// Gets the value of the `SO_MARK` option on this socket.
+    ///
+    /// For more information about this option, see [`set_mark`].
+    ///
+    /// [`set_mark`]: #method.set_mark
+    #[cfg(target_os = "linux")]
+    pub fn mark(&self) -> io::Result<u32> {
+        crate::mark::mark(self.io.get_ref())
+    }
+
+    /// Sets the value of the `SO_MARK` option on this socket.
+    ///
+    /// The mark is attached to the datagrams sent from the socket, so they can
+    /// be routed with `ip rule fwmark` or matched by packet filters. Setting it
+    /// requires the `CAP_NET_ADMIN` capability, otherwise an error of kind
+    /// `PermissionDenied` is returned.
+    ///
+    /// This option is only supported on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn set_mark(&self, mark: u32) -> io::Result<()> {
+        crate::mark::set_mark(self.io.get_ref(), mark)
+    }
+
+    /// Gets the value of the `SO_PRIORITY` option on this socket.
+    ///
+    /// For more information about this option, see [`set_priority`].
+    ///
+    /// [`set_priority`]: #method.set_priority
+    pub fn priority(&self) -> io::Result<u32> {
+        crate::priority::priority(self.io.get_ref())
+    }
+
+    /// Sets the value of the `SO_PRIORITY` option on this socket.
+    ///
+    /// The priority selects the band of a queueing discipline like `prio`
+    /// which the datagrams sent from the socket are queued in. Priorities from
+    /// 0 to 6 can be set by anyone, higher ones require the `CAP_NET_ADMIN`
+    /// capability, otherwise an error of kind `PermissionDenied` is returned.
+    ///
+    /// This option is only supported on Linux, elsewhere an error of kind
+    /// `Other` is returned.
+    pub fn set_priority(&self, priority: u32) -> io::Result<()> {
+        crate::priority::set_priority(self.io.get_ref(), priority)
+    }
+
+    /// Returns `true` if the destination addresses of received datagrams are
+    /// reported.
+    ///
+    /// For more information about this option, see [`set_pktinfo`].
+    ///
+    /// [`set_pktinfo`]: #method.set_pktinfo
+    #[cfg(target_os = "linux")]
+    pub fn pktinfo(&self) -> io::Result<bool> {
+        pktinfo::pktinfo(self.io.get_ref())
+    }
+
+    /// Sets whether the destination addresses of received datagrams are
+    /// reported, as needed by [`recv_from_with_dst`].
+    ///
+    /// This sets the `IP_PKTINFO` option on an IPv4 socket, and the
+    /// `IPV6_RECVPKTINFO` option on an IPv6 one. An IPv6 socket which also
+    /// receives IPv4 datagrams gets `IP_PKTINFO` as well. Sending from a
+    /// chosen address with [`send_to_from`] works without either of them.
+    ///
+    /// This option is only supported on Linux.
+    ///
+    /// [`recv_from_with_dst`]: #method.recv_from_with_dst
+    /// [`send_to_from`]: #method.send_to_from
+    #[cfg(target_os = "linux")]
+    pub fn set_pktinfo(&self, on: bool) -> io::Result<()> {
+        pktinfo::set_pktinfo(self.io.get_ref(), on)
+    }
+
+    /// Binds this socket to the network interface named `ifname`.
+    ///
+    /// This is the same as calling [`set_device`] with `Some(ifname)`.
+    ///
+    /// [`set_device`]: #method.set_device
+    #[cfg(target_os = "linux")]
+    pub fn bind_device(&self, ifname: &str) -> io::Result<()> {
+        self.set_device(Some(ifname))
+    }
+
+    /// Gets the value of the `IPV6_V6ONLY` option for this socket.
+    ///
+    /// If this is `true`, a socket bound to an IPv6 address only exchanges
+    /// datagrams with IPv6 peers. The option can be configured before binding
+    /// with [`UdpSocketBuilder::only_v6`].
+    ///
+    /// [`UdpSocketBuilder::only_v6`]: struct.UdpSocketBuilder.html#method.only_v6
+    pub fn only_v6(&self) -> io::Result<bool> {
+        self.io.get_ref().only_v6()
+    }
+
     /// Executes an operation of the `IP_ADD_MEMBERSHIP` type.
     ///
     /// This function specifies a new multicast group for this socket to join.
@@ -370,6 +813,131 @@ impl UdpSocket {
     }
 }
 
+/// A builder for configuring a [`UdpSocket`] before it is bound.
+///
+/// Some socket options only take effect if they are set before the socket is
+/// bound to an address. `UdpSocketBuilder` collects those options and applies
+/// them when [`bind`] is called.
+///
+/// [`UdpSocket`]: struct.UdpSocket.html
+/// [`bind`]: #method.bind
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use romio::udp::UdpSocketBuilder;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let socket_addr = "[::]:0".parse()?;
+/// let socket = UdpSocketBuilder::new()
+///     .only_v6(false)
+///     .bind(&socket_addr)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct UdpSocketBuilder {
+    only_v6: Option<bool>,
+    reuse_address: Option<bool>,
+    #[cfg(unix)]
+    reuse_port: Option<bool>,
+}
+
+impl UdpSocketBuilder {
+    /// Creates a new builder with the default configuration.
+    pub fn new() -> UdpSocketBuilder {
+        UdpSocketBuilder::default()
+    }
+
+    /// Sets the value of the `IPV6_V6ONLY` option on the socket.
+    ///
+    /// If this is set to `true` then a socket bound to an IPv6 address only
+    /// exchanges datagrams with IPv6 peers. If it is set to `false` then a
+    /// socket bound to the IPv6 wildcard address `[::]` also receives IPv4
+    /// datagrams, whose source addresses are reported as IPv4-mapped IPv6
+    /// addresses.
+    ///
+    /// When this option is not set the operating system default is used, which
+    /// differs between platforms. It is ignored when binding to an IPv4
+    /// address.
+    pub fn only_v6(&mut self, only_v6: bool) -> &mut UdpSocketBuilder {
+        self.only_v6 = Some(only_v6);
+        self
+    }
+
+    /// Sets the value of the `SO_REUSEADDR` option on the socket.
+    ///
+    /// This allows binding to an address which is still in use by another
+    /// socket, with semantics that differ between platforms. To spread the
+    /// datagrams sent to a port over several sockets, use [`reuse_port`]
+    /// instead.
+    ///
+    /// [`reuse_port`]: #method.reuse_port
+    pub fn reuse_address(&mut self, reuse: bool) -> &mut UdpSocketBuilder {
+        self.reuse_address = Some(reuse);
+        self
+    }
+
+    /// Sets the value of the `SO_REUSEPORT` option on the socket.
+    ///
+    /// Several sockets which all set this option can be bound to the same
+    /// address, for example to run a worker for each of them. On Linux the
+    /// incoming datagrams are distributed between the sockets.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::udp::UdpSocketBuilder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let socket_addr = "0.0.0.0:5353".parse()?;
+    /// let workers = (0..4)
+    ///     .map(|_| UdpSocketBuilder::new().reuse_port(true).bind(&socket_addr))
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(unix)]
+    pub fn reuse_port(&mut self, reuse: bool) -> &mut UdpSocketBuilder {
+        self.reuse_port = Some(reuse);
+        self
+    }
+
+    /// Creates a new `UdpSocket` bound to the specified address, with the
+    /// options of this builder applied before binding.
+    pub fn bind(&self, addr: &SocketAddr) -> Result<UdpSocket, AddrError> {
+        self.build(addr)
+            .map_err(|e| AddrError::new(Operation::Bind, addr, e))
+    }
+
+    fn build(&self, addr: &SocketAddr) -> io::Result<UdpSocket> {
+        let builder = match *addr {
+            SocketAddr::V4(..) => net2::UdpBuilder::new_v4()?,
+            SocketAddr::V6(..) => net2::UdpBuilder::new_v6()?,
+        };
+
+        if let (SocketAddr::V6(..), Some(only_v6)) = (addr, self.only_v6) {
+            builder.only_v6(only_v6)?;
+        }
+
+        if let Some(reuse) = self.reuse_address {
+            builder.reuse_address(reuse)?;
+        }
+
+        #[cfg(unix)]
+        {
+            use net2::unix::UnixUdpBuilderExt;
+
+            if let Some(reuse) = self.reuse_port {
+                builder.reuse_port(reuse)?;
+            }
+        }
+
+        let socket = builder.bind(addr)?;
+        mio::net::UdpSocket::from_socket(socket).map(UdpSocket::new)
+    }
+}
+
 impl fmt::Debug for UdpSocket {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.io.get_ref().fmt(f)
@@ -379,6 +947,9 @@ impl fmt::Debug for UdpSocket {
 #[cfg(all(unix))]
 mod sys {
     use super::UdpSocket;
+    use std::io;
+    use std::mem::ManuallyDrop;
+    use std::net::{self, SocketAddr};
     use std::os::unix::prelude::*;
 
     impl AsRawFd for UdpSocket {
@@ -386,6 +957,257 @@ mod sys {
             self.io.get_ref().as_raw_fd()
         }
     }
+
+    // mio doesn't expose `MSG_PEEK`, so the socket is borrowed as a std one,
+    // without closing it afterwards.
+
+    pub(super) fn peek(socket: &mio::net::UdpSocket, buf: &mut [u8]) -> io::Result<usize> {
+        let socket = ManuallyDrop::new(unsafe { net::UdpSocket::from_raw_fd(socket.as_raw_fd()) });
+        socket.peek(buf)
+    }
+
+    pub(super) fn peek_from(
+        socket: &mio::net::UdpSocket,
+        buf: &mut [u8],
+    ) -> io::Result<(usize, SocketAddr)> {
+        let socket = ManuallyDrop::new(unsafe { net::UdpSocket::from_raw_fd(socket.as_raw_fd()) });
+        socket.peek_from(buf)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod mmsg {
+    use std::io;
+    use std::mem;
+    use std::net::SocketAddr;
+    use std::os::unix::prelude::*;
+    use std::ptr;
+
+    use libc;
+    use mio;
+
+    pub(super) fn recv_mmsg(
+        socket: &mio::net::UdpSocket,
+        bufs: &mut [&mut [u8]],
+    ) -> io::Result<Vec<(usize, SocketAddr)>> {
+        let mut addrs: Vec<libc::sockaddr_storage> = vec![unsafe { mem::zeroed() }; bufs.len()];
+        let mut iovecs: Vec<libc::iovec> = bufs
+            .iter_mut()
+            .map(|buf| libc::iovec {
+                iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+                iov_len: buf.len(),
+            })
+            .collect();
+        let mut msgs: Vec<libc::mmsghdr> = iovecs
+            .iter_mut()
+            .zip(addrs.iter_mut())
+            .map(|(iovec, addr)| {
+                let mut msg: libc::mmsghdr = unsafe { mem::zeroed() };
+                msg.msg_hdr.msg_name = addr as *mut _ as *mut libc::c_void;
+                msg.msg_hdr.msg_namelen =
+                    mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+                msg.msg_hdr.msg_iov = iovec;
+                msg.msg_hdr.msg_iovlen = 1;
+                msg
+            })
+            .collect();
+
+        let n = unsafe {
+            libc::recvmmsg(
+                socket.as_raw_fd(),
+                msgs.as_mut_ptr(),
+                msgs.len() as libc::c_uint,
+                0,
+                ptr::null_mut(),
+            )
+        };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        msgs[..n as usize]
+            .iter()
+            .zip(addrs.iter())
+            .map(|(msg, addr)| Ok((msg.msg_len as usize, crate::sockaddr::to_socket_addr(addr)?)))
+            .collect()
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod pktinfo {
+    use std::io;
+    use std::mem;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+    use std::os::unix::prelude::*;
+    use std::ptr;
+
+    use libc;
+    use mio;
+
+    use crate::sockopt::{getsockopt, setsockopt};
+
+    /// `struct in6_pktinfo`, which libc doesn't define for Linux.
+    #[repr(C)]
+    struct In6Pktinfo {
+        ipi6_addr: libc::in6_addr,
+        ipi6_ifindex: libc::c_uint,
+    }
+
+    /// A buffer for control messages, large and aligned enough for either
+    /// kind of pktinfo.
+    type Control = [u64; 8];
+
+    pub(super) fn pktinfo(socket: &mio::net::UdpSocket) -> io::Result<bool> {
+        let on = match socket.local_addr()? {
+            SocketAddr::V4(..) => getsockopt(socket, libc::IPPROTO_IP, libc::IP_PKTINFO)?,
+            SocketAddr::V6(..) => getsockopt(socket, libc::IPPROTO_IPV6, libc::IPV6_RECVPKTINFO)?,
+        };
+        Ok(on != 0)
+    }
+
+    pub(super) fn set_pktinfo(socket: &mio::net::UdpSocket, on: bool) -> io::Result<()> {
+        let on = on as libc::c_int;
+        match socket.local_addr()? {
+            SocketAddr::V4(..) => setsockopt(socket, libc::IPPROTO_IP, libc::IP_PKTINFO, on),
+            SocketAddr::V6(..) => {
+                setsockopt(socket, libc::IPPROTO_IPV6, libc::IPV6_RECVPKTINFO, on)?;
+                if !socket.only_v6()? {
+                    setsockopt(socket, libc::IPPROTO_IP, libc::IP_PKTINFO, on)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub(super) fn recv_from_with_dst(
+        socket: &mio::net::UdpSocket,
+        buf: &mut [u8],
+    ) -> io::Result<(usize, SocketAddr, Option<IpAddr>)> {
+        let mut addr: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        let mut iovec = libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+        let mut control: Control = [0; 8];
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_name = &mut addr as *mut _ as *mut libc::c_void;
+        msg.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+        msg.msg_iov = &mut iovec;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = mem::size_of::<Control>() as _;
+
+        let n = unsafe { libc::recvmsg(socket.as_raw_fd(), &mut msg, 0) };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let source = crate::sockaddr::to_socket_addr(&addr)?;
+
+        let mut destination = None;
+        let mut cmsg = unsafe { libc::CMSG_FIRSTHDR(&msg) };
+        while !cmsg.is_null() {
+            let (level, ty) = unsafe { ((*cmsg).cmsg_level, (*cmsg).cmsg_type) };
+            let data = unsafe { libc::CMSG_DATA(cmsg) };
+            match (level, ty) {
+                (libc::IPPROTO_IP, libc::IP_PKTINFO) => {
+                    let info = unsafe { ptr::read_unaligned(data as *const libc::in_pktinfo) };
+                    let ip = Ipv4Addr::from(u32::from_be(info.ipi_addr.s_addr));
+                    destination = Some(IpAddr::V4(ip));
+                }
+                (libc::IPPROTO_IPV6, libc::IPV6_PKTINFO) => {
+                    let info = unsafe { ptr::read_unaligned(data as *const In6Pktinfo) };
+                    destination = Some(IpAddr::V6(Ipv6Addr::from(info.ipi6_addr.s6_addr)));
+                }
+                _ => {}
+            }
+            cmsg = unsafe { libc::CMSG_NXTHDR(&msg, cmsg) };
+        }
+
+        Ok((n as usize, source, destination))
+    }
+
+    pub(super) fn send_to_from(
+        socket: &mio::net::UdpSocket,
+        buf: &[u8],
+        target: &SocketAddr,
+        source: &IpAddr,
+    ) -> io::Result<usize> {
+        let (mut addr, addr_len) = crate::sockaddr::from_socket_addr(target);
+        let mut iovec = libc::iovec {
+            iov_base: buf.as_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+        let mut control: Control = [0; 8];
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_name = &mut addr as *mut _ as *mut libc::c_void;
+        msg.msg_namelen = addr_len;
+        msg.msg_iov = &mut iovec;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+
+        unsafe {
+            match source {
+                IpAddr::V4(source) => {
+                    let mut info: libc::in_pktinfo = mem::zeroed();
+                    info.ipi_spec_dst.s_addr = u32::from(*source).to_be();
+                    add_cmsg(&mut msg, libc::IPPROTO_IP, libc::IP_PKTINFO, info);
+                }
+                IpAddr::V6(source) => {
+                    let mut info: In6Pktinfo = mem::zeroed();
+                    info.ipi6_addr.s6_addr = source.octets();
+                    add_cmsg(&mut msg, libc::IPPROTO_IPV6, libc::IPV6_PKTINFO, info);
+                }
+            }
+        }
+
+        let n = unsafe { libc::sendmsg(socket.as_raw_fd(), &msg, 0) };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(n as usize)
+    }
+
+    /// Stores `data` as the only control message of `msg`, whose control
+    /// buffer must be large enough for it.
+    unsafe fn add_cmsg<T>(msg: &mut libc::msghdr, level: libc::c_int, ty: libc::c_int, data: T) {
+        let len = mem::size_of::<T>() as libc::c_uint;
+        msg.msg_controllen = libc::CMSG_SPACE(len) as _;
+
+        let cmsg = libc::CMSG_FIRSTHDR(msg);
+        (*cmsg).cmsg_level = level;
+        (*cmsg).cmsg_type = ty;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(len) as _;
+        ptr::write_unaligned(libc::CMSG_DATA(cmsg) as *mut T, data);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod mmsg {
+    use std::io;
+    use std::net::SocketAddr;
+
+    use mio;
+
+    pub(super) fn recv_mmsg(
+        socket: &mio::net::UdpSocket,
+        bufs: &mut [&mut [u8]],
+    ) -> io::Result<Vec<(usize, SocketAddr)>> {
+        let mut received = Vec::new();
+
+        for buf in bufs.iter_mut() {
+            match socket.recv_from(buf) {
+                Ok(datagram) => received.push(datagram),
+                // Stop once the queue is drained, but only report `WouldBlock`
+                // if nothing was received at all.
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock && !received.is_empty() => {
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(received)
+    }
 }
 
 /// The future returned by `UdpSocket::send_to`
@@ -420,3 +1242,98 @@ impl<'a, 'b> Future for RecvFrom<'a, 'b> {
         socket.poll_recv_from(lw, buf)
     }
 }
+
+/// The future returned by `UdpSocket::recv_from_with_dst`
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+pub struct RecvFromWithDst<'a, 'b> {
+    socket: &'a mut UdpSocket,
+    buf: &'b mut [u8],
+}
+
+#[cfg(target_os = "linux")]
+impl<'a, 'b> Future for RecvFromWithDst<'a, 'b> {
+    type Output = io::Result<(usize, SocketAddr, Option<IpAddr>)>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        let RecvFromWithDst { socket, buf } = &mut *self;
+        socket.poll_recv_from_with_dst(lw, buf)
+    }
+}
+
+/// The future returned by `UdpSocket::send_to_from`
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+pub struct SendToFrom<'a, 'b> {
+    socket: &'a mut UdpSocket,
+    buf: &'b [u8],
+    target: &'b SocketAddr,
+    source: &'b IpAddr,
+}
+
+#[cfg(target_os = "linux")]
+impl<'a, 'b> Future for SendToFrom<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        let SendToFrom {
+            socket,
+            buf,
+            target,
+            source,
+        } = &mut *self;
+        socket.poll_send_to_from(lw, buf, target, source)
+    }
+}
+
+/// The future returned by `UdpSocket::peek`
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct Peek<'a, 'b> {
+    socket: &'a mut UdpSocket,
+    buf: &'b mut [u8],
+}
+
+#[cfg(unix)]
+impl<'a, 'b> Future for Peek<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        let Peek { socket, buf } = &mut *self;
+        socket.poll_peek(lw, buf)
+    }
+}
+
+/// The future returned by `UdpSocket::peek_from`
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct PeekFrom<'a, 'b> {
+    socket: &'a mut UdpSocket,
+    buf: &'b mut [u8],
+}
+
+#[cfg(unix)]
+impl<'a, 'b> Future for PeekFrom<'a, 'b> {
+    type Output = io::Result<(usize, SocketAddr)>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        let PeekFrom { socket, buf } = &mut *self;
+        socket.poll_peek_from(lw, buf)
+    }
+}
+
+/// The future returned by `UdpSocket::recv_mmsg`
+#[derive(Debug)]
+pub struct RecvMmsg<'a, 'b, 'c> {
+    socket: &'a mut UdpSocket,
+    bufs: &'b mut [&'c mut [u8]],
+}
+
+impl<'a, 'b, 'c> Future for RecvMmsg<'a, 'b, 'c> {
+    type Output = io::Result<Vec<(usize, SocketAddr)>>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        let RecvMmsg { socket, bufs } = &mut *self;
+        socket.poll_recv_mmsg(lw, bufs)
+    }
+}
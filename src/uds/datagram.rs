@@ -1,16 +1,22 @@
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use super::ucred::{self, UCred};
+use crate::io::{AddrError, Operation};
 use crate::reactor::PollEvented;
 
+use futures::stream::Stream;
 use futures::task::LocalWaker;
-use futures::{ready, Poll};
+use futures::{ready, Future, Poll};
 use mio::Ready;
 use mio_uds;
 
 use std::fmt;
 use std::io;
+use std::mem;
 use std::net::Shutdown;
 use std::os::unix::io::{AsRawFd, RawFd};
 use std::os::unix::net::SocketAddr;
 use std::path::Path;
+use std::pin::Pin;
 
 /// An I/O object representing a Unix datagram socket.
 pub struct UnixDatagram {
@@ -29,8 +35,10 @@ impl UnixDatagram {
     /// let sock = UnixDatagram::bind("/tmp/sock")?;
     /// # Ok(()) }
     /// ```
-    pub fn bind(path: impl AsRef<Path>) -> io::Result<UnixDatagram> {
-        let socket = mio_uds::UnixDatagram::bind(path)?;
+    pub fn bind(path: impl AsRef<Path>) -> Result<UnixDatagram, AddrError> {
+        let path = path.as_ref();
+        let socket = mio_uds::UnixDatagram::bind(path)
+            .map_err(|e| AddrError::with_path(Operation::Bind, path, e))?;
         Ok(UnixDatagram::new(socket))
     }
 
@@ -58,7 +66,7 @@ impl UnixDatagram {
     }
 
     fn new(socket: mio_uds::UnixDatagram) -> UnixDatagram {
-        let io = PollEvented::new(socket);
+        let io = PollEvented::new_ready(socket);
         UnixDatagram { io }
     }
 
@@ -78,6 +86,32 @@ impl UnixDatagram {
         Ok(UnixDatagram::new(socket))
     }
 
+    /// Connects the socket to the socket named by `path`.
+    ///
+    /// Datagrams are then sent to it with [`send`] and only received from it
+    /// with [`recv`].
+    ///
+    /// [`send`]: #method.send
+    /// [`recv`]: #method.recv
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::uds::UnixDatagram;
+    ///
+    /// # fn run() -> std::io::Result<()> {
+    /// let sock = UnixDatagram::unbound()?;
+    /// sock.connect("/tmp/sock")?;
+    /// # Ok(()) }
+    /// ```
+    pub fn connect(&self, path: impl AsRef<Path>) -> Result<(), AddrError> {
+        let path = path.as_ref();
+        self.io
+            .get_ref()
+            .connect(path)
+            .map_err(|e| AddrError::with_path(Operation::Connect, path, e))
+    }
+
     /// Test whether this socket is ready to be read or not.
     pub fn poll_read_ready(&self, lw: &LocalWaker) -> Poll<io::Result<Ready>> {
         self.io.poll_read_ready(lw)
@@ -121,6 +155,126 @@ impl UnixDatagram {
         self.io.get_ref().peer_addr()
     }
 
+    /// Sends data on the socket to the socket named by `path`.
+    ///
+    /// On success, returns the number of bytes written. A datagram is sent
+    /// whole or not at all. Sending to a path which doesn't exist fails with
+    /// an error of kind `NotFound`, and to a socket nobody is bound to anymore
+    /// with one of kind `ConnectionRefused`, both right away.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::uds::UnixDatagram;
+    ///
+    /// # async fn run() -> std::io::Result<()> {
+    /// let mut socket = UnixDatagram::unbound()?;
+    /// await!(socket.send_to(b"Good morrow!", "/tmp/sock"))?;
+    /// # Ok(()) }
+    /// ```
+    pub fn send_to<'a, 'b>(
+        &'a mut self,
+        buf: &'b [u8],
+        path: &'b (impl AsRef<Path> + ?Sized),
+    ) -> SendTo<'a, 'b> {
+        SendTo {
+            socket: self,
+            buf,
+            path: path.as_ref(),
+        }
+    }
+
+    /// Receives data from the socket. On success, returns the number of bytes
+    /// read and the address from whence the data came.
+    ///
+    /// A datagram is received whole, or truncated to the size of `buf` if it
+    /// doesn't fit, in which case the rest of it is lost.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::uds::UnixDatagram;
+    ///
+    /// # async fn run() -> std::io::Result<()> {
+    /// let mut socket = UnixDatagram::bind("/tmp/sock")?;
+    ///
+    /// let mut buf = [0; 1024];
+    /// let (n, addr) = await!(socket.recv_from(&mut buf))?;
+    /// println!("{} bytes from {:?}", n, addr);
+    /// # Ok(()) }
+    /// ```
+    pub fn recv_from<'a, 'b>(&'a mut self, buf: &'b mut [u8]) -> RecvFrom<'a, 'b> {
+        RecvFrom { socket: self, buf }
+    }
+
+    /// Sends data on the socket to the socket it is connected to.
+    ///
+    /// On success, returns the number of bytes written. Fails unless the
+    /// socket was connected with [`connect`].
+    ///
+    /// [`connect`]: #method.connect
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::uds::UnixDatagram;
+    ///
+    /// # async fn run() -> std::io::Result<()> {
+    /// let mut socket = UnixDatagram::unbound()?;
+    /// socket.connect("/tmp/sock")?;
+    /// await!(socket.send(b"Good morrow!"))?;
+    /// # Ok(()) }
+    /// ```
+    pub fn send<'a, 'b>(&'a mut self, buf: &'b [u8]) -> SendDatagram<'a, 'b> {
+        SendDatagram { socket: self, buf }
+    }
+
+    /// Receives data from the socket it is connected to. On success, returns
+    /// the number of bytes read.
+    ///
+    /// Like with [`recv_from`], a datagram which doesn't fit into `buf` is
+    /// truncated.
+    ///
+    /// [`recv_from`]: #method.recv_from
+    pub fn recv<'a, 'b>(&'a mut self, buf: &'b mut [u8]) -> RecvDatagram<'a, 'b> {
+        RecvDatagram { socket: self, buf }
+    }
+
+    /// Returns a stream of the datagrams received by this socket, along with
+    /// the addresses they came from.
+    ///
+    /// Each datagram is received into a buffer of its own, of `max_size`
+    /// bytes. Longer datagrams are truncated to `max_size`, the rest of them
+    /// is lost. The stream never ends.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use futures::prelude::*;
+    /// use romio::uds::UnixDatagram;
+    ///
+    /// # async fn run() -> std::io::Result<()> {
+    /// let socket = UnixDatagram::bind("/tmp/sock")?;
+    ///
+    /// let mut messages = socket.incoming_messages(64 * 1024);
+    /// while let Some(message) = await!(messages.next()) {
+    ///     let (data, addr) = message?;
+    ///     println!("{} bytes from {:?}", data.len(), addr);
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub fn incoming_messages(&self, max_size: usize) -> IncomingMessages<'_> {
+        IncomingMessages {
+            socket: self,
+            max_size,
+            buf: Vec::new(),
+        }
+    }
+
     /// Receives data from the socket.
     ///
     /// On success, returns the number of bytes read and the address from
@@ -142,6 +296,67 @@ impl UnixDatagram {
         }
     }
 
+    /// Receives data from the socket, along with the credentials of the
+    /// process which sent it.
+    ///
+    /// The kernel attaches the credentials, so unlike anything in the data
+    /// they can't be forged by the sender. They are only received once
+    /// [`set_passcred`] was enabled, otherwise the future fails.
+    ///
+    /// [`set_passcred`]: #method.set_passcred
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::uds::UnixDatagram;
+    ///
+    /// # async fn run() -> std::io::Result<()> {
+    /// let mut socket = UnixDatagram::bind("/tmp/sock")?;
+    /// socket.set_passcred(true)?;
+    ///
+    /// let mut buf = [0; 1024];
+    /// let (n, addr, cred) = await!(socket.recv_from_with_cred(&mut buf))?;
+    /// println!("{} bytes from {:?}, sent by uid {}", n, addr, cred.uid);
+    /// # Ok(()) }
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn recv_from_with_cred<'a, 'b>(
+        &'a mut self,
+        buf: &'b mut [u8],
+    ) -> RecvFromWithCred<'a, 'b> {
+        RecvFromWithCred { socket: self, buf }
+    }
+
+    /// Receives data from the socket, along with the credentials of the
+    /// process which sent it.
+    ///
+    /// On success, returns the number of bytes read, the address from whence
+    /// the data came and the sender's credentials.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn poll_recv_from_with_cred(
+        &mut self,
+        lw: &LocalWaker,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<(usize, SocketAddr, UCred)>> {
+        ready!(self.io.poll_read_ready(lw)?);
+
+        // The credentials are peeked at before the datagram is received,
+        // which takes `&mut self` so that no other receive comes in between.
+        let socket = self.io.get_ref();
+        let r = ucred::peek_cred(socket).and_then(|cred| {
+            let (n, addr) = socket.recv_from(buf)?;
+            Ok((n, addr, cred))
+        });
+
+        if is_wouldblock(&r) {
+            self.io.clear_read_ready(lw)?;
+            Poll::Pending
+        } else {
+            Poll::Ready(r)
+        }
+    }
+
     /// Sends data on the socket to the specified address.
     ///
     /// On success, returns the number of bytes written.
@@ -163,6 +378,38 @@ impl UnixDatagram {
         }
     }
 
+    /// Sends data on the socket to the socket it is connected to.
+    ///
+    /// On success, returns the number of bytes written.
+    pub fn poll_send(&self, lw: &LocalWaker, buf: &[u8]) -> Poll<io::Result<usize>> {
+        ready!(self.io.poll_write_ready(lw)?);
+
+        let r = self.io.get_ref().send(buf);
+
+        if is_wouldblock(&r) {
+            self.io.clear_write_ready(lw)?;
+            Poll::Pending
+        } else {
+            Poll::Ready(r)
+        }
+    }
+
+    /// Receives data from the socket it is connected to.
+    ///
+    /// On success, returns the number of bytes read.
+    pub fn poll_recv(&self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        ready!(self.io.poll_read_ready(lw)?);
+
+        let r = self.io.get_ref().recv(buf);
+
+        if is_wouldblock(&r) {
+            self.io.clear_read_ready(lw)?;
+            Poll::Pending
+        } else {
+            Poll::Ready(r)
+        }
+    }
+
     /// Returns the value of the `SO_ERROR` option.
     ///
     /// # Examples
@@ -181,6 +428,27 @@ impl UnixDatagram {
         self.io.get_ref().take_error()
     }
 
+    /// Gets the value of the `SO_PASSCRED` option for this socket.
+    ///
+    /// For more information about this option, see [`set_passcred`].
+    ///
+    /// [`set_passcred`]: #method.set_passcred
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn passcred(&self) -> io::Result<bool> {
+        ucred::passcred(self.io.get_ref())
+    }
+
+    /// Sets the value of the `SO_PASSCRED` option for this socket.
+    ///
+    /// When enabled, every datagram is received along with the credentials
+    /// of the process which sent it, which [`recv_from_with_cred`] returns.
+    ///
+    /// [`recv_from_with_cred`]: #method.recv_from_with_cred
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_passcred(&self, on: bool) -> io::Result<()> {
+        ucred::set_passcred(self.io.get_ref(), on)
+    }
+
     /// Shut down the read, write, or both halves of this connection.
     ///
     /// This function will cause all pending and future I/O calls on the
@@ -203,6 +471,120 @@ impl UnixDatagram {
     }
 }
 
+/// The future returned by `UnixDatagram::send_to`
+#[derive(Debug)]
+pub struct SendTo<'a, 'b> {
+    socket: &'a mut UnixDatagram,
+    buf: &'b [u8],
+    path: &'b Path,
+}
+
+impl<'a, 'b> Future for SendTo<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        let SendTo { socket, buf, path } = &mut *self;
+        socket.poll_send_to(lw, buf, *path)
+    }
+}
+
+/// The future returned by `UnixDatagram::recv_from`
+#[derive(Debug)]
+pub struct RecvFrom<'a, 'b> {
+    socket: &'a mut UnixDatagram,
+    buf: &'b mut [u8],
+}
+
+impl<'a, 'b> Future for RecvFrom<'a, 'b> {
+    type Output = io::Result<(usize, SocketAddr)>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        let RecvFrom { socket, buf } = &mut *self;
+        socket.poll_recv_from(lw, buf)
+    }
+}
+
+/// The future returned by `UnixDatagram::send`
+#[derive(Debug)]
+pub struct SendDatagram<'a, 'b> {
+    socket: &'a mut UnixDatagram,
+    buf: &'b [u8],
+}
+
+impl<'a, 'b> Future for SendDatagram<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        let SendDatagram { socket, buf } = &mut *self;
+        socket.poll_send(lw, buf)
+    }
+}
+
+/// The future returned by `UnixDatagram::recv`
+#[derive(Debug)]
+pub struct RecvDatagram<'a, 'b> {
+    socket: &'a mut UnixDatagram,
+    buf: &'b mut [u8],
+}
+
+impl<'a, 'b> Future for RecvDatagram<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        let RecvDatagram { socket, buf } = &mut *self;
+        socket.poll_recv(lw, buf)
+    }
+}
+
+/// A stream of the datagrams received by a `UnixDatagram`, along with the
+/// addresses they came from.
+///
+/// This is created by the `UnixDatagram::incoming_messages` method.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct IncomingMessages<'a> {
+    socket: &'a UnixDatagram,
+    max_size: usize,
+    /// Kept while waiting for the next datagram.
+    buf: Vec<u8>,
+}
+
+impl<'a> Stream for IncomingMessages<'a> {
+    type Item = io::Result<(Vec<u8>, SocketAddr)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Option<Self::Item>> {
+        let IncomingMessages {
+            socket,
+            max_size,
+            buf,
+        } = &mut *self;
+        buf.resize(*max_size, 0);
+        let (n, addr) = ready!(socket.poll_recv_from(lw, buf)?);
+
+        let mut data = mem::replace(buf, Vec::new());
+        data.truncate(n);
+        Poll::Ready(Some(Ok((data, addr))))
+    }
+}
+
+/// The future returned by `UnixDatagram::recv_from_with_cred`
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Debug)]
+pub struct RecvFromWithCred<'a, 'b> {
+    socket: &'a mut UnixDatagram,
+    buf: &'b mut [u8],
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<'a, 'b> Future for RecvFromWithCred<'a, 'b> {
+    type Output = io::Result<(usize, SocketAddr, UCred)>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        let RecvFromWithCred { socket, buf } = &mut *self;
+        socket.poll_recv_from_with_cred(lw, buf)
+    }
+}
+
 impl fmt::Debug for UnixDatagram {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.io.get_ref().fmt(f)
@@ -1,5 +1,6 @@
 use super::UnixStream;
 
+use crate::io::{AddrError, Operation};
 use crate::reactor::PollEvented;
 
 use futures::task::LocalWaker;
@@ -7,11 +8,15 @@ use futures::{ready, Poll, Stream};
 use mio_uds;
 
 use std::fmt;
+use std::fs;
 use std::io;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
 use std::os::unix::io::{AsRawFd, RawFd};
 use std::os::unix::net::{self, SocketAddr};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
+#[cfg(target_os = "linux")]
+use std::ptr;
 
 /// A Unix socket which can accept connections from other Unix sockets.
 ///
@@ -39,6 +44,27 @@ use std::pin::Pin;
 #[must_use = "streams do nothing unless polled"]
 pub struct UnixListener {
     io: PollEvented<mio_uds::UnixListener>,
+    /// The socket file removed on drop, if `set_unlink_on_drop` enabled it.
+    unlink_on_drop: Option<SocketFile>,
+}
+
+/// A socket file, identified by its inode so that a file which replaced it
+/// isn't mistaken for it.
+#[derive(Debug)]
+struct SocketFile {
+    path: PathBuf,
+    dev: u64,
+    ino: u64,
+}
+
+/// A stream of the connections accepted on a `UnixListener`, together with
+/// the addresses of their peers.
+///
+/// This is created by the `UnixListener::incoming_with_addr` method.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct IncomingWithAddr<'a> {
+    listener: &'a UnixListener,
 }
 
 impl UnixListener {
@@ -55,10 +81,138 @@ impl UnixListener {
     /// # Ok(())}
     /// ```
     ///
-    pub fn bind(path: impl AsRef<Path>) -> io::Result<UnixListener> {
-        let listener = mio_uds::UnixListener::bind(path)?;
+    pub fn bind(path: impl AsRef<Path>) -> Result<UnixListener, AddrError> {
+        let path = path.as_ref();
+        let listener = mio_uds::UnixListener::bind(path)
+            .map_err(|e| AddrError::with_path(Operation::Bind, path, e))?;
         let io = PollEvented::new(listener);
-        Ok(UnixListener { io })
+        Ok(UnixListener {
+            io,
+            unlink_on_drop: None,
+        })
+    }
+
+    /// Creates a new `UnixListener` bound to the specified path, removing a
+    /// stale socket file left behind at `path` first.
+    ///
+    /// A socket file isn't removed when the listener bound to it is dropped,
+    /// or when its process crashes, so binding to the same path again fails
+    /// with `AddrInUse`. In that case this method connects to the existing
+    /// socket: if the connection is refused, nobody is listening on it
+    /// anymore, so the file is removed and binding is retried. If anybody
+    /// accepts the connection, or the file isn't a socket, the `AddrInUse`
+    /// error is returned and the file is left alone.
+    ///
+    /// Another process binding to `path` in between the connection attempt
+    /// and the removal of the file can't be detected, so processes
+    /// competing for a path should be serialized, for example with a lock
+    /// file.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::uds::UnixListener;
+    ///
+    /// # fn main () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let socket = UnixListener::bind_clean("/tmp/sock")?;
+    /// # Ok(())}
+    /// ```
+    pub fn bind_clean(path: impl AsRef<Path>) -> Result<UnixListener, AddrError> {
+        let path = path.as_ref();
+        let err = match UnixListener::bind(path) {
+            Err(e) => e,
+            res => return res,
+        };
+        if err.kind() != io::ErrorKind::AddrInUse {
+            return Err(err);
+        }
+
+        // Connecting to a file which isn't a socket is refused too.
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => {}
+            _ => return Err(err),
+        }
+        match net::UnixStream::connect(path) {
+            Err(ref e) if e.kind() == io::ErrorKind::ConnectionRefused => {}
+            _ => return Err(err),
+        }
+
+        if let Err(e) = fs::remove_file(path) {
+            if e.kind() != io::ErrorKind::NotFound {
+                return Err(AddrError::with_path(Operation::Bind, path, e));
+            }
+        }
+        UnixListener::bind(path)
+    }
+
+    /// Binds to `path` like [`bind`], and removes the socket file again when
+    /// the listener is dropped.
+    ///
+    /// See [`set_unlink_on_drop`] for details.
+    ///
+    /// [`bind`]: #method.bind
+    /// [`set_unlink_on_drop`]: #method.set_unlink_on_drop
+    pub fn bind_unlink_on_drop(path: impl AsRef<Path>) -> Result<UnixListener, AddrError> {
+        let path = path.as_ref();
+        let mut listener = UnixListener::bind(path)?;
+        listener
+            .set_unlink_on_drop(true)
+            .map_err(|e| AddrError::with_path(Operation::Bind, path, e))?;
+        Ok(listener)
+    }
+
+    /// Returns whether the socket file is removed when the listener is
+    /// dropped.
+    ///
+    /// For more information about this option, see [`set_unlink_on_drop`].
+    ///
+    /// [`set_unlink_on_drop`]: #method.set_unlink_on_drop
+    pub fn unlink_on_drop(&self) -> bool {
+        self.unlink_on_drop.is_some()
+    }
+
+    /// Sets whether the socket file is removed when the listener is dropped.
+    ///
+    /// Unix sockets leave their file behind by default, which makes binding
+    /// to the same path fail until it is removed. With this option enabled,
+    /// dropping the listener removes the file, unless it is gone already or
+    /// was replaced by another file in the meantime. Listeners without a file,
+    /// such as those bound to an abstract name on Linux, are left as they
+    /// are.
+    ///
+    /// The file is looked up by the path the listener was bound to, so a
+    /// relative path should not be bound to if the working directory changes
+    /// before the listener is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use romio::uds::UnixListener;
+    ///
+    /// # fn main () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let mut listener = UnixListener::bind("/tmp/sock")?;
+    /// listener.set_unlink_on_drop(true)?;
+    /// assert!(listener.unlink_on_drop());
+    /// # Ok(())}
+    /// ```
+    pub fn set_unlink_on_drop(&mut self, unlink: bool) -> io::Result<()> {
+        if !unlink {
+            self.unlink_on_drop = None;
+            return Ok(());
+        }
+
+        let addr = self.local_addr()?;
+        let path = match addr.as_pathname() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let metadata = fs::symlink_metadata(path)?;
+        self.unlink_on_drop = Some(SocketFile {
+            path: path.to_path_buf(),
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+        });
+        Ok(())
     }
 
     /// Returns the local socket address of this listener.
@@ -95,18 +249,35 @@ impl UnixListener {
         self.io.get_ref().take_error()
     }
 
-    fn poll_accept(&self, lw: &LocalWaker) -> Poll<io::Result<(UnixStream, SocketAddr)>> {
-        let (io, addr) = ready!(self.poll_accept_std(lw)?);
-
-        let io = mio_uds::UnixStream::from_stream(io)?;
-        Poll::Ready(Ok((UnixStream::new(io), addr)))
+    /// Returns a stream over the connections being received on this listener,
+    /// yielding each accepted stream together with the address of its peer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use futures::prelude::*;
+    /// use romio::uds::UnixListener;
+    ///
+    /// # async fn run () -> Result<(), Box<dyn std::error::Error + 'static>> {
+    /// let listener = UnixListener::bind("/tmp/sock")?;
+    /// let mut incoming = listener.incoming_with_addr();
+    ///
+    /// while let Some(accepted) = await!(incoming.next()) {
+    ///     let (stream, addr) = accepted?;
+    ///     println!("new client from {:?}", addr);
+    /// }
+    /// # Ok(())}
+    /// ```
+    pub fn incoming_with_addr(&self) -> IncomingWithAddr<'_> {
+        IncomingWithAddr { listener: self }
     }
 
-    fn poll_accept_std(&self, lw: &LocalWaker) -> Poll<io::Result<(net::UnixStream, SocketAddr)>> {
+    fn poll_accept(&self, lw: &LocalWaker) -> Poll<io::Result<(UnixStream, SocketAddr)>> {
         ready!(self.io.poll_read_ready(lw)?);
 
-        match self.io.get_ref().accept_std() {
-            Ok(Some((sock, addr))) => Poll::Ready(Ok((sock, addr))),
+        match accept(self.io.get_ref()) {
+            Ok(Some((io, addr))) => Poll::Ready(Ok((UnixStream::new(io), addr))),
             Ok(None) => {
                 self.io.clear_read_ready(lw)?;
                 Poll::Pending
@@ -120,12 +291,75 @@ impl UnixListener {
     }
 }
 
+/// Accepts a connection, returning it in nonblocking mode, or `None` if no
+/// connection is pending.
+///
+/// On Linux `accept4` makes the new socket nonblocking and close-on-exec right
+/// away, instead of the extra `ioctl` that `mio_uds::UnixStream::from_stream`
+/// would issue for every connection. The address of the peer is looked up
+/// afterwards, as `std` offers no way to build it from the raw address.
+#[cfg(target_os = "linux")]
+fn accept(
+    listener: &mio_uds::UnixListener,
+) -> io::Result<Option<(mio_uds::UnixStream, SocketAddr)>> {
+    use std::os::unix::io::FromRawFd;
+
+    let fd = unsafe {
+        libc::accept4(
+            listener.as_raw_fd(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+        )
+    };
+    if fd < 0 {
+        let err = io::Error::last_os_error();
+        if err.kind() == io::ErrorKind::WouldBlock {
+            return Ok(None);
+        }
+        return Err(err);
+    }
+
+    let stream = unsafe { mio_uds::UnixStream::from_raw_fd(fd) };
+    let addr = stream.peer_addr()?;
+    Ok(Some((stream, addr)))
+}
+
+/// Accepts a connection, returning it in nonblocking mode, or `None` if no
+/// connection is pending.
+///
+/// `std` makes the new socket close-on-exec before returning it.
+#[cfg(not(target_os = "linux"))]
+fn accept(
+    listener: &mio_uds::UnixListener,
+) -> io::Result<Option<(mio_uds::UnixStream, SocketAddr)>> {
+    listener.accept()
+}
+
 impl fmt::Debug for UnixListener {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.io.get_ref().fmt(f)
     }
 }
 
+impl Drop for UnixListener {
+    fn drop(&mut self) {
+        let file = match self.unlink_on_drop.take() {
+            Some(file) => file,
+            None => return,
+        };
+
+        // A failure can't be reported here, and a file which is gone already
+        // is what we want anyway.
+        match fs::symlink_metadata(&file.path) {
+            Ok(ref metadata) if metadata.dev() == file.dev && metadata.ino() == file.ino => {
+                drop(fs::remove_file(&file.path));
+            }
+            _ => {}
+        }
+    }
+}
+
 impl AsRawFd for UnixListener {
     fn as_raw_fd(&self) -> RawFd {
         self.io.get_ref().as_raw_fd()
@@ -165,3 +399,12 @@ impl Stream for UnixListener {
         Poll::Ready(Some(Ok(socket)))
     }
 }
+
+impl<'a> Stream for IncomingWithAddr<'a> {
+    type Item = io::Result<(UnixStream, SocketAddr)>;
+
+    fn poll_next(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Option<Self::Item>> {
+        let accepted = ready!(self.listener.poll_accept(lw)?);
+        Poll::Ready(Some(Ok(accepted)))
+    }
+}
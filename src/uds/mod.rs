@@ -27,7 +27,11 @@ mod listener;
 mod stream;
 mod ucred;
 
-pub use self::datagram::UnixDatagram;
-pub use self::listener::{UnixListener};
-pub use self::stream::{ConnectFuture, UnixStream};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::datagram::RecvFromWithCred;
+pub use self::datagram::{
+    IncomingMessages, RecvDatagram, RecvFrom, SendDatagram, SendTo, UnixDatagram,
+};
+pub use self::listener::{IncomingWithAddr, UnixListener};
+pub use self::stream::{ConnectFuture, ConnectTimeout, UnixStream};
 pub use self::ucred::UCred;
@@ -1,26 +1,33 @@
 use super::ucred::{self, UCred};
 
-use crate::reactor::PollEvented;
+use crate::io::{AddrError, Operation};
+use crate::reactor::{Delay, PollEvented};
+use crate::sigpipe;
 
-use futures::io::{AsyncRead, AsyncWrite};
+use futures::io::{AsyncRead, AsyncWrite, Initializer};
 use futures::task::LocalWaker;
 use futures::{ready, Future, Poll};
 use iovec::IoVec;
 use mio::Ready;
 
+use std::cmp;
 use std::fmt;
 use std::io;
 use std::net::Shutdown;
 use std::os::unix::io::{AsRawFd, RawFd};
 use std::os::unix::net::SocketAddr;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::time::{Duration, Instant};
 
 /// A structure representing a connected Unix socket.
 ///
 /// This socket can be connected directly with `UnixStream::connect` or accepted
 /// from a listener with `UnixListener::incoming`. Additionally, a pair of
 /// anonymous Unix sockets can be created with `UnixStream::pair`.
+///
+/// Writing to a stream whose peer is gone fails with
+/// `io::ErrorKind::BrokenPipe`, without raising `SIGPIPE`.
 pub struct UnixStream {
     io: PollEvented<mio_uds::UnixStream>,
 }
@@ -29,9 +36,27 @@ pub struct UnixStream {
 /// `UnixStream` when the stream is connected.
 #[derive(Debug)]
 pub struct ConnectFuture {
+    path: PathBuf,
     inner: State,
 }
 
+/// Future returned by `UnixStream::connect_timeout` which will resolve to a
+/// `UnixStream` when the stream is connected, or fail once the timeout
+/// expired.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct ConnectTimeout {
+    path: PathBuf,
+    /// `None` while waiting to retry.
+    connect: Option<ConnectFuture>,
+    retry: Delay,
+    deadline: Delay,
+}
+
+/// How long `ConnectTimeout` waits before retrying a connection the listener
+/// had no room for.
+const CONNECT_RETRY: Duration = Duration::from_millis(10);
+
 #[derive(Debug)]
 enum State {
     Waiting(UnixStream),
@@ -57,14 +82,52 @@ impl UnixStream {
     /// # Ok(()) }
     /// ```
     pub fn connect(path: impl AsRef<Path>) -> ConnectFuture {
-        let res = mio_uds::UnixStream::connect(path).map(UnixStream::new);
-
-        let inner = match res {
-            Ok(stream) => State::Waiting(stream),
+        let path = path.as_ref();
+        let inner = match mio_uds::UnixStream::connect(path) {
+            Ok(stream) => State::Waiting(UnixStream::new(stream)),
             Err(e) => State::Error(e),
         };
 
-        ConnectFuture { inner }
+        ConnectFuture {
+            path: path.to_path_buf(),
+            inner,
+        }
+    }
+
+    /// Connects to the socket named by `path`, failing with an error of kind
+    /// `TimedOut` if that takes longer than `dur`.
+    ///
+    /// A listener whose backlog of connections waiting to be accepted is full
+    /// turns away new connections right away. Instead of failing, the
+    /// connection is retried every few milliseconds until there's room, or
+    /// the time is up. The time starts running right away, not when the
+    /// future is first polled, and an attempt which succeeds right at the
+    /// deadline isn't failed.
+    ///
+    /// Retrying only applies where the kernel reports a full backlog as
+    /// `EAGAIN`, like Linux does. macOS and FreeBSD refuse the connection
+    /// with `ECONNREFUSED` instead, which is returned right away.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// #![feature(async_await, await_macro, futures_api)]
+    /// use romio::uds::UnixStream;
+    /// use std::time::Duration;
+    ///
+    /// # async fn run() -> std::io::Result<()> {
+    /// let stream = await!(UnixStream::connect_timeout("/tmp/sock", Duration::from_secs(3)))?;
+    /// # Ok(()) }
+    /// ```
+    pub fn connect_timeout(path: impl AsRef<Path>, dur: Duration) -> ConnectTimeout {
+        let path = path.as_ref();
+        let deadline = Instant::now() + dur;
+        ConnectTimeout {
+            path: path.to_path_buf(),
+            connect: Some(UnixStream::connect(path)),
+            retry: Delay::new(deadline),
+            deadline: Delay::new(deadline),
+        }
     }
 
     /// Creates an unnamed pair of connected sockets.
@@ -92,7 +155,11 @@ impl UnixStream {
     }
 
     pub(crate) fn new(stream: mio_uds::UnixStream) -> UnixStream {
-        let io = PollEvented::new(stream);
+        // Failing to set the option only means that writing to a closed
+        // connection raises SIGPIPE, which Rust programs ignore by default.
+        drop(sigpipe::set_nosigpipe(&stream));
+
+        let io = PollEvented::new_ready(stream);
         UnixStream { io }
     }
 
@@ -182,6 +249,11 @@ impl UnixStream {
     /// specified portions to immediately return with an appropriate value
     /// (see the documentation of `Shutdown`).
     ///
+    /// Shutting down the write half lets the peer know that no more data is
+    /// coming: its reads return `Ok(0)` once it read everything sent before.
+    /// The connection stays open in the other direction, so the peer can still
+    /// send a response, until it shuts down its own write half as well.
+    ///
     /// ```rust
     /// #![feature(async_await, await_macro, futures_api)]
     /// use romio::uds::UnixStream;
@@ -198,6 +270,10 @@ impl UnixStream {
 }
 
 impl AsyncRead for UnixStream {
+    unsafe fn initializer(&self) -> Initializer {
+        <&UnixStream>::initializer(&self)
+    }
+
     fn poll_read(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
         <&UnixStream>::poll_read(&mut &*self, lw, buf)
     }
@@ -230,6 +306,11 @@ impl AsyncWrite for UnixStream {
 }
 
 impl<'a> AsyncRead for &'a UnixStream {
+    unsafe fn initializer(&self) -> Initializer {
+        // The socket only ever writes to the buffers it reads into.
+        Initializer::nop()
+    }
+
     fn poll_read(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
         (&self.io).poll_read(lw, buf)
     }
@@ -239,6 +320,12 @@ impl<'a> AsyncRead for &'a UnixStream {
         lw: &LocalWaker,
         bufs: &mut [&mut IoVec],
     ) -> Poll<io::Result<usize>> {
+        // Without any buffers there is nothing to wait for, and a zero-length
+        // read would be mistaken for end of stream.
+        if bufs.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
         ready!(self.poll_read_ready(lw)?);
 
         let r = self.io.get_ref().read_bufs(bufs);
@@ -254,19 +341,33 @@ impl<'a> AsyncRead for &'a UnixStream {
 
 impl<'a> AsyncWrite for &'a UnixStream {
     fn poll_write(&mut self, lw: &LocalWaker, buf: &[u8]) -> Poll<io::Result<usize>> {
-        (&self.io).poll_write(lw, buf)
+        ready!(self.poll_write_ready(lw)?);
+
+        let r = sigpipe::send(*self, buf);
+
+        if is_wouldblock(&r) {
+            self.io.clear_write_ready(lw)?;
+            Poll::Pending
+        } else {
+            Poll::Ready(r)
+        }
     }
 
     fn poll_vectored_write(&mut self, lw: &LocalWaker, bufs: &[&IoVec]) -> Poll<io::Result<usize>> {
+        if bufs.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
         ready!(self.poll_write_ready(lw)?);
 
-        let r = self.io.get_ref().write_bufs(bufs);
+        let r = sigpipe::send_bufs(*self, bufs);
 
         if is_wouldblock(&r) {
             self.io.clear_write_ready(lw)?;
+            Poll::Pending
+        } else {
+            Poll::Ready(r)
         }
-
-        return Poll::Ready(r);
     }
 
     fn poll_flush(&mut self, lw: &LocalWaker) -> Poll<io::Result<()>> {
@@ -291,12 +392,22 @@ impl AsRawFd for UnixStream {
 }
 
 impl Future for ConnectFuture {
-    type Output = io::Result<UnixStream>;
+    type Output = Result<UnixStream, AddrError>;
 
-    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<io::Result<UnixStream>> {
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Result<UnixStream, AddrError>> {
+        let this = &mut *self;
+        match ready!(this.inner.poll_connect(lw)) {
+            Ok(stream) => Poll::Ready(Ok(stream)),
+            Err(e) => Poll::Ready(Err(AddrError::with_path(Operation::Connect, &this.path, e))),
+        }
+    }
+}
+
+impl State {
+    fn poll_connect(&mut self, lw: &LocalWaker) -> Poll<io::Result<UnixStream>> {
         use std::mem;
 
-        match self.inner {
+        match *self {
             State::Waiting(ref mut stream) => {
                 ready!(stream.io.poll_write_ready(lw)?);
 
@@ -305,7 +416,7 @@ impl Future for ConnectFuture {
                 }
             }
             State::Error(_) => {
-                let e = match mem::replace(&mut self.inner, State::Empty) {
+                let e = match mem::replace(self, State::Empty) {
                     State::Error(e) => e,
                     _ => unreachable!(),
                 };
@@ -315,13 +426,71 @@ impl Future for ConnectFuture {
             State::Empty => panic!("can't poll stream twice"),
         }
 
-        match mem::replace(&mut self.inner, State::Empty) {
+        match mem::replace(self, State::Empty) {
             State::Waiting(stream) => Poll::Ready(Ok(stream)),
             _ => unreachable!(),
         }
     }
 }
 
+impl Future for ConnectTimeout {
+    type Output = Result<UnixStream, AddrError>;
+
+    fn poll(mut self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Result<UnixStream, AddrError>> {
+        let this = &mut *self;
+        loop {
+            if let Some(connect) = &mut this.connect {
+                let res = match Pin::new(connect).poll(lw) {
+                    Poll::Ready(res) => res,
+                    Poll::Pending => break,
+                };
+                this.connect = None;
+
+                // Connecting to a listener with a full backlog would block,
+                // which `mio_uds` takes for a connection in progress, so it
+                // shows up as a socket which never got connected. Only some
+                // kernels, like Linux, do that, the BSDs refuse the
+                // connection, which isn't retried.
+                let full = match &res {
+                    Ok(stream) => match stream.peer_addr() {
+                        Err(e) => e.kind() == io::ErrorKind::NotConnected,
+                        Ok(_) => false,
+                    },
+                    Err(e) => e.kind() == io::ErrorKind::WouldBlock,
+                };
+                let now = Instant::now();
+                let deadline = this.deadline.deadline();
+                if !full {
+                    return Poll::Ready(res);
+                } else if now >= deadline {
+                    break;
+                }
+                // The last attempt is made right at the deadline.
+                this.retry.reset(cmp::min(now + CONNECT_RETRY, deadline));
+            }
+
+            match Pin::new(&mut this.retry).poll(lw) {
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(e)) => {
+                    return Poll::Ready(Err(AddrError::with_path(
+                        Operation::Connect,
+                        &this.path,
+                        e,
+                    )))
+                }
+                Poll::Pending => break,
+            }
+            this.connect = Some(UnixStream::connect(&this.path));
+        }
+
+        let e = match ready!(Pin::new(&mut this.deadline).poll(lw)) {
+            Ok(()) => io::Error::new(io::ErrorKind::TimedOut, "connection timed out"),
+            Err(e) => e,
+        };
+        Poll::Ready(Err(AddrError::with_path(Operation::Connect, &this.path, e)))
+    }
+}
+
 fn is_wouldblock<T>(r: &io::Result<T>) -> bool {
     match *r {
         Ok(_) => false,
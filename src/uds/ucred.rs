@@ -10,7 +10,7 @@ pub struct UCred {
 }
 
 #[cfg(any(target_os = "linux", target_os = "android"))]
-pub(crate) use self::impl_linux::get_peer_cred;
+pub(crate) use self::impl_linux::{get_peer_cred, passcred, peek_cred, set_passcred};
 
 #[cfg(any(
     target_os = "dragonfly",
@@ -24,10 +24,11 @@ pub(crate) use self::impl_macos::get_peer_cred;
 
 #[cfg(any(target_os = "linux", target_os = "android"))]
 pub(crate) mod impl_linux {
+    use crate::sockopt;
     use crate::uds::UnixStream;
     use libc::{c_void, getsockopt, socklen_t, SOL_SOCKET, SO_PEERCRED};
     use std::os::unix::io::AsRawFd;
-    use std::{io, mem};
+    use std::{io, mem, ptr};
 
     use libc::ucred;
 
@@ -66,6 +67,49 @@ pub(crate) mod impl_linux {
             }
         }
     }
+
+    pub(crate) fn passcred(sock: &impl AsRawFd) -> io::Result<bool> {
+        Ok(sockopt::getsockopt(sock, SOL_SOCKET, libc::SO_PASSCRED)? != 0)
+    }
+
+    pub(crate) fn set_passcred(sock: &impl AsRawFd, on: bool) -> io::Result<()> {
+        sockopt::setsockopt(sock, SOL_SOCKET, libc::SO_PASSCRED, on as libc::c_int)
+    }
+
+    /// Returns the credentials sent along with the next datagram queued on
+    /// `sock`, without receiving it.
+    pub(crate) fn peek_cred(sock: &impl AsRawFd) -> io::Result<super::UCred> {
+        // Large and aligned enough for a single `SCM_CREDENTIALS` message.
+        let mut control = [0u64; 4];
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_control = control.as_mut_ptr() as *mut c_void;
+        msg.msg_controllen = mem::size_of_val(&control) as _;
+
+        // Peeking without any buffer leaves the datagram queued whatever its
+        // size, the caller receives it afterwards.
+        let n = unsafe { libc::recvmsg(sock.as_raw_fd(), &mut msg, libc::MSG_PEEK) };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut cmsg = unsafe { libc::CMSG_FIRSTHDR(&msg) };
+        while !cmsg.is_null() {
+            let (level, ty) = unsafe { ((*cmsg).cmsg_level, (*cmsg).cmsg_type) };
+            if level == SOL_SOCKET && ty == libc::SCM_CREDENTIALS {
+                let cred = unsafe { ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const ucred) };
+                return Ok(super::UCred {
+                    uid: cred.uid,
+                    gid: cred.gid,
+                });
+            }
+            cmsg = unsafe { libc::CMSG_NXTHDR(&msg, cmsg) };
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "no credentials received, `set_passcred` enables them",
+        ))
+    }
 }
 
 #[cfg(any(
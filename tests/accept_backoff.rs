@@ -0,0 +1,79 @@
+#![cfg(unix)]
+#![feature(async_await, await_macro, pin)]
+use std::cmp;
+use std::fs::File;
+use std::net::TcpStream;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use futures::executor;
+
+use romio::tcp::{AcceptErrorPolicy, TcpListenerBuilder};
+
+fn exhaust() -> Vec<File> {
+    let mut filler = Vec::new();
+    loop {
+        match File::open("/dev/null") {
+            Ok(file) => filler.push(file),
+            Err(e) => {
+                assert_eq!(e.raw_os_error(), Some(libc::EMFILE));
+                return filler;
+            }
+        }
+    }
+}
+
+// This lowers the descriptor limit of the whole process, so it lives in a
+// test binary of its own.
+#[test]
+fn accept_backs_off_on_emfile() {
+    drop(env_logger::try_init());
+
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    assert_eq!(
+        unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) },
+        0
+    );
+    limit.rlim_cur = cmp::min(limit.rlim_cur, 256);
+    assert_eq!(unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &limit) }, 0);
+
+    let server = TcpListenerBuilder::new()
+        .accept_backoff(Duration::from_millis(50))
+        .bind(&"127.0.0.1:0".parse().unwrap())
+        .unwrap();
+    assert_eq!(server.accept_backoff(), Duration::from_millis(50));
+    let addr = server.local_addr().unwrap();
+
+    // Accept one connection first, so the reactor has all the descriptors it
+    // needs before they run out.
+    let _warmup = TcpStream::connect(&addr).unwrap();
+    let _accepted = executor::block_on(server.accept()).unwrap();
+
+    let client = TcpStream::connect(&addr).unwrap();
+    let filler = exhaust();
+
+    // the accept keeps backing off instead of failing, until descriptors are
+    // available again
+    let start = Instant::now();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(300));
+        drop(filler);
+    });
+    let (_, peer_addr) = executor::block_on(server.accept()).unwrap();
+    assert_eq!(peer_addr, client.local_addr().unwrap());
+    assert!(start.elapsed() >= Duration::from_millis(300));
+
+    // unless every error is asked for
+    server.set_accept_error_policy(AcceptErrorPolicy::ReportAll);
+    let client = TcpStream::connect(&addr).unwrap();
+    let filler = exhaust();
+    let err = executor::block_on(server.accept()).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EMFILE));
+
+    drop(filler);
+    let (_, peer_addr) = executor::block_on(server.accept()).unwrap();
+    assert_eq!(peer_addr, client.local_addr().unwrap());
+}
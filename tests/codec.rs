@@ -0,0 +1,65 @@
+#![feature(async_await, await_macro, futures_api)]
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+
+use bytes::Bytes;
+use futures::executor;
+use futures::{SinkExt, StreamExt};
+
+use romio::codec::{Framed, LengthDelimitedCodec, LinesCodec};
+use romio::TcpListener;
+
+#[test]
+fn lines_across_reads() {
+    drop(env_logger::try_init());
+    let mut server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // client thread
+    thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        client.set_nodelay(true).unwrap();
+        client.write_all(b"What's in a name?\r\nThat which we").unwrap();
+        thread::sleep(std::time::Duration::from_millis(50));
+        client.write_all(b" call a rose\nBy any other word").unwrap();
+    });
+
+    executor::block_on(async {
+        let stream = await!(server.next()).unwrap().unwrap();
+        let mut lines = Framed::new(stream, LinesCodec::new());
+
+        let line = await!(lines.next()).unwrap().unwrap();
+        assert_eq!(line, "What's in a name?");
+        let line = await!(lines.next()).unwrap().unwrap();
+        assert_eq!(line, "That which we call a rose");
+        let line = await!(lines.next()).unwrap().unwrap();
+        assert_eq!(line, "By any other word");
+        assert!(await!(lines.next()).is_none());
+    });
+}
+
+#[test]
+fn length_delimited_round_trip() {
+    drop(env_logger::try_init());
+    let mut server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // client thread
+    let client = thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        let mut buf = vec![];
+        client.read_to_end(&mut buf).unwrap();
+        buf
+    });
+
+    executor::block_on(async {
+        let stream = await!(server.next()).unwrap().unwrap();
+        let mut frames = Framed::new(stream, LengthDelimitedCodec::new());
+        await!(frames.send(Bytes::from_static(b"would smell"))).unwrap();
+        await!(frames.send(Bytes::from_static(b"as sweet"))).unwrap();
+    });
+
+    let buf = client.join().unwrap();
+    assert_eq!(&buf[..], &b"\0\0\0\x0bwould smell\0\0\0\x08as sweet"[..]);
+}
@@ -0,0 +1,82 @@
+#![cfg(unix)]
+#![feature(async_await, await_macro, pin)]
+use std::cmp;
+use std::fs::File;
+use std::io::Read;
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use futures::executor;
+use futures::StreamExt;
+
+use romio::tcp::TcpListenerBuilder;
+
+// This lowers the descriptor limit of the whole process, so it lives in a
+// test binary of its own.
+#[test]
+fn accept_sheds_connections_on_emfile() {
+    drop(env_logger::try_init());
+
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    assert_eq!(
+        unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) },
+        0
+    );
+    limit.rlim_cur = cmp::min(limit.rlim_cur, 256);
+    assert_eq!(unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &limit) }, 0);
+
+    let server = TcpListenerBuilder::new()
+        .fd_reserve(true)
+        .bind(&"127.0.0.1:0".parse().unwrap())
+        .unwrap();
+    assert!(server.fd_reserve());
+    let addr = server.local_addr().unwrap();
+
+    // Accept one connection first, so the reactor has all the descriptors it
+    // needs before they run out.
+    let _warmup = TcpStream::connect(&addr).unwrap();
+    let _accepted = executor::block_on(server.accept()).unwrap();
+
+    let mut clients: Vec<_> = (0..3).map(|_| TcpStream::connect(&addr).unwrap()).collect();
+
+    let mut filler = Vec::new();
+    loop {
+        match File::open("/dev/null") {
+            Ok(file) => filler.push(file),
+            Err(e) => {
+                assert_eq!(e.raw_os_error(), Some(libc::EMFILE));
+                break;
+            }
+        }
+    }
+
+    executor::block_on(async {
+        let mut incoming = server.incoming_with_addr();
+
+        // Every pending connection is shed with an error, backing off in
+        // between instead of spinning.
+        let start = Instant::now();
+        for _ in 0..clients.len() {
+            let err = await!(incoming.next()).unwrap().unwrap_err();
+            assert_eq!(err.raw_os_error(), Some(libc::EMFILE));
+        }
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        for client in &mut clients {
+            client
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            let mut buf = [0; 16];
+            assert_eq!(client.read(&mut buf).unwrap(), 0);
+        }
+
+        // once descriptors are available again, accepting carries on
+        filler.clear();
+        let client = TcpStream::connect(&addr).unwrap();
+        let (_, peer_addr) = await!(incoming.next()).unwrap().unwrap();
+        assert_eq!(peer_addr, client.local_addr().unwrap());
+    });
+}
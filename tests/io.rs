@@ -0,0 +1,232 @@
+#![feature(async_await, await_macro, futures_api)]
+use std::io::{self, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use futures::executor;
+use futures::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use futures::task::LocalWaker;
+use futures::Poll;
+
+use romio::io::{BufWriter, TimeoutStream};
+use romio::TcpListener;
+
+const THE_WINTERS_TALE: &[u8] = b"
+                    Each your doing,
+    So singular in each particular,
+    Crowns what you are doing in the present deed,
+    That all your acts are queens.
+";
+
+/// A writer which accepts a few bytes at a time, then fails.
+struct Flaky {
+    written: Vec<u8>,
+    limit: usize,
+}
+
+impl AsyncWrite for Flaky {
+    fn poll_write(&mut self, _: &LocalWaker, buf: &[u8]) -> Poll<io::Result<usize>> {
+        if self.written.len() >= self.limit {
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::BrokenPipe, "flaky")));
+        }
+        let n = buf.len().min(7).min(self.limit - self.written.len());
+        self.written.extend_from_slice(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(&mut self, _: &LocalWaker) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(&mut self, _: &LocalWaker) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A writer which never accepts any data.
+struct Stuck;
+
+impl AsyncWrite for Stuck {
+    fn poll_write(&mut self, _: &LocalWaker, _: &[u8]) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(0))
+    }
+
+    fn poll_flush(&mut self, _: &LocalWaker) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(&mut self, _: &LocalWaker) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A writer which counts the writes it receives, accepting at most `chunk`
+/// bytes each, and making every other write wait.
+#[derive(Default)]
+struct Counting {
+    written: Vec<u8>,
+    chunk: usize,
+    writes: usize,
+    stall: bool,
+    closed: bool,
+}
+
+impl AsyncWrite for Counting {
+    fn poll_write(&mut self, lw: &LocalWaker, buf: &[u8]) -> Poll<io::Result<usize>> {
+        assert!(!self.closed);
+        self.stall = !self.stall;
+        if self.stall {
+            lw.wake();
+            return Poll::Pending;
+        }
+        let n = buf.len().min(self.chunk);
+        self.written.extend_from_slice(&buf[..n]);
+        self.writes += 1;
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(&mut self, _: &LocalWaker) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(&mut self, _: &LocalWaker) -> Poll<io::Result<()>> {
+        self.closed = true;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[test]
+fn copy_everything() {
+    drop(env_logger::try_init());
+    let mut reader = THE_WINTERS_TALE;
+    let mut writer = Flaky {
+        written: vec![],
+        limit: usize::max_value(),
+    };
+
+    let copy = romio::io::copy_with_capacity(&mut reader, &mut writer, 16);
+    let n = executor::block_on(copy).unwrap();
+    assert_eq!(n, THE_WINTERS_TALE.len() as u64);
+    assert_eq!(writer.written, THE_WINTERS_TALE);
+}
+
+#[test]
+fn copy_reports_partial_transfer() {
+    drop(env_logger::try_init());
+    let mut reader = THE_WINTERS_TALE;
+    let mut writer = Flaky {
+        written: vec![],
+        limit: 50,
+    };
+
+    let e = executor::block_on(romio::io::copy(&mut reader, &mut writer)).unwrap_err();
+    assert_eq!(e.transferred(), 50);
+    assert_eq!(e.get_ref().kind(), io::ErrorKind::BrokenPipe);
+    assert_eq!(writer.written, &THE_WINTERS_TALE[..50]);
+}
+
+#[test]
+fn copy_fails_on_write_zero() {
+    drop(env_logger::try_init());
+    let mut reader = THE_WINTERS_TALE;
+
+    let e = executor::block_on(romio::io::copy(&mut reader, &mut Stuck)).unwrap_err();
+    assert_eq!(e.transferred(), 0);
+    assert_eq!(e.get_ref().kind(), io::ErrorKind::WriteZero);
+}
+
+#[test]
+fn timeout_stream_fails_stalled_read() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let _client = TcpStream::connect(&addr).unwrap();
+
+    executor::block_on(async {
+        let (stream, _) = await!(server.accept()).unwrap();
+        let mut stream = TimeoutStream::new(stream);
+        stream.set_read_timeout(Some(Duration::from_millis(50)));
+        assert_eq!(stream.read_timeout(), Some(Duration::from_millis(50)));
+
+        let start = Instant::now();
+        let mut buf = [0; 16];
+        let err = await!(stream.read(&mut buf)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    });
+}
+
+#[test]
+fn timeout_stream_keeps_slow_peer() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // the whole transfer takes longer than the timeout, but each byte
+    // arrives in time
+    thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        for &byte in &THE_WINTERS_TALE[..10] {
+            thread::sleep(Duration::from_millis(30));
+            client.write_all(&[byte]).unwrap();
+        }
+    });
+
+    executor::block_on(async {
+        let (stream, _) = await!(server.accept()).unwrap();
+        let mut stream = TimeoutStream::new(stream);
+        stream.set_read_timeout(Some(Duration::from_millis(200)));
+
+        let mut buf = vec![];
+        await!(stream.read_to_end(&mut buf)).unwrap();
+        assert_eq!(buf, &THE_WINTERS_TALE[..10]);
+    });
+}
+
+#[test]
+fn buf_writer_coalesces_small_writes() {
+    drop(env_logger::try_init());
+    let writer = Counting {
+        chunk: usize::max_value(),
+        ..Counting::default()
+    };
+    let mut writer = BufWriter::with_capacity(writer, 64);
+
+    executor::block_on(async {
+        for _ in 0..100 {
+            await!(writer.write_all(b"message\n")).unwrap();
+        }
+        // every ninth message finds the buffer full
+        assert_eq!(writer.get_ref().writes, 12);
+        assert_eq!(writer.buffer().len(), 100 * 8 - 12 * 64);
+
+        await!(writer.flush()).unwrap();
+        assert_eq!(writer.get_ref().writes, 13);
+        assert!(writer.buffer().is_empty());
+    });
+    assert_eq!(writer.get_ref().written, &b"message\n".repeat(100)[..]);
+}
+
+#[test]
+fn buf_writer_drains_before_closing() {
+    drop(env_logger::try_init());
+    let writer = Counting {
+        chunk: 7,
+        ..Counting::default()
+    };
+    let mut writer = BufWriter::new(writer);
+
+    executor::block_on(async {
+        await!(writer.write_all(THE_WINTERS_TALE)).unwrap();
+        assert_eq!(writer.get_ref().writes, 0);
+
+        // the partial writes are retried until everything is written out
+        await!(writer.close()).unwrap();
+    });
+    let writer = writer.into_inner();
+    assert!(writer.closed);
+    assert_eq!(writer.written, THE_WINTERS_TALE);
+    assert_eq!(writer.writes, (THE_WINTERS_TALE.len() + 6) / 7);
+}
@@ -0,0 +1,32 @@
+#![feature(async_await, await_macro, futures_api)]
+use std::net::SocketAddr;
+
+use futures::executor;
+
+use romio::resolver;
+
+#[test]
+fn resolve_ip_addresses() {
+    drop(env_logger::try_init());
+    executor::block_on(async {
+        let addrs = await!(resolver::resolve("127.0.0.1", 8080)).unwrap();
+        assert_eq!(addrs, ["127.0.0.1:8080".parse::<SocketAddr>().unwrap()]);
+
+        let addrs = await!(resolver::resolve("::1", 8080)).unwrap();
+        assert_eq!(addrs, ["[::1]:8080".parse::<SocketAddr>().unwrap()]);
+    });
+}
+
+#[test]
+fn resolve_localhost() {
+    drop(env_logger::try_init());
+    let addrs = executor::block_on(resolver::resolve("localhost", 80)).unwrap();
+    assert!(!addrs.is_empty());
+    assert!(addrs.iter().all(|addr| addr.ip().is_loopback() && addr.port() == 80));
+}
+
+#[test]
+fn resolve_invalid_host() {
+    drop(env_logger::try_init());
+    assert!(executor::block_on(resolver::resolve("not a hostname", 80)).is_err());
+}
@@ -0,0 +1,155 @@
+#![feature(async_await, await_macro, futures_api)]
+use std::thread;
+use std::time::{Duration, Instant};
+
+use futures::channel::oneshot;
+use futures::executor;
+use futures::io::{AsyncReadExt, AsyncWriteExt};
+use futures::FutureExt;
+
+use romio::timer::Delay;
+use romio::{TcpListener, TcpStream};
+
+#[test]
+fn run_returns_output() {
+    drop(env_logger::try_init());
+    assert_eq!(romio::run(async { 7 }), 7);
+}
+
+#[test]
+fn run_drives_io() {
+    drop(env_logger::try_init());
+
+    let echoed = romio::run(async {
+        let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = async {
+            let (mut stream, _) = await!(listener.accept()).unwrap();
+            let mut buf = vec![0; 5];
+            await!(stream.read_exact(&mut buf)).unwrap();
+            await!(stream.write_all(&buf)).unwrap();
+        };
+
+        let client = async {
+            let mut stream = await!(TcpStream::connect(&addr)).unwrap();
+            await!(stream.write_all(b"hello")).unwrap();
+            let mut buf = vec![];
+            await!(stream.read_to_end(&mut buf)).unwrap();
+            buf
+        };
+
+        await!(server.join(client)).1
+    });
+    assert_eq!(echoed, b"hello");
+}
+
+#[test]
+fn run_drives_timers() {
+    drop(env_logger::try_init());
+    let deadline = Instant::now() + Duration::from_millis(100);
+
+    romio::run(Delay::new(deadline)).unwrap();
+    assert!(Instant::now() >= deadline);
+}
+
+#[test]
+fn run_is_woken_from_other_threads() {
+    drop(env_logger::try_init());
+    let (tx, rx) = oneshot::channel();
+
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        tx.send(42).unwrap();
+    });
+
+    assert_eq!(romio::run(rx), Ok(42));
+}
+
+#[test]
+fn resources_outlive_run_on_global_reactor() {
+    drop(env_logger::try_init());
+
+    // created outside of `run`, the listener keeps working afterwards
+    let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let _client = std::net::TcpStream::connect(&addr).unwrap();
+    romio::run(listener.accept()).unwrap();
+
+    let _client = std::net::TcpStream::connect(&addr).unwrap();
+    executor::block_on(listener.accept()).unwrap();
+}
+
+#[test]
+fn spawn_resolves_to_output() {
+    drop(env_logger::try_init());
+
+    let sum = romio::run(async {
+        let handles: Vec<_> = (0..10).map(|i| romio::spawn(async move { i })).collect();
+        let mut sum = 0;
+        for handle in handles {
+            sum += await!(handle);
+        }
+        sum
+    });
+    assert_eq!(sum, 45);
+}
+
+#[test]
+fn spawned_tasks_are_woken_from_other_threads() {
+    drop(env_logger::try_init());
+
+    let value = romio::run(async {
+        let (tx, rx) = oneshot::channel();
+        let handle = romio::spawn(async { await!(rx).unwrap() + 1 });
+
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            tx.send(41).unwrap();
+        });
+        await!(handle)
+    });
+    assert_eq!(value, 42);
+}
+
+#[test]
+fn dropping_join_handle_cancels_task() {
+    drop(env_logger::try_init());
+
+    romio::run(async {
+        // the task is dropped along with the sender it holds
+        let (tx, rx) = oneshot::channel::<()>();
+        let (_never_tx, never_rx) = oneshot::channel::<()>();
+        let handle = romio::spawn(async move {
+            drop(await!(never_rx));
+            drop(tx);
+        });
+
+        await!(Delay::new(Instant::now() + Duration::from_millis(10))).unwrap();
+        drop(handle);
+        assert_eq!(await!(rx), Err(oneshot::Canceled));
+    });
+}
+
+#[test]
+fn detached_tasks_run_to_completion() {
+    drop(env_logger::try_init());
+
+    let value = romio::run(async {
+        let (tx, rx) = oneshot::channel();
+        romio::spawn(async move {
+            await!(Delay::new(Instant::now() + Duration::from_millis(10))).unwrap();
+            tx.send(7).unwrap();
+        })
+        .detach();
+        await!(rx).unwrap()
+    });
+    assert_eq!(value, 7);
+}
+
+#[test]
+#[should_panic(expected = "outside of `romio::run`")]
+fn spawn_outside_of_run_panics() {
+    romio::spawn(async {}).detach();
+}
@@ -0,0 +1,144 @@
+#![cfg(unix)]
+#![feature(async_await, await_macro, pin)]
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+use futures::executor;
+use futures::future;
+use futures::io::AsyncWriteExt;
+use iovec::IoVec;
+use tempdir::TempDir;
+
+use romio::uds::UnixStream;
+use romio::TcpListener;
+
+const THE_WINTERS_TALE: &[u8] = b"
+                    Each your doing,
+    So singular in each particular,
+    Crowns what you are doing in the present deed,
+    That all your acts are queens.
+";
+
+// The test harness ignores SIGPIPE, which would hide it being raised, so the
+// tests restore the default action, which kills the process. That applies to
+// the whole process, so they live in a test binary of their own.
+fn restore_sigpipe() {
+    unsafe { libc::signal(libc::SIGPIPE, libc::SIG_DFL) };
+}
+
+fn is_closed(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::BrokenPipe || e.kind() == io::ErrorKind::ConnectionReset
+}
+
+#[test]
+fn tcp_write_to_closed_peer() {
+    drop(env_logger::try_init());
+    restore_sigpipe();
+
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let client = TcpStream::connect(&addr).unwrap();
+
+    executor::block_on(async {
+        let (mut stream, _) = await!(server.accept()).unwrap();
+        drop(client);
+
+        // the first write after the close is answered with a reset, the
+        // writes after that fail with EPIPE
+        let err = loop {
+            match await!(stream.write(THE_WINTERS_TALE)) {
+                Ok(_) => thread::sleep(Duration::from_millis(10)),
+                Err(e) => break e,
+            }
+        };
+        assert!(is_closed(&err), "{}", err);
+
+        let bufs: Vec<&IoVec> = vec![THE_WINTERS_TALE.into(), THE_WINTERS_TALE.into()];
+        let err = await!(future::poll_fn(|lw| stream.poll_vectored_write(lw, &bufs))).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    });
+}
+
+#[test]
+fn send_file_to_closed_peer() {
+    drop(env_logger::try_init());
+    restore_sigpipe();
+
+    let tmp_dir = TempDir::new("send_file_sigpipe").unwrap();
+    let path = tmp_dir.path().join("the-winters-tale");
+    let data = THE_WINTERS_TALE.repeat(8 << 10);
+    std::fs::write(&path, &data).unwrap();
+    let file = std::fs::File::open(&path).unwrap();
+
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let client = TcpStream::connect(&addr).unwrap();
+
+    executor::block_on(async {
+        let (mut stream, _) = await!(server.accept()).unwrap();
+        drop(client);
+
+        let err = loop {
+            match await!(stream.send_file(&file, 0, data.len() as u64)) {
+                Ok(_) => thread::sleep(Duration::from_millis(10)),
+                Err(e) => break e,
+            }
+        };
+        assert!(is_closed(err.get_ref()), "{}", err);
+        // whatever made it out before the reset is accounted for
+        assert!(err.sent() < data.len() as u64);
+        assert!(is_closed(&io::Error::from(err)));
+    });
+}
+
+#[test]
+fn copy_splice_to_closed_peer() {
+    drop(env_logger::try_init());
+    restore_sigpipe();
+
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let upstream = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let upstream_addr = upstream.local_addr().unwrap();
+
+    // the client keeps sending until the proxy gives up on it
+    thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        let chunk = vec![0; 64 * 1024];
+        while client.write_all(&chunk).is_ok() {}
+    });
+
+    // upstream closes the connection right away
+    thread::spawn(move || {
+        let (mut stream, _) = upstream.accept().unwrap();
+        let mut buf = [0; 1];
+        drop(stream.read(&mut buf));
+    });
+
+    executor::block_on(async {
+        let (mut inbound, _) = await!(server.accept()).unwrap();
+        let mut outbound = await!(romio::TcpStream::connect(&upstream_addr)).unwrap();
+        let err = await!(romio::tcp::copy_splice(&mut inbound, &mut outbound)).unwrap_err();
+        assert!(is_closed(&err), "{}", err);
+    });
+}
+
+#[test]
+fn uds_write_to_closed_peer() {
+    drop(env_logger::try_init());
+    restore_sigpipe();
+
+    let (client, mut server) = UnixStream::pair().unwrap();
+    drop(client);
+
+    executor::block_on(async {
+        let err = await!(server.write(b"hello")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+
+        let bufs: Vec<&IoVec> = vec![b"hello"[..].into(), b" world"[..].into()];
+        let err = await!(future::poll_fn(|lw| server.poll_vectored_write(lw, &bufs))).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    });
+}
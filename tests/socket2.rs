@@ -0,0 +1,40 @@
+#![cfg(feature = "socket2")]
+#![feature(async_await, await_macro, futures_api)]
+use std::net::SocketAddr;
+
+use futures::executor;
+use futures::io::{AsyncReadExt, AsyncWriteExt};
+use socket2::{Domain, Socket, Type};
+
+use romio::tcp::{TcpListener, TcpStream};
+
+#[test]
+fn serve_configured_sockets() {
+    drop(env_logger::try_init());
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+
+    let socket = Socket::new(Domain::ipv4(), Type::stream(), None).unwrap();
+    socket.set_reuse_address(true).unwrap();
+    socket.bind(&addr.into()).unwrap();
+    socket.listen(128).unwrap();
+    let listener = TcpListener::from_socket(socket).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // bound first, which Windows requires for connecting existing sockets
+    let socket = Socket::new(Domain::ipv4(), Type::stream(), None).unwrap();
+    socket.set_reuse_address(true).unwrap();
+    assert!(socket.reuse_address().unwrap());
+    let local: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    socket.bind(&local.into()).unwrap();
+
+    executor::block_on(async {
+        let mut client = await!(TcpStream::connect_std(socket, &addr)).unwrap();
+        let (mut stream, peer_addr) = await!(listener.accept()).unwrap();
+        assert_eq!(peer_addr, client.local_addr().unwrap());
+
+        await!(client.write_all(b"hello")).unwrap();
+        let mut buf = [0; 5];
+        await!(stream.read_exact(&mut buf)).unwrap();
+        assert_eq!(&buf, b"hello");
+    });
+}
@@ -1,15 +1,24 @@
 #![feature(async_await, await_macro, pin)]
-use std::io::{Read, Write};
-use std::net::TcpStream;
+use std::cmp;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Shutdown, TcpStream};
+use std::sync::mpsc;
+use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
 
-use futures::{StreamExt};
+use futures::{FutureExt, StreamExt};
+use futures::channel::oneshot;
 use futures::executor;
-use futures::future::FutureObj;
+use futures::future::{self, FutureObj};
 use futures::io::{AsyncReadExt, AsyncWriteExt};
-use futures::task::Spawn;
+use futures::task::{Spawn, SpawnExt};
+use iovec::IoVec;
 
+use romio::io::{AddrError, Interest, Operation};
 use romio::TcpListener;
+use romio::tcp::TcpListenerBuilder;
+use romio::timer::TimeoutExt;
 
 const THE_WINTERS_TALE: &[u8] = b"
                     Each your doing,
@@ -78,3 +87,2311 @@ fn both_sides_async_using_threadpool() {
         assert_eq!(buf, THE_WINTERS_TALE);
     })));
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn dual_stack_listener_accepts_v4() {
+    drop(env_logger::try_init());
+    let mut server = TcpListenerBuilder::new()
+        .only_v6(false)
+        .bind(&"[::]:0".parse().unwrap())
+        .unwrap();
+    assert_eq!(server.only_v6().unwrap(), false);
+    let port = server.local_addr().unwrap().port();
+
+    // client thread
+    thread::spawn(move || {
+        let mut client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        client.write_all(THE_WINTERS_TALE).unwrap();
+    });
+
+    executor::block_on(async {
+        let stream = await!(server.next()).unwrap().unwrap();
+        match stream.peer_addr().unwrap().ip() {
+            IpAddr::V6(ip) => assert_eq!(ip.to_ipv4(), Some(Ipv4Addr::new(127, 0, 0, 1))),
+            IpAddr::V4(ip) => panic!("expected a v4-mapped address, got {}", ip),
+        }
+    });
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn v6_only_listener_rejects_v4() {
+    drop(env_logger::try_init());
+    let server = TcpListenerBuilder::new()
+        .only_v6(true)
+        .bind(&"[::]:0".parse().unwrap())
+        .unwrap();
+    assert_eq!(server.only_v6().unwrap(), true);
+    let port = server.local_addr().unwrap().port();
+
+    assert!(TcpStream::connect(("127.0.0.1", port)).is_err());
+}
+
+#[test]
+fn connect_refused() {
+    drop(env_logger::try_init());
+    let addr = {
+        let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        server.local_addr().unwrap()
+    };
+
+    executor::block_on(async {
+        let err = await!(romio::TcpStream::connect(&addr)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+    });
+}
+
+#[test]
+fn concurrent_accepts() {
+    drop(env_logger::try_init());
+    let server = Arc::new(TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap());
+    let addr = server.local_addr().unwrap();
+
+    let mut pool = executor::ThreadPool::new().unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    for _ in 0..4 {
+        let server = server.clone();
+        let tx = tx.clone();
+        pool.spawn(async move {
+            loop {
+                let (stream, _) = await!(server.accept()).unwrap();
+                let _ = tx.send(stream);
+            }
+        }).unwrap();
+    }
+
+    let clients: Vec<_> = (0..20).map(|_| TcpStream::connect(&addr).unwrap()).collect();
+
+    for _ in &clients {
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    }
+}
+
+#[test]
+fn serve_drains_connections_on_shutdown() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let (stop, stopped) = oneshot::channel::<()>();
+    let (tx, rx) = mpsc::channel();
+
+    // client thread
+    let client = thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        let mut buf = [0; 1];
+        client.write_all(b"A").unwrap();
+        client.read_exact(&mut buf).unwrap();
+
+        stop.send(()).unwrap();
+        thread::sleep(Duration::from_millis(100));
+        assert!(TcpStream::connect(&addr).is_err());
+
+        client.write_all(THE_WINTERS_TALE).unwrap();
+    });
+
+    let pool = executor::ThreadPool::new().unwrap();
+    let serve = server.serve(pool, stopped.map(|_| ()), move |mut stream, _| {
+        let tx = tx.clone();
+        async move {
+            let mut buf = [0; 1];
+            await!(stream.read_exact(&mut buf)).unwrap();
+            await!(stream.write_all(&buf)).unwrap();
+
+            let mut rest = vec![];
+            await!(stream.read_to_end(&mut rest)).unwrap();
+            tx.send(rest).unwrap();
+        }
+    });
+    executor::block_on(serve).unwrap();
+
+    assert_eq!(rx.try_recv().unwrap(), THE_WINTERS_TALE);
+    client.join().unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn serve_halts_on_accept_errors_only_when_asked_to() {
+    use romio::tcp::AcceptErrorPolicy;
+    use std::os::unix::io::AsRawFd;
+
+    // Shutting down a listening socket makes every accept fail with `EINVAL`
+    // on Linux.
+    fn fail_accepts(fd: i32) {
+        assert_eq!(unsafe { libc::shutdown(fd, libc::SHUT_RD) }, 0);
+    }
+
+    drop(env_logger::try_init());
+
+    // by default the error is logged and serving goes on
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    fail_accepts(server.as_raw_fd());
+    let (_stop, stopped) = oneshot::channel::<()>();
+    let pool = executor::ThreadPool::new().unwrap();
+    let serve = server.serve(pool, stopped.map(|_| ()), |_, _| async {});
+    let err = executor::block_on(serve.timeout(Duration::from_millis(200))).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+    // halting closes the listener, but drains the connections in flight first
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    server.set_accept_error_policy(AcceptErrorPolicy::Halt);
+    let addr = server.local_addr().unwrap();
+    let fd = server.as_raw_fd();
+    let client = TcpStream::connect(&addr).unwrap();
+
+    let (_stop, stopped) = oneshot::channel::<()>();
+    let (started_tx, started_rx) = mpsc::channel();
+    let (done_tx, done_rx) = mpsc::channel();
+    let pool = executor::ThreadPool::new().unwrap();
+    let serve = server.serve(pool, stopped.map(|_| ()), move |mut stream, _| {
+        let started_tx = started_tx.clone();
+        let done_tx = done_tx.clone();
+        async move {
+            started_tx.send(()).unwrap();
+            let mut rest = vec![];
+            await!(stream.read_to_end(&mut rest)).unwrap();
+            done_tx.send(()).unwrap();
+        }
+    });
+    let serving = thread::spawn(move || executor::block_on(serve));
+
+    started_rx.recv().unwrap();
+    fail_accepts(fd);
+    thread::sleep(Duration::from_millis(100));
+    drop(client);
+
+    let err = serving.join().unwrap().unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+    assert!(done_rx.try_recv().is_ok());
+}
+
+#[test]
+fn vectored_writes() {
+    drop(env_logger::try_init());
+    let mut server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let header = b"THE WINTER'S TALE\n".to_vec();
+    let body: Vec<u8> = THE_WINTERS_TALE.iter().cloned().cycle().take(1 << 22).collect();
+    let expected = [&header[..], &body[..]].concat();
+
+    // client thread, reads slowly enough for the writes to be partial
+    let client = thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        thread::sleep(Duration::from_millis(100));
+        let mut received = vec![];
+        client.read_to_end(&mut received).unwrap();
+        received
+    });
+
+    executor::block_on(async {
+        let mut stream = await!(server.next()).unwrap().unwrap();
+        let mut written = 0;
+
+        while written < header.len() + body.len() {
+            let h = &header[cmp::min(written, header.len())..];
+            let b = &body[written.saturating_sub(header.len())..];
+            let bufs: Vec<&IoVec> = [h, b]
+                .iter()
+                .filter(|s| !s.is_empty())
+                .map(|&s| s.into())
+                .collect();
+            written += await!(future::poll_fn(|lw| stream.poll_vectored_write(lw, &bufs))).unwrap();
+        }
+    });
+
+    assert!(client.join().unwrap() == expected);
+}
+
+#[test]
+fn vectored_reads() {
+    drop(env_logger::try_init());
+    let mut server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // client thread
+    thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        client.write_all(&[0, 0, 0, THE_WINTERS_TALE.len() as u8]).unwrap();
+        client.write_all(THE_WINTERS_TALE).unwrap();
+    });
+
+    executor::block_on(async {
+        let mut stream = await!(server.next()).unwrap().unwrap();
+        let mut header = [0; 4];
+        let mut payload = vec![0; THE_WINTERS_TALE.len()];
+        let mut read = 0;
+
+        while read < header.len() + payload.len() {
+            let n = {
+                let h = &mut header[cmp::min(read, 4)..];
+                let p = &mut payload[read.saturating_sub(4)..];
+                let mut bufs: Vec<&mut IoVec> = vec![];
+                if !h.is_empty() {
+                    bufs.push(h.into());
+                }
+                bufs.push(p.into());
+                await!(future::poll_fn(|lw| stream.poll_vectored_read(lw, &mut bufs))).unwrap()
+            };
+            assert!(n > 0);
+            read += n;
+        }
+
+        assert_eq!(header, [0, 0, 0, THE_WINTERS_TALE.len() as u8]);
+        assert_eq!(payload, THE_WINTERS_TALE);
+    });
+}
+
+#[test]
+fn empty_vectored_io() {
+    drop(env_logger::try_init());
+    let mut server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // client thread, keeps the connection open without sending anything
+    let (tx, rx) = mpsc::channel::<()>();
+    thread::spawn(move || {
+        let _client = TcpStream::connect(&addr).unwrap();
+        drop(rx.recv());
+    });
+
+    executor::block_on(async {
+        let mut stream = await!(server.next()).unwrap().unwrap();
+        let n = await!(future::poll_fn(|lw| stream.poll_vectored_read(lw, &mut []))).unwrap();
+        assert_eq!(n, 0);
+        let n = await!(future::poll_fn(|lw| stream.poll_vectored_write(lw, &[]))).unwrap();
+        assert_eq!(n, 0);
+    });
+
+    tx.send(()).unwrap();
+}
+
+#[test]
+fn connect_from_std() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let socket = net2::TcpBuilder::new_v4()
+        .unwrap()
+        .bind("127.0.0.1:0")
+        .unwrap()
+        .to_tcp_stream()
+        .unwrap();
+    let local_addr = socket.local_addr().unwrap();
+
+    executor::block_on(async {
+        let mut client = await!(romio::TcpStream::connect_from_std(socket, &addr)).unwrap();
+        assert_eq!(client.local_addr().unwrap(), local_addr);
+
+        let (mut stream, peer_addr) = await!(server.accept()).unwrap();
+        assert_eq!(peer_addr, local_addr);
+
+        let mut buf = vec![0; THE_WINTERS_TALE.len()];
+        await!(client.write_all(THE_WINTERS_TALE)).unwrap();
+        await!(stream.read_exact(&mut buf)).unwrap();
+        assert_eq!(buf, THE_WINTERS_TALE);
+    });
+}
+
+#[test]
+fn copy_splice_proxies() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let upstream = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let upstream_addr = upstream.local_addr().unwrap();
+
+    let data: Vec<u8> = (0..4 << 20).map(|i| (i % 251) as u8).collect();
+    let expected = data.clone();
+
+    // client thread
+    thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        client.write_all(&data).unwrap();
+    });
+
+    // upstream thread
+    let upstream = thread::spawn(move || {
+        let (mut stream, _) = upstream.accept().unwrap();
+        let mut received = vec![];
+        stream.read_to_end(&mut received).unwrap();
+        received
+    });
+
+    executor::block_on(async {
+        let (mut inbound, _) = await!(server.accept()).unwrap();
+        let mut outbound = await!(romio::TcpStream::connect(&upstream_addr)).unwrap();
+        let copied = await!(romio::tcp::copy_splice(&mut inbound, &mut outbound)).unwrap();
+        assert_eq!(copied, expected.len() as u64);
+    });
+
+    assert!(upstream.join().unwrap() == expected);
+}
+
+#[test]
+fn send_file() {
+    drop(env_logger::try_init());
+    let tmp_dir = tempdir::TempDir::new("send_file").unwrap();
+    let path = tmp_dir.path().join("the-winters-tale");
+    let data: Vec<u8> = THE_WINTERS_TALE.iter().cloned().cycle().take(3 << 20).collect();
+    std::fs::write(&path, &data).unwrap();
+    let file = std::fs::File::open(&path).unwrap();
+
+    let mut server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // client thread
+    let client = thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        let mut received = vec![];
+        client.read_to_end(&mut received).unwrap();
+        hash(&received)
+    });
+
+    executor::block_on(async {
+        let mut stream = await!(server.next()).unwrap().unwrap();
+
+        let offset = 1 << 20;
+        let sent = await!(stream.send_file(&file, 0, offset)).unwrap();
+        assert_eq!(sent, offset);
+
+        // resume from the offset, asking for more than the file holds
+        let sent = await!(stream.send_file(&file, offset, 4 << 20)).unwrap();
+        assert_eq!(sent, data.len() as u64 - offset);
+    });
+
+    assert_eq!(client.join().unwrap(), hash(&data));
+}
+
+fn hash(data: &[u8]) -> u64 {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+
+    let mut hasher = DefaultHasher::new();
+    hasher.write(data);
+    hasher.finish()
+}
+
+#[test]
+fn incoming_with_addr() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // client thread
+    let client = thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        client.write_all(THE_WINTERS_TALE).unwrap();
+        client.local_addr().unwrap()
+    });
+
+    executor::block_on(async {
+        let mut incoming = server.incoming_with_addr();
+        let (mut stream, peer_addr) = await!(incoming.next()).unwrap().unwrap();
+
+        let mut buf = vec![0; THE_WINTERS_TALE.len()];
+        await!(stream.read_exact(&mut buf)).unwrap();
+        assert_eq!(buf, THE_WINTERS_TALE);
+        assert_eq!(peer_addr, client.join().unwrap());
+    });
+}
+
+#[test]
+fn connect_multi_falls_back() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // nothing answers on this address, connecting to it hangs
+    let blackhole = "10.255.255.1:80".parse().unwrap();
+
+    executor::block_on(async {
+        let connect = romio::TcpStream::connect_multi(&[blackhole, addr])
+            .attempt_delay(Duration::from_millis(50));
+        let stream = await!(connect).unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), addr);
+
+        let (_, peer_addr) = await!(server.accept()).unwrap();
+        assert_eq!(peer_addr, stream.local_addr().unwrap());
+    });
+}
+
+#[test]
+fn connect_happy_eyeballs_falls_back_to_ipv4() {
+    drop(env_logger::try_init());
+    // only listening on IPv4, so an IPv6 address of localhost is refused
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let port = server.local_addr().unwrap().port();
+
+    executor::block_on(async {
+        let connect = romio::TcpStream::connect_happy_eyeballs("localhost", port)
+            .attempt_delay(Duration::from_millis(50));
+        let client = await!(connect.timeout(Duration::from_secs(5))).unwrap();
+        let (_, peer_addr) = await!(server.accept()).unwrap();
+        assert_eq!(client.local_addr().unwrap(), peer_addr);
+        assert!(client.peer_addr().unwrap().is_ipv4());
+    });
+
+    let e = executor::block_on(romio::TcpStream::connect_happy_eyeballs("127.0.0.1", 1));
+    assert!(e.is_err());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn dropped_connects_release_their_sockets() {
+    use futures::{Future, Poll};
+    use std::pin::Pin;
+
+    drop(env_logger::try_init());
+    let open_fds = || std::fs::read_dir("/proc/self/fd").unwrap().count();
+
+    // nothing answers on this address, connecting to it hangs
+    let blackhole = "10.255.255.1:80".parse().unwrap();
+    let before = open_fds();
+
+    for _ in 0..4096 {
+        let mut connect = romio::TcpStream::connect(&blackhole);
+
+        // poll once so that the socket is registered with the reactor
+        executor::block_on(future::poll_fn(|lw| {
+            let _ = Pin::new(&mut connect).poll(lw);
+            Poll::Ready(())
+        }));
+        drop(connect);
+    }
+
+    // other tests running alongside open sockets as well
+    assert!(open_fds() < before + 256);
+}
+
+#[test]
+fn connect_with_addr_reports_the_winner() {
+    drop(env_logger::try_init());
+    let refused = {
+        let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        server.local_addr().unwrap()
+    };
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let (stream, connected) =
+            await!(romio::TcpStream::connect_multi(&[refused, addr]).with_addr()).unwrap();
+        assert_eq!(connected, addr);
+        assert_eq!(stream.peer_addr().unwrap(), addr);
+
+        let (_, connected) = await!(romio::TcpStream::connect(&addr).with_addr()).unwrap();
+        assert_eq!(connected, addr);
+
+        let e = await!(romio::TcpStream::connect(&refused).with_addr()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::ConnectionRefused);
+    });
+}
+
+#[test]
+fn connect_multi_reports_every_failure() {
+    drop(env_logger::try_init());
+    let addrs: Vec<_> = (0..2)
+        .map(|_| {
+            let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+            server.local_addr().unwrap()
+        })
+        .collect();
+
+    let e = executor::block_on(romio::TcpStream::connect_multi(&addrs)).unwrap_err();
+    for addr in &addrs {
+        assert!(e.to_string().contains(&addr.to_string()));
+    }
+
+    let e = executor::block_on(romio::TcpStream::connect_multi(&[])).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn bind_addrs_falls_back() {
+    drop(env_logger::try_init());
+    let listener = TcpListener::bind_addrs("localhost:0").unwrap();
+    assert!(listener.local_addr().unwrap().ip().is_loopback());
+
+    // an address in use is skipped, whatever its family
+    let taken = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let taken_addr = taken.local_addr().unwrap();
+    let addrs = [taken_addr, "[::1]:0".parse().unwrap(), "127.0.0.1:0".parse().unwrap()];
+    let listener = TcpListener::bind_addrs(&addrs[..]).unwrap();
+    assert_ne!(listener.local_addr().unwrap(), taken_addr);
+    assert!(listener.local_addr().unwrap().ip().is_loopback());
+}
+
+#[test]
+fn bind_addrs_reports_every_failure() {
+    drop(env_logger::try_init());
+    let taken: Vec<_> = (0..2)
+        .map(|_| TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap())
+        .collect();
+    let addrs: Vec<_> = taken.iter().map(|l| l.local_addr().unwrap()).collect();
+
+    let e = TcpListener::bind_addrs(&addrs[..]).unwrap_err();
+    for addr in &addrs {
+        assert!(e.to_string().contains(&addr.to_string()));
+    }
+
+    let e = TcpListener::bind_addrs(&addrs[..1]).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::AddrInUse);
+
+    let e = TcpListener::bind_addrs(&addrs[..0]).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn close_wakes_pending_accept() {
+    drop(env_logger::try_init());
+    let mut server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let close = server.close_handle();
+
+    let mut pool = executor::ThreadPool::new().unwrap();
+    pool.spawn(async move {
+        await!(romio::timer::Delay::new(Instant::now() + Duration::from_millis(50))).unwrap();
+        close.close();
+    }).unwrap();
+
+    let start = Instant::now();
+    executor::block_on(async {
+        assert!(await!(server.next()).is_none());
+        assert!(await!(server.accept()).is_err());
+    });
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn pause_and_resume_accepting() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    server.pause();
+    let clients: Vec<_> = (0..3).map(|_| TcpStream::connect(&addr).unwrap()).collect();
+
+    executor::block_on(async {
+        let accept = server.accept().timeout(Duration::from_millis(100));
+        let e = await!(accept).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+
+        server.resume();
+        for _ in &clients {
+            await!(server.accept().timeout(Duration::from_secs(1))).unwrap();
+        }
+    });
+}
+
+#[test]
+fn accept_rate_limit() {
+    drop(env_logger::try_init());
+    let mut server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    server.set_accept_burst(2);
+    server.set_accept_rate(Some(2));
+    let _clients: Vec<_> = (0..10).map(|_| TcpStream::connect(&addr).unwrap()).collect();
+
+    let start = Instant::now();
+    executor::block_on(async {
+        // the burst is accepted right away
+        for _ in 0..2 {
+            await!(server.next()).unwrap().unwrap();
+        }
+        assert!(start.elapsed() < Duration::from_millis(250));
+
+        // the others at two per second
+        for _ in 2..10 {
+            await!(server.next()).unwrap().unwrap();
+        }
+    });
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(3900), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(6), "{:?}", elapsed);
+
+    // without a limit the connections are accepted right away again
+    server.set_accept_rate(None);
+    let _clients: Vec<_> = (0..10).map(|_| TcpStream::connect(&addr).unwrap()).collect();
+
+    let start = Instant::now();
+    executor::block_on(async {
+        for _ in 0..10 {
+            await!(server.next()).unwrap().unwrap();
+        }
+    });
+    assert!(start.elapsed() < Duration::from_millis(250));
+}
+
+#[test]
+fn local_and_peer_addrs_match() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        let (stream, accepted_addr) = await!(server.accept()).unwrap();
+
+        // connect side
+        assert_eq!(client.peer_addr().unwrap(), addr);
+        let client_addr = client.local_addr().unwrap();
+        assert_eq!(client_addr.ip(), addr.ip());
+        assert_ne!(client_addr.port(), 0);
+
+        // accept side
+        assert_eq!(accepted_addr, client_addr);
+        assert_eq!(stream.peer_addr().unwrap(), accepted_addr);
+        assert_eq!(stream.local_addr().unwrap(), addr);
+    });
+}
+
+#[test]
+fn limit_concurrency() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let _clients: Vec<_> = (0..3).map(|_| TcpStream::connect(&addr).unwrap()).collect();
+
+    let mut pool = executor::ThreadPool::new().unwrap();
+
+    executor::block_on(async {
+        let mut incoming = server.limit_concurrency(2);
+        let (_, _, first) = await!(incoming.next()).unwrap().unwrap();
+        let (_, _, _second) = await!(incoming.next()).unwrap().unwrap();
+        assert_eq!(incoming.available(), 0);
+
+        // the third connection waits for a permit
+        let third = incoming.next().map(|accepted| accepted.unwrap());
+        let e = await!(third.timeout(Duration::from_millis(100))).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+
+        // and is accepted once one is dropped
+        pool.spawn(async move {
+            await!(romio::timer::Delay::new(Instant::now() + Duration::from_millis(50))).unwrap();
+            drop(first);
+        }).unwrap();
+
+        let start = Instant::now();
+        await!(incoming.next()).unwrap().unwrap();
+        assert!(start.elapsed() < Duration::from_millis(500));
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn raw_fd_round_trip() {
+    use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd};
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let fd = server.as_raw_fd();
+    let raw = server.into_raw_fd();
+    assert_eq!(raw, fd);
+    let server = unsafe { TcpListener::from_raw_fd(raw) };
+    assert_eq!(server.local_addr().unwrap(), addr);
+
+    executor::block_on(async {
+        let client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        let (stream, _) = await!(server.accept()).unwrap();
+
+        let mut client = unsafe { romio::TcpStream::from_raw_fd(client.into_raw_fd()) };
+        let mut stream = unsafe { romio::TcpStream::from_raw_fd(stream.into_raw_fd()) };
+
+        await!(client.write_all(THE_WINTERS_TALE)).unwrap();
+        let mut buf = vec![0; THE_WINTERS_TALE.len()];
+        await!(stream.read_exact(&mut buf)).unwrap();
+        assert_eq!(buf, THE_WINTERS_TALE);
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn ready_and_try_io() {
+    use std::os::unix::io::AsRawFd;
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let recv = |stream: &romio::TcpStream, buf: &mut [u8]| {
+        let n = unsafe {
+            libc::recv(
+                stream.as_raw_fd(),
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+                0,
+            )
+        };
+        if n < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(n as usize)
+        }
+    };
+
+    executor::block_on(async {
+        let mut client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        let (stream, _) = await!(server.accept()).unwrap();
+        let readable = mio::Ready::readable();
+
+        let ready = await!(client.ready(mio::Ready::writable())).unwrap();
+        assert!(ready.is_writable());
+
+        await!(client.write_all(b"hello")).unwrap();
+        let ready = await!(stream.ready(readable)).unwrap();
+        assert!(ready.is_readable());
+
+        let mut buf = [0; 16];
+        let n = stream.try_io(readable, || recv(&stream, &mut buf)).unwrap();
+        assert_eq!(&buf[..n], b"hello");
+
+        // draining the socket clears the readiness, so waiting blocks again
+        let err = stream.try_io(readable, || recv(&stream, &mut buf)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        let err = await!(stream.ready(readable).timeout(Duration::from_millis(100))).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        await!(client.write_all(b"again")).unwrap();
+        await!(stream.ready(readable)).unwrap();
+        let n = stream.try_io(readable, || recv(&stream, &mut buf)).unwrap();
+        assert_eq!(&buf[..n], b"again");
+    });
+}
+
+#[test]
+fn try_read_would_block_then_ready() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let (tx, rx) = mpsc::channel::<()>();
+    thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        rx.recv().unwrap();
+        client.write_all(b"hello").unwrap();
+        rx.recv().unwrap();
+        client.shutdown(Shutdown::Both).unwrap();
+    });
+
+    executor::block_on(async {
+        let (stream, _) = await!(server.accept()).unwrap();
+
+        let mut buf = [0; 16];
+        let err = stream.try_read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+        // the data arrives after the failed attempt, which still wakes up
+        // the wait for readiness
+        tx.send(()).unwrap();
+        let ready = await!(stream.ready(Interest::READABLE).timeout(Duration::from_secs(5)))
+            .unwrap();
+        assert!(ready.is_readable());
+        assert_eq!(stream.try_read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"hello");
+
+        let ready = await!(stream.ready(Interest::WRITABLE)).unwrap();
+        assert!(ready.is_writable());
+        assert_eq!(stream.try_write(b"bye").unwrap(), 3);
+
+        // once both directions are closed, that is reported as well
+        stream.shutdown(Shutdown::Write).unwrap();
+        tx.send(()).unwrap();
+        if cfg!(unix) {
+            let start = Instant::now();
+            loop {
+                let ready = await!(stream.ready(Interest::READABLE)).unwrap();
+                if ready.is_read_closed() {
+                    assert!(ready.is_write_closed());
+                    break;
+                }
+                assert!(start.elapsed() < Duration::from_secs(5));
+            }
+        }
+        assert_eq!(stream.try_read(&mut buf).unwrap(), 0);
+    });
+}
+
+#[test]
+fn reads_skip_zeroing_the_buffer() {
+    use futures::io::AsyncRead;
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let mut client = TcpStream::connect(&addr).unwrap();
+    let (mut stream, _) = executor::block_on(server.accept()).unwrap();
+
+    unsafe {
+        assert!(!stream.initializer().should_initialize());
+        assert!(!(&stream).initializer().should_initialize());
+    }
+
+    // `read_to_end` hands the spare capacity to the stream without zeroing it
+    client.write_all(THE_WINTERS_TALE).unwrap();
+    client.shutdown(Shutdown::Write).unwrap();
+    let mut buf = Vec::with_capacity(16);
+    executor::block_on(stream.read_to_end(&mut buf)).unwrap();
+    assert_eq!(buf, THE_WINTERS_TALE);
+
+    let stream = stream.with_capacity(16);
+    unsafe {
+        assert!(!stream.initializer().should_initialize());
+    }
+}
+
+#[test]
+fn tiny_reads_never_stall() {
+    use futures::Poll;
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let payload: Vec<u8> = THE_WINTERS_TALE
+        .iter()
+        .cloned()
+        .cycle()
+        .take(1 << 18)
+        .collect();
+    let expected = payload.clone();
+    thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        // written in bursts, so that the reader catches up now and then
+        for chunk in payload.chunks(16 * 1024) {
+            client.write_all(chunk).unwrap();
+            thread::sleep(Duration::from_millis(1));
+        }
+    });
+
+    executor::block_on(async {
+        let (mut stream, _) = await!(server.accept()).unwrap();
+        let mut received = Vec::with_capacity(expected.len());
+        let mut chunk = [0; 7];
+
+        loop {
+            // The socket is never drained, so readiness must stay set between
+            // the reads, or be reported again once a read would block.
+            let n = await!(stream.read(&mut chunk).timeout(Duration::from_secs(5))).unwrap();
+            if n == 0 {
+                break;
+            }
+            received.extend_from_slice(&chunk[..n]);
+
+            // go back to the executor in between
+            let mut yielded = false;
+            await!(future::poll_fn(|lw| {
+                if yielded {
+                    return Poll::Ready(());
+                }
+                yielded = true;
+                lw.wake();
+                Poll::Pending
+            }));
+        }
+
+        assert_eq!(received.len(), expected.len());
+        assert!(received == expected);
+    });
+}
+
+#[test]
+fn first_read_doesnt_wait_for_the_reactor() {
+    use futures::io::AsyncRead;
+    use futures::Poll;
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let mut client = TcpStream::connect(&addr).unwrap();
+    let (mut stream, _) = executor::block_on(server.accept()).unwrap();
+    client.write_all(b"hello").unwrap();
+    thread::sleep(Duration::from_millis(50));
+
+    // the data which is already there is returned by the first poll, instead
+    // of waiting for the reactor to report the stream readable
+    let mut buf = [0; 16];
+    let first = executor::block_on(future::poll_fn(|lw| {
+        Poll::Ready(stream.poll_read(lw, &mut buf))
+    }));
+    match first {
+        Poll::Ready(n) => assert_eq!(n.unwrap(), 5),
+        Poll::Pending => panic!("the first read waited for readiness"),
+    }
+    assert_eq!(&buf[..5], b"hello");
+
+    // once a read would block, the task is still woken up for more data
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        client.write_all(b"again").unwrap();
+    });
+    let n = executor::block_on(stream.read(&mut buf)).unwrap();
+    assert_eq!(&buf[..n], b"again");
+}
+
+#[cfg(unix)]
+#[test]
+fn reactor_metrics() {
+    use std::os::unix::io::AsRawFd;
+
+    drop(env_logger::try_init());
+    let before = romio::io::reactor_metrics().unwrap();
+
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let mut client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        let (stream, _) = await!(server.accept()).unwrap();
+
+        // a new stream is assumed to be readable, which doesn't count as a
+        // wakeup
+        let mut buf = [0; 16];
+        let err = stream.try_read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+        await!(client.write_all(b"hello")).unwrap();
+        await!(stream.ready(Interest::READABLE)).unwrap();
+
+        // drain the socket behind the stream's back, so that the first read
+        // after the wakeup would block
+        let n = unsafe {
+            libc::recv(
+                stream.as_raw_fd(),
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+                0,
+            )
+        };
+        assert_eq!(n, 5);
+        let err = stream.try_read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+    });
+
+    // other tests share the global reactor, so the counters only grow
+    let after = romio::io::reactor_metrics().unwrap();
+    assert!(after.registrations_total >= before.registrations_total + 3);
+    assert!(after.wakeups > before.wakeups);
+    assert!(after.spurious_wakeups > before.spurious_wakeups);
+}
+
+#[test]
+fn closed_without_reading() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let mut client = TcpStream::connect(&addr).unwrap();
+    let (stream, _) = executor::block_on(server.accept()).unwrap();
+    let stream = Arc::new(stream);
+
+    executor::block_on(async {
+        assert!(!stream.is_closed());
+        let err = await!(stream.closed().timeout(Duration::from_millis(100))).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        // pending data isn't consumed, and keeps the stream open
+        client.write_all(b"hello").unwrap();
+        let err = await!(stream.closed().timeout(Duration::from_millis(100))).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        let mut buf = [0; 5];
+        await!((&*stream).read_exact(&mut buf)).unwrap();
+        assert_eq!(&buf, b"hello");
+    });
+
+    // a reader waiting at the same time is woken up as well
+    let reader = {
+        let stream = stream.clone();
+        thread::spawn(move || {
+            let mut buf = [0; 16];
+            executor::block_on((&*stream).read(&mut buf)).unwrap()
+        })
+    };
+    thread::sleep(Duration::from_millis(50));
+    drop(client);
+
+    executor::block_on(async {
+        await!(stream.closed().timeout(Duration::from_secs(5))).unwrap();
+        assert!(stream.is_closed());
+    });
+    assert_eq!(reader.join().unwrap(), 0);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn connect_bound() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let local = "127.0.0.2:0".parse().unwrap();
+
+    executor::block_on(async {
+        let client = await!(romio::TcpStream::connect_bound(&local, &addr)).unwrap();
+        let (stream, peer_addr) = await!(server.accept()).unwrap();
+
+        let client_addr = client.local_addr().unwrap();
+        assert_eq!(client_addr.ip(), local.ip());
+        assert_ne!(client_addr.port(), 0);
+        assert_eq!(peer_addr, client_addr);
+        assert_eq!(stream.peer_addr().unwrap(), client_addr);
+    });
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn builder_connects_from_local_addr() {
+    use romio::tcp::TcpStreamBuilder;
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let local = "127.0.0.2:0".parse().unwrap();
+
+    executor::block_on(async {
+        let client = await!(TcpStreamBuilder::new().local_addr(local).connect(&addr)).unwrap();
+        let (_, peer_addr) = await!(server.accept()).unwrap();
+        assert_eq!(peer_addr.ip(), local.ip());
+        assert_eq!(client.local_addr().unwrap(), peer_addr);
+    });
+}
+
+#[test]
+fn connect_bound_reports_bind_errors() {
+    use romio::tcp::TcpStreamBuilder;
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // the listener's own address can't be bound again
+    let e = executor::block_on(romio::TcpStream::connect_bound(&addr, &addr)).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::AddrInUse);
+    assert!(e.to_string().contains(&format!("bind to {} failed", addr)));
+
+    let connect = TcpStreamBuilder::new().local_addr(addr).connect(&addr);
+    let e = executor::block_on(connect).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::AddrInUse);
+    if cfg!(unix) {
+        assert_eq!(e.raw_os_error(), Some(libc::EADDRINUSE));
+    }
+    assert_eq!(e.op(), Operation::Bind);
+    assert_eq!(e.socket_addr(), Some(addr));
+}
+
+#[test]
+fn errors_name_the_address() {
+    drop(env_logger::try_init());
+    let addr = {
+        let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        server.local_addr().unwrap()
+    };
+
+    let e = executor::block_on(romio::TcpStream::connect(&addr)).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::ConnectionRefused);
+    let msg = format!("connect to {} failed: ", addr);
+    assert!(e.to_string().starts_with(&msg));
+
+    assert_eq!(e.op(), Operation::Connect);
+    assert_eq!(e.socket_addr(), Some(addr));
+    assert_eq!(e.path(), None);
+    if cfg!(unix) {
+        assert_eq!(e.raw_os_error(), Some(libc::ECONNREFUSED));
+    }
+
+    // converting to an `io::Error` keeps the address
+    let e = io::Error::from(e);
+    assert_eq!(e.kind(), io::ErrorKind::ConnectionRefused);
+    assert!(e.to_string().starts_with(&msg));
+    assert_eq!(AddrError::from_io(&e).unwrap().socket_addr(), Some(addr));
+
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let e = TcpListener::bind(&addr).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::AddrInUse);
+    if cfg!(unix) {
+        assert_eq!(e.raw_os_error(), Some(libc::EADDRINUSE));
+    }
+    assert_eq!(e.op(), Operation::Bind);
+    assert_eq!(e.socket_addr(), Some(addr));
+}
+
+#[test]
+fn ttl_round_trip() {
+    use romio::tcp::TcpStreamBuilder;
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let client = await!(TcpStreamBuilder::new().ttl(7).connect(&addr)).unwrap();
+        assert_eq!(client.ttl().unwrap(), 7);
+
+        client.set_ttl(100).unwrap();
+        assert_eq!(client.ttl().unwrap(), 100);
+
+        let (stream, peer_addr) = await!(server.accept()).unwrap();
+        assert_eq!(peer_addr, client.local_addr().unwrap());
+        stream.set_ttl(42).unwrap();
+        assert_eq!(stream.ttl().unwrap(), 42);
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn mss_round_trip() {
+    use romio::tcp::TcpStreamBuilder;
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        // an unclamped loopback connection uses large segments
+        let client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        let mss = client.mss().unwrap();
+        assert!(mss >= 536 && mss <= 65535, "implausible mss {}", mss);
+        drop(await!(server.accept()).unwrap());
+
+        // the clamp applies to both ends of the connection, minus the space
+        // taken by TCP options
+        let client = await!(TcpStreamBuilder::new().mss(1000).connect(&addr)).unwrap();
+        let (stream, _) = await!(server.accept()).unwrap();
+        let mss = client.mss().unwrap();
+        assert!(mss > 900 && mss <= 1000, "unclamped mss {}", mss);
+        let mss = stream.mss().unwrap();
+        assert!(mss > 900 && mss <= 1000, "unclamped peer mss {}", mss);
+    });
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn device_round_trip() {
+    use romio::tcp::TcpStreamBuilder;
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    assert_eq!(server.device().unwrap(), None);
+
+    let err = server.set_device(Some("an-interface-name")).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    match server.set_device(Some("lo")) {
+        Ok(()) => {}
+        // binding to a device may need CAP_NET_RAW
+        Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => return,
+        Err(e) => panic!("{}", e),
+    }
+    assert_eq!(server.device().unwrap(), Some("lo".to_string()));
+
+    executor::block_on(async {
+        let client = await!(TcpStreamBuilder::new().device("lo").connect(&addr)).unwrap();
+        assert_eq!(client.device().unwrap(), Some("lo".to_string()));
+        client.set_device(None).unwrap();
+        assert_eq!(client.device().unwrap(), None);
+
+        let (_, peer_addr) = await!(server.accept()).unwrap();
+        assert_eq!(peer_addr, client.local_addr().unwrap());
+    });
+
+    server.set_device(None).unwrap();
+    assert_eq!(server.device().unwrap(), None);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn mark_round_trip() {
+    use romio::tcp::TcpStreamBuilder;
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    assert_eq!(server.mark().unwrap(), 0);
+
+    match server.set_mark(7) {
+        Ok(()) => assert_eq!(server.mark().unwrap(), 7),
+        // marking needs CAP_NET_ADMIN, the error is passed on as is
+        Err(e) => {
+            assert_eq!(e.raw_os_error(), Some(libc::EPERM));
+            let connect = TcpStreamBuilder::new().mark(7).connect(&addr);
+            let err = executor::block_on(connect).unwrap_err();
+            assert_eq!(err.raw_os_error(), Some(libc::EPERM));
+            return;
+        }
+    }
+
+    executor::block_on(async {
+        let client = await!(TcpStreamBuilder::new().mark(42).connect(&addr)).unwrap();
+        assert_eq!(client.mark().unwrap(), 42);
+        client.set_mark(0).unwrap();
+        assert_eq!(client.mark().unwrap(), 0);
+    });
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn priority_round_trip() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    assert_eq!(server.priority().unwrap(), 0);
+
+    for priority in 0..=6 {
+        server.set_priority(priority).unwrap();
+        assert_eq!(server.priority().unwrap(), priority);
+    }
+
+    match server.set_priority(7) {
+        Ok(()) => assert_eq!(server.priority().unwrap(), 7),
+        // priorities above 6 need CAP_NET_ADMIN, the error is passed on as is
+        Err(e) => {
+            assert_eq!(e.kind(), io::ErrorKind::PermissionDenied);
+            assert_eq!(server.priority().unwrap(), 6);
+        }
+    }
+    server.set_priority(4).unwrap();
+
+    executor::block_on(async {
+        let client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        let (stream, _) = await!(server.accept()).unwrap();
+        // accepted connections inherit the priority of the listener
+        assert_eq!(stream.priority().unwrap(), 4);
+
+        assert_eq!(client.priority().unwrap(), 0);
+        for priority in 0..=6 {
+            client.set_priority(priority).unwrap();
+            assert_eq!(client.priority().unwrap(), priority);
+        }
+    });
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn congestion_round_trip() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        let algorithm = client.congestion().unwrap();
+        assert!(!algorithm.is_empty());
+        assert!(!algorithm.contains('\0'));
+
+        client.set_congestion(&algorithm).unwrap();
+        assert_eq!(client.congestion().unwrap(), algorithm);
+
+        // reno is built into every kernel, and always allowed
+        client.set_congestion("reno").unwrap();
+        assert_eq!(client.congestion().unwrap(), "reno");
+
+        // the kernel's error is passed on as is
+        let err = client.set_congestion("romio-none").unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::ENOENT));
+    });
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn user_timeout_round_trip() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        assert_eq!(client.user_timeout().unwrap(), Duration::from_secs(0));
+
+        client.set_user_timeout(Duration::from_secs(30)).unwrap();
+        assert_eq!(client.user_timeout().unwrap(), Duration::from_secs(30));
+
+        // rounded up to whole milliseconds
+        client.set_user_timeout(Duration::from_micros(1500)).unwrap();
+        assert_eq!(client.user_timeout().unwrap(), Duration::from_millis(2));
+
+        client.set_user_timeout(Duration::from_secs(0)).unwrap();
+        assert_eq!(client.user_timeout().unwrap(), Duration::from_secs(0));
+    });
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn tcp_info_after_echo() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let mut client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        let (mut stream, _) = await!(server.accept()).unwrap();
+
+        let mut buf = vec![0; THE_WINTERS_TALE.len()];
+        for _ in 0..10 {
+            await!(client.write_all(THE_WINTERS_TALE)).unwrap();
+            await!(stream.read_exact(&mut buf)).unwrap();
+            await!(stream.write_all(&buf)).unwrap();
+            await!(client.read_exact(&mut buf)).unwrap();
+        }
+
+        let info = client.tcp_info().unwrap();
+        assert!(info.rtt > Duration::from_secs(0));
+        assert!(info.snd_cwnd > 0);
+        assert!(info.snd_mss > 0);
+        assert_eq!(info.total_retrans, 0);
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn oob_round_trip() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        let (mut stream, _) = await!(server.accept()).unwrap();
+
+        // only the last byte is urgent, the one before it is regular data
+        assert_eq!(await!(client.send_oob(b"ab")).unwrap(), 2);
+
+        let mut urgent = [0; 1];
+        assert_eq!(await!(stream.recv_oob(&mut urgent)).unwrap(), 1);
+        assert_eq!(&urgent, b"b");
+
+        let mut buf = [0; 1];
+        await!(stream.read_exact(&mut buf)).unwrap();
+        assert_eq!(&buf, b"a");
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn oob_inline() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        let (mut stream, _) = await!(server.accept()).unwrap();
+
+        assert!(!stream.oob_inline().unwrap());
+        stream.set_oob_inline(true).unwrap();
+        assert!(stream.oob_inline().unwrap());
+
+        assert_eq!(await!(client.send_oob(b"!")).unwrap(), 1);
+
+        let mut buf = [0; 1];
+        await!(stream.read_exact(&mut buf)).unwrap();
+        assert_eq!(&buf, b"!");
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn tos_round_trip() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        client.set_tos(0xb8).unwrap();
+        assert_eq!(client.tos().unwrap(), 0xb8);
+
+        let err = client.set_traffic_class(0xb8).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = client.traffic_class().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn traffic_class_round_trip() {
+    drop(env_logger::try_init());
+    let server = match TcpListener::bind(&"[::1]:0".parse().unwrap()) {
+        Ok(server) => server,
+        // IPv6 isn't available everywhere
+        Err(_) => return,
+    };
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        client.set_traffic_class(0xb8).unwrap();
+        assert_eq!(client.traffic_class().unwrap(), 0xb8);
+
+        let err = client.set_tos(0xb8).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = client.tos().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    });
+}
+
+#[test]
+fn read_owned() {
+    use bytes::BytesMut;
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // client thread
+    thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        client.write_all(THE_WINTERS_TALE).unwrap();
+    });
+
+    executor::block_on(async {
+        let (mut stream, _) = await!(server.accept()).unwrap();
+
+        // the data is appended, and more room is made once the buffer is full
+        let mut buf = BytesMut::with_capacity(8);
+        loop {
+            let (res, returned) = await!(stream.read_owned(buf));
+            buf = returned;
+            if res.unwrap() == 0 {
+                break;
+            }
+        }
+        assert_eq!(&buf[..], THE_WINTERS_TALE);
+    });
+}
+
+#[test]
+fn keepalive_params_round_trip() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        assert_eq!(client.keepalive_params().unwrap(), None);
+
+        let idle = Duration::from_secs(15);
+        client.set_keepalive_params(idle, Duration::from_millis(4500), 3).unwrap();
+        let params = client.keepalive_params().unwrap().unwrap();
+        assert_eq!(params.idle, idle);
+
+        if cfg!(any(target_os = "linux", target_os = "macos", target_os = "freebsd")) {
+            // the interval is rounded up to whole seconds
+            assert_eq!(params.interval, Some(Duration::from_secs(5)));
+            assert_eq!(params.retries, Some(3));
+        }
+    });
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+#[test]
+fn cork_round_trip() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let mut client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        let (mut stream, _) = await!(server.accept()).unwrap();
+        assert_eq!(client.cork().unwrap(), false);
+
+        client.set_cork(true).unwrap();
+        assert_eq!(client.cork().unwrap(), true);
+        await!(client.write_all(THE_WINTERS_TALE)).unwrap();
+
+        // uncorking flushes what was held back
+        client.set_cork(false).unwrap();
+        assert_eq!(client.cork().unwrap(), false);
+
+        let mut buf = vec![0; THE_WINTERS_TALE.len()];
+        await!(stream.read_exact(&mut buf)).unwrap();
+        assert_eq!(buf, THE_WINTERS_TALE);
+    });
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+#[test]
+fn corked_uncorks_on_drop() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let mut client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        let (mut stream, _) = await!(server.accept()).unwrap();
+
+        // bailing out with an error still uncorks
+        let res = async {
+            let mut corked = client.corked()?;
+            assert_eq!(corked.cork()?, true);
+            await!(corked.write_all(THE_WINTERS_TALE))?;
+            Err::<(), _>(io::Error::new(io::ErrorKind::Other, "abandoned"))
+        };
+        assert!(await!(res).is_err());
+        assert_eq!(client.cork().unwrap(), false);
+
+        let mut buf = vec![0; THE_WINTERS_TALE.len()];
+        await!(stream.read_exact(&mut buf).timeout(Duration::from_secs(5))).unwrap();
+        assert_eq!(buf, THE_WINTERS_TALE);
+
+        let corked = client.corked().unwrap();
+        corked.uncork().unwrap();
+        assert_eq!(client.cork().unwrap(), false);
+    });
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn quickack_round_trip() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let mut client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        let (mut stream, _) = await!(server.accept()).unwrap();
+
+        stream.set_quickack(true).unwrap();
+        assert_eq!(stream.quickack().unwrap(), true);
+        stream.set_quickack(false).unwrap();
+        assert_eq!(stream.quickack().unwrap(), false);
+
+        stream.set_quickack(true).unwrap();
+        await!(client.write_all(THE_WINTERS_TALE)).unwrap();
+        let mut buf = vec![0; THE_WINTERS_TALE.len()];
+        await!(stream.read_exact(&mut buf)).unwrap();
+        assert_eq!(buf, THE_WINTERS_TALE);
+        stream.set_quickack(true).unwrap();
+        assert_eq!(stream.quickack().unwrap(), true);
+    });
+}
+
+#[test]
+fn read_clean_close() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        let (mut stream, _) = await!(server.accept()).unwrap();
+
+        client.write_all(THE_WINTERS_TALE).unwrap();
+        drop(client);
+
+        let mut buf = Vec::new();
+        await!(stream.read_to_end(&mut buf)).unwrap();
+        assert_eq!(buf, THE_WINTERS_TALE);
+
+        let mut buf = [0; 16];
+        assert_eq!(await!(stream.read(&mut buf)).unwrap(), 0);
+    });
+}
+
+#[test]
+fn read_connection_reset() {
+    use net2::TcpStreamExt;
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let client = TcpStream::connect(&addr).unwrap();
+        let (mut stream, _) = await!(server.accept()).unwrap();
+
+        // A zero linger timeout makes close send a RST rather than a FIN.
+        client.set_linger(Some(Duration::from_secs(0))).unwrap();
+        drop(client);
+
+        let mut buf = [0; 16];
+        let err = await!(stream.read(&mut buf)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+    });
+}
+
+#[test]
+fn try_clone() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let mut writer = await!(romio::TcpStream::connect(&addr)).unwrap();
+        let mut reader = writer.try_clone().unwrap();
+        let (mut stream, _) = await!(server.accept()).unwrap();
+
+        // echo whatever arrives from the writer back to the reader
+        await!(writer.write_all(THE_WINTERS_TALE)).unwrap();
+        let mut buf = vec![0; THE_WINTERS_TALE.len()];
+        await!(stream.read_exact(&mut buf)).unwrap();
+        await!(stream.write_all(&buf)).unwrap();
+
+        let mut echoed = vec![0; THE_WINTERS_TALE.len()];
+        await!(reader.read_exact(&mut echoed)).unwrap();
+        assert_eq!(echoed, THE_WINTERS_TALE);
+
+        // the connection stays open as long as one of the handles does
+        drop(writer);
+        await!(stream.write_all(b"still there")).unwrap();
+        let mut buf = [0; 11];
+        await!(reader.read_exact(&mut buf)).unwrap();
+        assert_eq!(&buf, b"still there");
+    });
+}
+
+#[test]
+fn buffered_small_reads() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // client thread
+    thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        client.write_all(THE_WINTERS_TALE).unwrap();
+    });
+
+    executor::block_on(async {
+        let (stream, _) = await!(server.accept()).unwrap();
+        let mut stream = stream.with_capacity(16);
+        assert_eq!(stream.capacity(), 16);
+
+        // read a byte at a time, the rest of each chunk stays buffered
+        let mut buf = Vec::new();
+        let mut byte = [0; 1];
+        while await!(stream.read(&mut byte)).unwrap() == 1 {
+            buf.push(byte[0]);
+            assert!(stream.buffer().len() < 16);
+        }
+        assert_eq!(buf, THE_WINTERS_TALE);
+
+        // reads larger than the buffer go to the socket directly
+        let mut large = [0; 32];
+        assert_eq!(await!(stream.read(&mut large)).unwrap(), 0);
+    });
+}
+
+#[test]
+fn buffered_read_line_across_segments() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // client thread
+    thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        client.set_nodelay(true).unwrap();
+        client.write_all(b"HELO exa").unwrap();
+        thread::sleep(Duration::from_millis(50));
+        client.write_all(b"mple.com\r\nQUIT\n\nbye").unwrap();
+    });
+
+    executor::block_on(async {
+        let (stream, _) = await!(server.accept()).unwrap();
+        // smaller than the first line, which has to be reassembled
+        let mut stream = stream.with_capacity(8);
+
+        let mut line = String::new();
+        assert_eq!(await!(stream.read_line(&mut line)).unwrap(), 18);
+        assert_eq!(line, "HELO example.com");
+
+        line.clear();
+        assert_eq!(await!(stream.read_line(&mut line)).unwrap(), 5);
+        assert_eq!(line, "QUIT");
+
+        line.clear();
+        assert_eq!(await!(stream.read_line(&mut line)).unwrap(), 1);
+        assert_eq!(line, "");
+
+        // the last line doesn't need a terminator
+        line.clear();
+        assert_eq!(await!(stream.read_line(&mut line)).unwrap(), 3);
+        assert_eq!(line, "bye");
+
+        assert_eq!(await!(stream.read_line(&mut line)).unwrap(), 0);
+        assert_eq!(line, "bye");
+    });
+}
+
+#[test]
+fn buffered_read_line_max_length() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // client thread
+    thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        client.write_all(b"short\r\n").unwrap();
+        client.write_all(&[b'a'; 1024]).unwrap();
+    });
+
+    executor::block_on(async {
+        let (stream, _) = await!(server.accept()).unwrap();
+        let mut stream = stream.with_capacity(16);
+        stream.set_max_line_length(Some(5));
+        assert_eq!(stream.max_line_length(), Some(5));
+
+        // the terminator doesn't count against the limit
+        let mut line = String::new();
+        assert_eq!(await!(stream.read_line(&mut line)).unwrap(), 7);
+        assert_eq!(line, "short");
+
+        let err = await!(stream.read_line(&mut line)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    });
+}
+
+#[test]
+fn incoming_survives_reset_before_accept() {
+    use net2::TcpStreamExt;
+
+    drop(env_logger::try_init());
+    let mut server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // The first connection is reset while it waits in the backlog, which some
+    // platforms report as `ECONNABORTED` when accepting it.
+    let aborted = TcpStream::connect(&addr).unwrap();
+    aborted.set_linger(Some(Duration::from_secs(0))).unwrap();
+    drop(aborted);
+
+    let mut client = TcpStream::connect(&addr).unwrap();
+    client.write_all(THE_WINTERS_TALE).unwrap();
+    drop(client);
+
+    executor::block_on(async {
+        loop {
+            let mut stream = await!(server.next()).unwrap().unwrap();
+            let mut buf = Vec::new();
+            match await!(stream.read_to_end(&mut buf)) {
+                Ok(_) if !buf.is_empty() => {
+                    assert_eq!(buf, THE_WINTERS_TALE);
+                    break;
+                }
+                // the aborted connection, where the platform accepts it
+                Ok(_) => {}
+                Err(e) => assert_eq!(e.kind(), io::ErrorKind::ConnectionReset),
+            }
+        }
+    });
+}
+
+#[test]
+fn accept_error_policy() {
+    use romio::tcp::AcceptErrorPolicy;
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    assert_eq!(server.accept_error_policy(), AcceptErrorPolicy::Continue);
+
+    server.set_accept_error_policy(AcceptErrorPolicy::Halt);
+    assert_eq!(server.accept_error_policy(), AcceptErrorPolicy::Halt);
+
+    server.set_accept_error_policy(AcceptErrorPolicy::ReportAll);
+    assert_eq!(server.accept_error_policy(), AcceptErrorPolicy::ReportAll);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn accept_errors_end_the_stream_only_when_halting() {
+    use romio::tcp::AcceptErrorPolicy;
+    use std::os::unix::io::AsRawFd;
+
+    // Shutting down a listening socket makes every accept fail with `EINVAL`
+    // on Linux, an error which is neither transient nor about descriptors.
+    fn fail_accepts(server: &TcpListener) {
+        assert_eq!(
+            unsafe { libc::shutdown(server.as_raw_fd(), libc::SHUT_RD) },
+            0
+        );
+    }
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let client = TcpStream::connect(&addr).unwrap();
+
+    executor::block_on(async {
+        let mut incoming = server.incoming_with_addr();
+        let (_, peer_addr) = await!(incoming.next()).unwrap().unwrap();
+        assert_eq!(peer_addr, client.local_addr().unwrap());
+
+        // by default the error is returned and the stream keeps accepting
+        fail_accepts(&server);
+        for _ in 0..2 {
+            let err = await!(incoming.next()).unwrap().unwrap_err();
+            assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+        }
+        assert!(!server.close_handle().is_closed());
+    });
+
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    server.set_accept_error_policy(AcceptErrorPolicy::Halt);
+
+    executor::block_on(async {
+        let mut incoming = server.incoming_with_addr();
+        fail_accepts(&server);
+
+        // halting returns the error once, and then ends the stream
+        let err = await!(incoming.next()).unwrap().unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+        assert!(await!(incoming.next()).is_none());
+        assert!(server.close_handle().is_closed());
+    });
+
+    // and so does the stream limiting concurrency
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    server.set_accept_error_policy(AcceptErrorPolicy::Halt);
+
+    executor::block_on(async {
+        let mut incoming = server.limit_concurrency(1);
+        fail_accepts(&server);
+
+        let err = await!(incoming.next()).unwrap().unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+        assert!(await!(incoming.next()).is_none());
+    });
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn connect_fastopen() {
+    drop(env_logger::try_init());
+    let enabled = std::fs::read_to_string("/proc/sys/net/ipv4/tcp_fastopen")
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok())
+        .map_or(false, |flags| flags & 1 != 0);
+    if !enabled {
+        return;
+    }
+
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let (client, in_syn) =
+            await!(romio::TcpStream::connect_fastopen(&addr, THE_WINTERS_TALE)).unwrap();
+        assert!(in_syn <= THE_WINTERS_TALE.len());
+        let (mut stream, peer_addr) = await!(server.accept()).unwrap();
+        assert_eq!(peer_addr, client.local_addr().unwrap());
+
+        let mut buf = vec![0; THE_WINTERS_TALE.len()];
+        await!(stream.read_exact(&mut buf)).unwrap();
+        assert_eq!(buf, THE_WINTERS_TALE);
+    });
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn defer_accept_round_trips() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    assert_eq!(server.defer_accept().unwrap(), None);
+
+    server.set_defer_accept(Some(Duration::from_secs(3))).unwrap();
+    assert_eq!(server.defer_accept().unwrap(), Some(Duration::from_secs(3)));
+
+    // rounded up to whole seconds, then to the next retransmission
+    server.set_defer_accept(Some(Duration::from_millis(2500))).unwrap();
+    assert_eq!(server.defer_accept().unwrap(), Some(Duration::from_secs(3)));
+
+    server.set_defer_accept(None).unwrap();
+    assert_eq!(server.defer_accept().unwrap(), None);
+
+    let server = TcpListenerBuilder::new()
+        .defer_accept(Duration::from_secs(7))
+        .bind(&"127.0.0.1:0".parse().unwrap())
+        .unwrap();
+    assert_eq!(server.defer_accept().unwrap(), Some(Duration::from_secs(7)));
+}
+
+#[test]
+fn accept_options_apply_to_accepted_streams() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    server.set_accept_nodelay(true);
+    server.set_accept_ttl(42);
+
+    let _clients: Vec<_> = (0..3).map(|_| TcpStream::connect(&addr).unwrap()).collect();
+
+    executor::block_on(async {
+        let (stream, _) = await!(server.accept()).unwrap();
+        assert_eq!(stream.nodelay().unwrap(), true);
+        assert_eq!(stream.ttl().unwrap(), 42);
+
+        for (stream, _) in await!(server.accept_batch(2)).unwrap() {
+            assert_eq!(stream.nodelay().unwrap(), true);
+            assert_eq!(stream.ttl().unwrap(), 42);
+        }
+    });
+}
+
+#[test]
+fn accept_many() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let clients: Vec<_> = (0..5).map(|_| TcpStream::connect(&addr).unwrap()).collect();
+
+    executor::block_on(async {
+        let mut streams = vec![];
+        let n = await!(future::poll_fn(|lw| server.poll_accept_many(lw, &mut streams, 3)));
+        assert_eq!(n.unwrap(), 3);
+        let n = await!(future::poll_fn(|lw| server.poll_accept_many(lw, &mut streams, 3)));
+        assert_eq!(n.unwrap(), 2);
+        assert_eq!(streams.len(), clients.len());
+
+        let mut peers: Vec<_> = streams.iter().map(|s| s.peer_addr().unwrap()).collect();
+        let mut expected: Vec<_> = clients.iter().map(|c| c.local_addr().unwrap()).collect();
+        peers.sort();
+        expected.sort();
+        assert_eq!(peers, expected);
+    });
+}
+
+#[test]
+fn accept_batch() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // all of the connections are queued before the first poll
+    let clients: Vec<_> = (0..10).map(|_| TcpStream::connect(&addr).unwrap()).collect();
+
+    executor::block_on(async {
+        let accepted = await!(server.accept_batch(16)).unwrap();
+        assert_eq!(accepted.len(), clients.len());
+
+        let mut peers: Vec<_> = accepted.iter().map(|(_, addr)| *addr).collect();
+        let mut expected: Vec<_> = clients.iter().map(|c| c.local_addr().unwrap()).collect();
+        peers.sort();
+        expected.sort();
+        assert_eq!(peers, expected);
+
+        // stopping at the limit keeps the listener readable for the rest
+        let more: Vec<_> = (0..5).map(|_| TcpStream::connect(&addr).unwrap()).collect();
+        assert_eq!(await!(server.accept_batch(3)).unwrap().len(), 3);
+        assert_eq!(await!(server.accept_batch(3)).unwrap().len(), 2);
+        drop(more);
+    });
+}
+
+#[test]
+fn accept_batch_is_budgeted_as_one_accept() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // more connections than the default budget of 32
+    let clients: Vec<_> = (0..64).map(|_| TcpStream::connect(&addr).unwrap()).collect();
+
+    executor::block_on(async {
+        let accepted = await!(server.accept_batch(clients.len())).unwrap();
+        assert_eq!(accepted.len(), clients.len());
+    });
+}
+
+#[test]
+fn greedy_reader_yields() {
+    use std::cell::Cell;
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // everything is buffered before the reader starts, so every read succeeds
+    // right away
+    let data = vec![0; 16 * 1024];
+    let mut client = TcpStream::connect(&addr).unwrap();
+    client.write_all(&data).unwrap();
+    drop(client);
+
+    executor::block_on(async {
+        let (mut stream, _) = await!(server.accept()).unwrap();
+        let other_ran = Cell::new(false);
+
+        let greedy = async {
+            let mut buf = [0; 1];
+            let mut read = 0;
+            while !other_ran.get() {
+                let n = await!(stream.read(&mut buf)).unwrap();
+                assert_eq!(n, 1, "the reader never yielded");
+                read += 1;
+            }
+            read
+        };
+        let other = async {
+            other_ran.set(true);
+        };
+
+        let (read, ()) = await!(greedy.join(other));
+        assert!(read < data.len());
+    });
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn reuseport_group() {
+    drop(env_logger::try_init());
+    let group = TcpListener::bind_reuseport_group(&"127.0.0.1:0".parse().unwrap(), 4).unwrap();
+    let addr = group[0].local_addr().unwrap();
+    for listener in &group {
+        assert_eq!(listener.local_addr().unwrap(), addr);
+    }
+
+    let clients: Vec<_> = (0..64).map(|_| TcpStream::connect(&addr).unwrap()).collect();
+
+    executor::block_on(async {
+        let mut counts = vec![];
+        for listener in &group {
+            let batch = listener.accept_batch(clients.len()).timeout(Duration::from_millis(100));
+            counts.push(await!(batch).map_or(0, |accepted| accepted.len()));
+        }
+
+        // the kernel spreads the connections over the listeners
+        assert_eq!(counts.iter().sum::<usize>(), clients.len());
+        assert!(counts.iter().filter(|&&n| n > 0).count() > 1);
+    });
+
+    // a listener without the option keeps the group from binding
+    let plain = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let err = TcpListener::bind_reuseport_group(&plain.local_addr().unwrap(), 2).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+}
+
+#[test]
+fn connect_refused_concurrently() {
+    drop(env_logger::try_init());
+    let addr = {
+        let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        server.local_addr().unwrap()
+    };
+
+    executor::block_on(async {
+        // all of the connects are in flight before the first one is awaited
+        let attempts: Vec<_> = (0..16).map(|_| romio::TcpStream::connect(&addr)).collect();
+
+        for attempt in attempts {
+            let err = await!(attempt).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+        }
+    });
+}
+
+#[test]
+fn concurrent_connects_race_independently() {
+    use futures::{Future, Poll};
+    use std::pin::Pin;
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let live = server.local_addr().unwrap();
+    let refused = {
+        let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        server.local_addr().unwrap()
+    };
+    // nothing answers on this address, connecting to it hangs
+    let blackhole = "10.255.255.1:80".parse().unwrap();
+
+    executor::block_on(async {
+        // every connect has a registration of its own, so a single task can
+        // poll them all and take the first one which succeeds
+        let mut connects = vec![
+            Some(romio::TcpStream::connect(&blackhole)),
+            Some(romio::TcpStream::connect(&refused)),
+            Some(romio::TcpStream::connect(&live)),
+        ];
+        let stream = await!(future::poll_fn(|lw| {
+            for (i, slot) in connects.iter_mut().enumerate() {
+                let res = match slot {
+                    Some(connect) => Pin::new(connect).poll(lw),
+                    None => continue,
+                };
+                match res {
+                    Poll::Ready(Ok(stream)) => return Poll::Ready(stream),
+                    // the blackhole may fail as unreachable instead of
+                    // hanging, which only has to happen after the others
+                    Poll::Ready(Err(e)) => {
+                        if i == 1 {
+                            assert_eq!(e.kind(), io::ErrorKind::ConnectionRefused);
+                        }
+                        *slot = None;
+                    }
+                    Poll::Pending => {}
+                }
+            }
+            Poll::Pending
+        }));
+
+        assert_eq!(stream.peer_addr().unwrap(), live);
+        let (_, peer_addr) = await!(server.accept()).unwrap();
+        assert_eq!(peer_addr, stream.local_addr().unwrap());
+
+        // the hanging connect didn't hold up the others
+        assert!(connects[0].is_some());
+    });
+}
+
+#[test]
+fn read_after_peer_closed_before_first_read() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    drop(TcpStream::connect(&addr).unwrap());
+
+    executor::block_on(async {
+        let (mut stream, _) = await!(server.accept()).unwrap();
+        // once the server shuts down its side too, the socket only reports
+        // HUP, which has to be enough to wake up the read
+        stream.shutdown(Shutdown::Write).unwrap();
+        thread::sleep(Duration::from_millis(50));
+
+        let mut buf = [0; 16];
+        let n = await!(stream.read(&mut buf).timeout(Duration::from_secs(5))).unwrap();
+        assert_eq!(n, 0);
+        let n = await!(stream.read(&mut buf).timeout(Duration::from_secs(5))).unwrap();
+        assert_eq!(n, 0);
+    });
+}
+
+#[test]
+fn read_timeout_against_silent_peer() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let _client = TcpStream::connect(&addr).unwrap();
+
+    executor::block_on(async {
+        let (stream, _) = await!(server.accept()).unwrap();
+        let mut buf = [0; 16];
+
+        let start = Instant::now();
+        let err = await!(stream.read_timeout(&mut buf, Duration::from_millis(50))).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(50));
+        assert!(elapsed < Duration::from_secs(1));
+    });
+}
+
+#[test]
+fn read_timeout_receives_data_in_time() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    thread::spawn(move || {
+        let mut client = TcpStream::connect(&addr).unwrap();
+        thread::sleep(Duration::from_millis(10));
+        client.write_all(b"hello").unwrap();
+        thread::sleep(Duration::from_secs(1));
+    });
+
+    executor::block_on(async {
+        let (stream, _) = await!(server.accept()).unwrap();
+        let mut buf = [0; 16];
+        let n = await!(stream.read_timeout(&mut buf, Duration::from_secs(5))).unwrap();
+        assert_eq!(&buf[..n], b"hello");
+    });
+}
+
+#[test]
+fn addresses_are_remembered() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let client = await!(romio::TcpStream::connect(&addr)).unwrap();
+        let (stream, peer_addr) = await!(server.accept()).unwrap();
+        assert_eq!(client.peer_addr().unwrap(), addr);
+        assert_eq!(client.local_addr().unwrap(), peer_addr);
+        assert_eq!(stream.local_addr().unwrap(), addr);
+        assert_eq!(stream.peer_addr().unwrap(), peer_addr);
+
+        // the addresses are still known once the socket is disconnected
+        drop(client);
+        stream.shutdown(Shutdown::Both).unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), peer_addr);
+        assert_eq!(stream.try_clone().unwrap().peer_addr().unwrap(), peer_addr);
+    });
+}
+
+#[test]
+fn flush_completes_with_full_send_buffer() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let _client = TcpStream::connect(&addr).unwrap();
+
+    executor::block_on(async {
+        let (mut stream, _) = await!(server.accept()).unwrap();
+
+        // the peer doesn't read, so writes block once the buffers are full
+        let chunk = vec![0; 64 * 1024];
+        loop {
+            match stream.try_write(&chunk) {
+                Ok(_) => {}
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => panic!("{}", e),
+            }
+        }
+
+        await!(stream.flush().timeout(Duration::from_secs(1))).unwrap();
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn accepted_socket_is_not_inherited() {
+    use std::os::unix::io::AsRawFd;
+    use std::process::Command;
+
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let _client = TcpStream::connect(&addr).unwrap();
+
+    let (stream, _) = executor::block_on(server.accept()).unwrap();
+    let fd = stream.as_raw_fd();
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+    assert_eq!(flags & libc::FD_CLOEXEC, libc::FD_CLOEXEC);
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("test ! -e /dev/fd/{}", fd))
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn send_and_sync() {
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    assert_send::<romio::TcpStream>();
+    assert_sync::<romio::TcpStream>();
+    assert_send::<TcpListener>();
+    assert_sync::<TcpListener>();
+    assert_send::<romio::tcp::ConnectFuture>();
+    assert_send::<romio::tcp::ConnectMulti>();
+    assert_send::<romio::tcp::ConnectHappyEyeballs>();
+    assert_send::<romio::tcp::ConnectFastOpen<'static>>();
+    assert_send::<romio::tcp::Accept<'static>>();
+    assert_send::<romio::tcp::ReadTimeout<'static, 'static>>();
+    assert_send::<romio::tcp::WriteTimeout<'static, 'static>>();
+}
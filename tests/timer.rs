@@ -0,0 +1,126 @@
+#![feature(async_await, await_macro, futures_api)]
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use futures::executor;
+use futures::future;
+use futures::io::AsyncReadExt;
+use futures::{FutureExt, StreamExt};
+
+use romio::timer::{Delay, Interval, TimeoutExt};
+use romio::{TcpListener, TcpStream};
+
+#[test]
+fn delay_completes_after_deadline() {
+    drop(env_logger::try_init());
+    let start = Instant::now();
+    let deadline = start + Duration::from_millis(100);
+
+    executor::block_on(Delay::new(deadline)).unwrap();
+    assert!(Instant::now() >= deadline);
+}
+
+#[test]
+fn delays_complete_in_order() {
+    drop(env_logger::try_init());
+    let start = Instant::now();
+
+    executor::block_on(async {
+        let long = Delay::new(start + Duration::from_millis(200));
+        let short = Delay::new(start + Duration::from_millis(50));
+
+        let (long, short) = await!(long.join(short));
+        long.unwrap();
+        short.unwrap();
+    });
+
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
+
+#[test]
+fn timeout_expires() {
+    drop(env_logger::try_init());
+    let server = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    executor::block_on(async {
+        let mut stream = await!(TcpStream::connect(&addr)).unwrap();
+        let mut buf = [0; 16];
+
+        let start = Instant::now();
+        let e = await!(stream.read(&mut buf).timeout(Duration::from_millis(100))).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    });
+}
+
+#[test]
+fn timeout_passes_through_result() {
+    drop(env_logger::try_init());
+    let ready = future::ready(Ok::<_, io::Error>(7));
+    assert_eq!(executor::block_on(ready.timeout(Duration::from_secs(1))).unwrap(), 7);
+}
+
+#[test]
+fn reset_delay() {
+    drop(env_logger::try_init());
+    let start = Instant::now();
+
+    executor::block_on(async {
+        let mut delay = Delay::new(start + Duration::from_millis(50));
+        await!(&mut delay).unwrap();
+
+        // reusing a completed delay
+        delay.reset(start + Duration::from_millis(100));
+        await!(&mut delay).unwrap();
+        assert!(Instant::now() >= start + Duration::from_millis(100));
+
+        // moving a pending delay earlier
+        let mut delay = Delay::new(start + Duration::from_secs(60));
+        let e = await!((&mut delay).timeout(Duration::from_millis(50))).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+        delay.reset(Instant::now() + Duration::from_millis(50));
+        await!(delay).unwrap();
+    });
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn interval_ticks() {
+    drop(env_logger::try_init());
+    let start = Instant::now();
+    let period = Duration::from_millis(50);
+
+    let ticks: Vec<_> = executor::block_on(Interval::new(period).take(3).collect());
+
+    for (i, tick) in ticks.iter().enumerate() {
+        assert_eq!(*tick - ticks[0], period * i as u32);
+    }
+    assert!(start.elapsed() >= period * 3);
+}
+
+#[test]
+fn interval_missed_ticks() {
+    drop(env_logger::try_init());
+    let period = Duration::from_millis(50);
+
+    let mut burst = Interval::new(period);
+    let mut skip = Interval::new_skip(period);
+    thread::sleep(period * 3 + period / 2);
+
+    executor::block_on(async {
+        // the missed ticks are yielded right away
+        let start = Instant::now();
+        for _ in 0..3 {
+            await!(burst.next()).unwrap();
+        }
+        assert!(start.elapsed() < period);
+
+        // the missed ticks are dropped
+        let first = await!(skip.next()).unwrap();
+        let second = await!(skip.next()).unwrap();
+        assert!(second - first >= period * 3);
+    });
+}
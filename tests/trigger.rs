@@ -0,0 +1,111 @@
+#![feature(async_await, await_macro, futures_api)]
+use std::net::TcpStream;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use futures::executor;
+use futures::stream;
+use futures::task::{local_waker_from_nonlocal, Wake};
+use futures::{Future, FutureExt, StreamExt};
+
+use romio::trigger::UntilExt;
+use romio::TcpListener;
+
+#[test]
+fn tripwire_clones_complete_on_cancel() {
+    drop(env_logger::try_init());
+    let (trigger, tripwire) = romio::trigger();
+    let other = tripwire.clone();
+    assert!(!tripwire.is_tripped());
+
+    let waiter = thread::spawn(move || executor::block_on(other));
+    thread::sleep(Duration::from_millis(50));
+    trigger.cancel();
+    waiter.join().unwrap();
+
+    assert!(trigger.is_cancelled());
+    assert!(tripwire.is_tripped());
+    // a clone created afterwards is tripped as well
+    executor::block_on(tripwire.clone());
+}
+
+#[test]
+fn dropped_tripwires_release_their_wakers() {
+    struct Task;
+
+    impl Wake for Task {
+        fn wake(_: &Arc<Self>) {}
+    }
+
+    drop(env_logger::try_init());
+    let (_trigger, tripwire) = romio::trigger();
+    let task = Arc::new(Task);
+    let lw = local_waker_from_nonlocal(task.clone());
+
+    // polling a tripwire again keeps only the latest waker
+    let mut first = tripwire.clone();
+    for _ in 0..10 {
+        assert!(Pin::new(&mut first).poll(&lw).is_pending());
+    }
+    let mut second = tripwire.clone();
+    assert!(Pin::new(&mut second).poll(&lw).is_pending());
+    assert_eq!(Arc::strong_count(&task), 4);
+
+    drop(first);
+    drop(second);
+    assert_eq!(Arc::strong_count(&task), 2);
+}
+
+#[test]
+fn until_ends_stream_on_cancel() {
+    drop(env_logger::try_init());
+    let (trigger, tripwire) = romio::trigger();
+
+    executor::block_on(async {
+        let mut numbers = stream::iter(0..).until(tripwire);
+        assert_eq!(await!(numbers.next()), Some(0));
+        assert_eq!(await!(numbers.next()), Some(1));
+
+        trigger.cancel();
+        assert_eq!(await!(numbers.next()), None);
+        assert_eq!(await!(numbers.next()), None);
+
+        // the stream itself is left alone
+        let mut numbers = numbers.into_inner();
+        assert_eq!(await!(numbers.next()), Some(2));
+    });
+}
+
+#[test]
+fn incoming_until_stops_accepting() {
+    drop(env_logger::try_init());
+    let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (trigger, tripwire) = romio::trigger();
+
+    let client = TcpStream::connect(&addr).unwrap();
+    executor::block_on(async {
+        let mut incoming = listener.incoming_with_addr().until(tripwire.clone());
+        let (_, peer_addr) = await!(incoming.next()).unwrap().unwrap();
+        assert_eq!(peer_addr, client.local_addr().unwrap());
+
+        // cancelling wakes up the pending accept, which ends the stream
+        let cancel = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            trigger.cancel();
+        });
+        let mut other = listener.incoming_with_addr().until(tripwire);
+        let (next, other_next) = await!(incoming.next().join(other.next()));
+        assert!(next.is_none());
+        assert!(other_next.is_none());
+        cancel.join().unwrap();
+    });
+
+    // the listener keeps working
+    assert_eq!(listener.local_addr().unwrap(), addr);
+    let client = TcpStream::connect(&addr).unwrap();
+    let (_, peer_addr) = executor::block_on(listener.accept()).unwrap();
+    assert_eq!(peer_addr, client.local_addr().unwrap());
+}
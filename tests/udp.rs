@@ -0,0 +1,239 @@
+#![feature(async_await, await_macro, futures_api)]
+use std::io;
+use std::net;
+
+use futures::executor;
+
+use romio::udp::UdpSocketBuilder;
+use romio::UdpSocket;
+
+const THE_MERCHANT_OF_VENICE: &[&[u8]] = &[
+    b"If you prick us, do we not bleed?",
+    b"If you tickle us, do we not laugh?",
+    b"If you poison us, do we not die?",
+];
+
+#[test]
+fn recv_mmsg() {
+    drop(env_logger::try_init());
+    let mut socket = UdpSocket::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = socket.local_addr().unwrap();
+
+    let sender = net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let sender_addr = sender.local_addr().unwrap();
+    for line in THE_MERCHANT_OF_VENICE {
+        sender.send_to(line, &addr).unwrap();
+    }
+
+    executor::block_on(async {
+        let mut received = vec![];
+
+        while received.len() < THE_MERCHANT_OF_VENICE.len() {
+            let mut bufs = vec![vec![0; 64]; 4];
+            let datagrams = {
+                let mut bufs: Vec<&mut [u8]> = bufs.iter_mut().map(|buf| &mut buf[..]).collect();
+                await!(socket.recv_mmsg(&mut bufs)).unwrap()
+            };
+            assert!(!datagrams.is_empty());
+
+            for (buf, (len, from)) in bufs.iter().zip(datagrams) {
+                assert_eq!(from, sender_addr);
+                received.push(buf[..len].to_vec());
+            }
+        }
+
+        assert_eq!(received, THE_MERCHANT_OF_VENICE);
+    });
+}
+
+#[test]
+fn peek_does_not_consume() {
+    drop(env_logger::try_init());
+    let mut socket = UdpSocket::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = socket.local_addr().unwrap();
+
+    let sender = net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let sender_addr = sender.local_addr().unwrap();
+    sender.send_to(THE_MERCHANT_OF_VENICE[0], &addr).unwrap();
+    sender.send_to(THE_MERCHANT_OF_VENICE[1], &addr).unwrap();
+
+    executor::block_on(async {
+        let mut buf = vec![0; 64];
+
+        let (len, from) = await!(socket.peek_from(&mut buf)).unwrap();
+        assert_eq!(from, sender_addr);
+        assert_eq!(&buf[..len], THE_MERCHANT_OF_VENICE[0]);
+
+        let mut header = [0; 6];
+        assert_eq!(await!(socket.peek(&mut header)).unwrap(), header.len());
+        assert_eq!(&header, &THE_MERCHANT_OF_VENICE[0][..6]);
+
+        let (len, from) = await!(socket.recv_from(&mut buf)).unwrap();
+        assert_eq!(from, sender_addr);
+        assert_eq!(&buf[..len], THE_MERCHANT_OF_VENICE[0]);
+
+        let (len, _) = await!(socket.peek_from(&mut buf)).unwrap();
+        assert_eq!(&buf[..len], THE_MERCHANT_OF_VENICE[1]);
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn reuse_port() {
+    drop(env_logger::try_init());
+    let first = UdpSocketBuilder::new()
+        .reuse_port(true)
+        .bind(&"127.0.0.1:0".parse().unwrap())
+        .unwrap();
+    let addr = first.local_addr().unwrap();
+
+    let second = UdpSocketBuilder::new()
+        .reuse_port(true)
+        .bind(&addr)
+        .unwrap();
+    assert_eq!(second.local_addr().unwrap(), addr);
+
+    assert!(UdpSocket::bind(&addr).is_err());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn device_round_trip() {
+    drop(env_logger::try_init());
+    let socket = UdpSocket::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    assert_eq!(socket.device().unwrap(), None);
+
+    // names which don't fit are rejected instead of truncated
+    let err = socket.set_device(Some("an-interface-name")).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    match socket.set_device(Some("lo")) {
+        Ok(()) => {}
+        // binding to a device may need CAP_NET_RAW
+        Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => return,
+        Err(e) => panic!("{}", e),
+    }
+    assert_eq!(socket.device().unwrap(), Some("lo".to_string()));
+
+    socket.set_device(None).unwrap();
+    assert_eq!(socket.device().unwrap(), None);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn mark_round_trip() {
+    drop(env_logger::try_init());
+    let socket = UdpSocket::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    assert_eq!(socket.mark().unwrap(), 0);
+
+    match socket.set_mark(7) {
+        Ok(()) => assert_eq!(socket.mark().unwrap(), 7),
+        // marking needs CAP_NET_ADMIN, the error is passed on as is
+        Err(e) => assert_eq!(e.raw_os_error(), Some(libc::EPERM)),
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn priority_round_trip() {
+    drop(env_logger::try_init());
+    let socket = UdpSocket::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    assert_eq!(socket.priority().unwrap(), 0);
+
+    for priority in 0..=6 {
+        socket.set_priority(priority).unwrap();
+        assert_eq!(socket.priority().unwrap(), priority);
+    }
+
+    match socket.set_priority(7) {
+        Ok(()) => assert_eq!(socket.priority().unwrap(), 7),
+        // priorities above 6 need CAP_NET_ADMIN, the error is passed on as is
+        Err(e) => assert_eq!(e.kind(), io::ErrorKind::PermissionDenied),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+#[test]
+fn priority_is_unsupported() {
+    drop(env_logger::try_init());
+    let socket = UdpSocket::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    assert_eq!(socket.priority().unwrap_err().kind(), io::ErrorKind::Other);
+    assert_eq!(
+        socket.set_priority(1).unwrap_err().kind(),
+        io::ErrorKind::Other
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn bind_device() {
+    drop(env_logger::try_init());
+    let mut socket = UdpSocket::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = socket.local_addr().unwrap();
+
+    match socket.bind_device("lo") {
+        Ok(()) => {}
+        // binding to a device needs CAP_NET_RAW
+        Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => return,
+        Err(e) => panic!("{}", e),
+    }
+    assert!(socket.bind_device("lo\0").is_err());
+
+    let sender = net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    sender.send_to(THE_MERCHANT_OF_VENICE[0], &addr).unwrap();
+
+    executor::block_on(async {
+        let mut buf = vec![0; 64];
+        let (len, _) = await!(socket.recv_from(&mut buf)).unwrap();
+        assert_eq!(&buf[..len], THE_MERCHANT_OF_VENICE[0]);
+    });
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn recv_from_with_dst_and_reply() {
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+    drop(env_logger::try_init());
+    let mut socket = UdpSocket::bind(&"0.0.0.0:0".parse().unwrap()).unwrap();
+    let port = socket.local_addr().unwrap().port();
+    assert!(!socket.pktinfo().unwrap());
+
+    let client = net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let client_addr = client.local_addr().unwrap();
+    // every address in 127.0.0.0/8 is local on Linux
+    let local = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2));
+
+    executor::block_on(async {
+        // without the option the destination is unknown, but the datagram
+        // is still received
+        client
+            .send_to(THE_MERCHANT_OF_VENICE[0], ("127.0.0.1", port))
+            .unwrap();
+        let mut buf = [0; 64];
+        let (len, from, to) = await!(socket.recv_from_with_dst(&mut buf)).unwrap();
+        assert_eq!(&buf[..len], THE_MERCHANT_OF_VENICE[0]);
+        assert_eq!(from, client_addr);
+        assert_eq!(to, None);
+
+        socket.set_pktinfo(true).unwrap();
+        assert!(socket.pktinfo().unwrap());
+
+        client
+            .send_to(THE_MERCHANT_OF_VENICE[1], (local, port))
+            .unwrap();
+        let (len, from, to) = await!(socket.recv_from_with_dst(&mut buf)).unwrap();
+        assert_eq!(&buf[..len], THE_MERCHANT_OF_VENICE[1]);
+        assert_eq!(from, client_addr);
+        assert_eq!(to, Some(local));
+        let to = to.unwrap();
+
+        // the reply comes from the address the datagram was sent to
+        await!(socket.send_to_from(THE_MERCHANT_OF_VENICE[2], &from, &to)).unwrap();
+    });
+
+    let mut buf = [0; 64];
+    let (len, from) = client.recv_from(&mut buf).unwrap();
+    assert_eq!(&buf[..len], THE_MERCHANT_OF_VENICE[2]);
+    assert_eq!(from, SocketAddr::new(local, port));
+}
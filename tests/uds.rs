@@ -1,13 +1,18 @@
 #![cfg(unix)]
 #![feature(async_await, await_macro, pin)]
-use std::io::{Read, Write};
+use std::cmp;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
 use std::os::unix::net::UnixStream as StdStream;
 use std::thread;
 
 use futures::executor;
-use futures::future::FutureObj;
+use futures::future::{self, FutureObj};
 use futures::io::{AsyncReadExt, AsyncWriteExt};
+use futures::task::SpawnExt;
 use futures::StreamExt;
+use iovec::IoVec;
 use tempdir::TempDir;
 
 use romio::uds::{UnixListener, UnixStream};
@@ -73,6 +78,38 @@ fn listener_writes() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn incoming_with_addr() -> Result<(), Error> {
+    drop(env_logger::try_init());
+    let tmp_dir = TempDir::new("incoming_with_addr")?;
+    let file_path = tmp_dir.path().join("sock");
+
+    let listener = UnixListener::bind(&file_path)?;
+
+    // client thread
+    let client = thread::spawn(move || {
+        let mut client = StdStream::connect(&file_path).unwrap();
+        client.write_all(THE_WINTERS_TALE).unwrap();
+        client.local_addr().unwrap()
+    });
+
+    executor::block_on(async {
+        let mut incoming = listener.incoming_with_addr();
+        let (mut stream, peer_addr) = await!(incoming.next()).unwrap().unwrap();
+
+        let mut buf = vec![0; THE_WINTERS_TALE.len()];
+        await!(stream.read_exact(&mut buf)).unwrap();
+        assert_eq!(buf, THE_WINTERS_TALE);
+
+        // the client never bound a path, so neither side has a name for it
+        let client_addr = client.join().unwrap();
+        assert!(client_addr.is_unnamed());
+        assert!(peer_addr.is_unnamed());
+    });
+
+    Ok(())
+}
+
 #[test]
 fn both_sides_async_using_threadpool() -> Result<(), Error>{
     drop(env_logger::try_init());
@@ -99,3 +136,437 @@ fn both_sides_async_using_threadpool() -> Result<(), Error>{
 
     Ok(())
 }
+
+#[test]
+fn vectored_io_across_buffers() -> Result<(), Error> {
+    drop(env_logger::try_init());
+    let (mut writer, mut reader) = UnixStream::pair()?;
+
+    let header = b"THE WINTER'S TALE\n".to_vec();
+    let body: Vec<u8> = THE_WINTERS_TALE.iter().cloned().cycle().take(1 << 20).collect();
+    let expected = [&header[..], &body[..]].concat();
+    let total = expected.len();
+
+    // the socket buffer is much smaller than the body, so the writes are short
+    // and have to resume in the middle of an iovec
+    let mut pool = executor::ThreadPool::new()?;
+    pool.spawn(async move {
+        let mut written = 0;
+        while written < total {
+            let h = &header[cmp::min(written, header.len())..];
+            let b = &body[written.saturating_sub(header.len())..];
+            let bufs: Vec<&IoVec> = [h, b]
+                .iter()
+                .filter(|s| !s.is_empty())
+                .map(|&s| s.into())
+                .collect();
+            written += await!(future::poll_fn(|lw| writer.poll_vectored_write(lw, &bufs))).unwrap();
+        }
+    })?;
+
+    let received = executor::block_on(async {
+        let mut first = [0; 5];
+        let mut rest = vec![0; total - first.len()];
+        let mut read = 0;
+        while read < total {
+            let f = &mut first[cmp::min(read, 5)..];
+            let r = &mut rest[read.saturating_sub(5)..];
+            let mut bufs: Vec<&mut IoVec> = vec![];
+            if !f.is_empty() {
+                bufs.push(f.into());
+            }
+            bufs.push(r.into());
+            read += await!(future::poll_fn(|lw| reader.poll_vectored_read(lw, &mut bufs))).unwrap();
+        }
+        [&first[..], &rest[..]].concat()
+    });
+
+    assert!(received == expected);
+
+    Ok(())
+}
+
+#[test]
+fn half_close() -> Result<(), Error> {
+    drop(env_logger::try_init());
+    let (mut client, mut server) = UnixStream::pair()?;
+
+    executor::block_on(async {
+        await!(client.write_all(b"request")).unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+
+        // the server sees the end of the request, but not of the connection
+        let mut request = vec![];
+        await!(server.read_to_end(&mut request)).unwrap();
+        assert_eq!(request, b"request");
+        let mut buf = [0; 16];
+        assert_eq!(await!(server.read(&mut buf)).unwrap(), 0);
+
+        await!(server.write_all(THE_WINTERS_TALE)).unwrap();
+        await!(server.write_all(THE_WINTERS_TALE)).unwrap();
+        server.shutdown(Shutdown::Write).unwrap();
+
+        let mut response = vec![];
+        await!(client.read_to_end(&mut response)).unwrap();
+        assert_eq!(response, [THE_WINTERS_TALE, THE_WINTERS_TALE].concat());
+
+        // once both sides shut down writing, writes fail
+        let err = await!(server.write_all(b"more")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    });
+
+    Ok(())
+}
+
+#[test]
+fn read_after_peer_closed_before_first_read() -> Result<(), Error> {
+    drop(env_logger::try_init());
+    let (client, mut server) = UnixStream::pair()?;
+    drop(client);
+
+    executor::block_on(async {
+        let mut buf = [0; 16];
+        assert_eq!(await!(server.read(&mut buf)).unwrap(), 0);
+        assert_eq!(await!(server.read(&mut buf)).unwrap(), 0);
+    });
+
+    Ok(())
+}
+
+#[test]
+fn reads_skip_zeroing_the_buffer() -> Result<(), Error> {
+    use futures::io::AsyncRead;
+
+    drop(env_logger::try_init());
+    let (mut client, mut server) = UnixStream::pair()?;
+
+    unsafe {
+        assert!(!server.initializer().should_initialize());
+        assert!(!(&server).initializer().should_initialize());
+    }
+
+    executor::block_on(async {
+        await!(client.write_all(THE_WINTERS_TALE)).unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+
+        // `read_to_end` hands the spare capacity to the stream without
+        // zeroing it
+        let mut buf = Vec::with_capacity(16);
+        await!(server.read_to_end(&mut buf)).unwrap();
+        assert_eq!(buf, THE_WINTERS_TALE);
+    });
+
+    Ok(())
+}
+
+#[test]
+fn datagram_send_to_and_recv_from() -> Result<(), Error> {
+    use romio::uds::UnixDatagram;
+
+    drop(env_logger::try_init());
+    let tmp_dir = TempDir::new("datagram_send_to")?;
+    let server_path = tmp_dir.path().join("server");
+    let client_path = tmp_dir.path().join("client");
+    let gone_path = tmp_dir.path().join("gone");
+
+    let mut server = UnixDatagram::bind(&server_path)?;
+    let mut client = UnixDatagram::bind(&client_path)?;
+    drop(UnixDatagram::bind(&gone_path)?);
+
+    executor::block_on(async {
+        await!(client.send_to(b"hello", &server_path)).unwrap();
+        await!(client.send_to(THE_WINTERS_TALE, &server_path)).unwrap();
+
+        let mut buf = [0; 16];
+        let (n, addr) = await!(server.recv_from(&mut buf)).unwrap();
+        assert_eq!(&buf[..n], b"hello");
+        assert_eq!(addr.as_pathname(), Some(client_path.as_path()));
+
+        // a datagram which doesn't fit is truncated
+        let (n, _) = await!(server.recv_from(&mut buf)).unwrap();
+        assert_eq!(&buf[..n], &THE_WINTERS_TALE[..16]);
+
+        // connected sockets send and receive without an address
+        client.connect(&server_path).unwrap();
+        await!(client.send(b"hello again")).unwrap();
+        let (n, _) = await!(server.recv_from(&mut buf)).unwrap();
+        assert_eq!(&buf[..n], b"hello again");
+
+        await!(server.send_to(b"hi", &client_path)).unwrap();
+        let n = await!(client.recv(&mut buf)).unwrap();
+        assert_eq!(&buf[..n], b"hi");
+
+        // sending to nobody fails right away
+        let err = await!(server.send_to(b"hello", &tmp_dir.path().join("missing"))).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let err = await!(server.send_to(b"hello", &gone_path)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+    });
+
+    Ok(())
+}
+
+#[test]
+fn datagram_incoming_messages() -> Result<(), Error> {
+    use romio::uds::UnixDatagram;
+
+    drop(env_logger::try_init());
+    let tmp_dir = TempDir::new("datagram_incoming_messages")?;
+    let server_path = tmp_dir.path().join("server");
+    let client_path = tmp_dir.path().join("client");
+
+    let server = UnixDatagram::bind(&server_path)?;
+    let client = UnixDatagram::bind(&client_path)?;
+    client.connect(&server_path)?;
+
+    let sender = thread::spawn(move || {
+        let mut client = client;
+        executor::block_on(async {
+            for line in THE_WINTERS_TALE.split(|&b| b == b'\n') {
+                await!(client.send(line)).unwrap();
+            }
+        });
+    });
+
+    let lines = THE_WINTERS_TALE.split(|&b| b == b'\n').count();
+    let messages = server.incoming_messages(32).take(lines as u64);
+    let messages: Vec<_> = executor::block_on(messages.collect());
+    sender.join().unwrap();
+
+    let expected = THE_WINTERS_TALE.split(|&b| b == b'\n');
+    for (message, line) in messages.into_iter().zip(expected) {
+        let (data, addr) = message?;
+        assert_eq!(addr.as_pathname(), Some(client_path.as_path()));
+        // longer lines are truncated
+        assert_eq!(data, &line[..cmp::min(line.len(), 32)]);
+    }
+
+    Ok(())
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn datagram_recv_from_with_cred() -> Result<(), Error> {
+    use romio::uds::UnixDatagram;
+
+    drop(env_logger::try_init());
+    let tmp_dir = TempDir::new("datagram_recv_from_with_cred")?;
+    let server_path = tmp_dir.path().join("server");
+    let client_path = tmp_dir.path().join("client");
+
+    let mut server = UnixDatagram::bind(&server_path)?;
+    let client = UnixDatagram::bind(&client_path)?;
+    assert!(!server.passcred()?);
+
+    executor::block_on(async {
+        let mut buf = [0; 16];
+
+        // without the option, the credentials are missing
+        await!(future::poll_fn(|lw| client.poll_send_to(
+            lw,
+            b"anonymous",
+            &server_path
+        )))
+        .unwrap();
+        let err = await!(server.recv_from_with_cred(&mut buf)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+
+        // the datagram is still there to be received
+        let (n, addr) = await!(future::poll_fn(|lw| server.poll_recv_from(lw, &mut buf))).unwrap();
+        assert_eq!(&buf[..n], b"anonymous");
+        assert_eq!(addr.as_pathname(), Some(client_path.as_path()));
+
+        server.set_passcred(true).unwrap();
+        assert!(server.passcred().unwrap());
+        await!(future::poll_fn(|lw| client.poll_send_to(
+            lw,
+            b"hello",
+            &server_path
+        )))
+        .unwrap();
+        let (n, addr, cred) = await!(server.recv_from_with_cred(&mut buf)).unwrap();
+        assert_eq!(&buf[..n], b"hello");
+        assert_eq!(addr.as_pathname(), Some(client_path.as_path()));
+        assert_eq!(cred.uid, unsafe { libc::getuid() });
+        assert_eq!(cred.gid, unsafe { libc::getgid() });
+    });
+
+    Ok(())
+}
+
+#[test]
+fn errors_name_the_path() -> Result<(), Error> {
+    use romio::io::Operation;
+
+    drop(env_logger::try_init());
+    let tmp_dir = TempDir::new("errors_name_the_path")?;
+    let file_path = tmp_dir.path().join("missing").join("sock");
+
+    let e = UnixListener::bind(&file_path).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::NotFound);
+    assert_eq!(e.raw_os_error(), Some(libc::ENOENT));
+    assert_eq!(e.op(), Operation::Bind);
+    assert_eq!(e.path(), Some(file_path.as_path()));
+
+    let e = executor::block_on(UnixStream::connect(&file_path)).unwrap_err();
+    let msg = format!("connect to {} failed: ", file_path.display());
+    assert!(e.to_string().starts_with(&msg));
+    assert_eq!(e.raw_os_error(), Some(libc::ENOENT));
+    assert_eq!(e.op(), Operation::Connect);
+    assert_eq!(e.path(), Some(file_path.as_path()));
+
+    Ok(())
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn connect_timeout_waits_for_room_in_the_backlog() -> Result<(), Error> {
+    use std::os::unix::net::UnixListener as StdListener;
+    use std::time::{Duration, Instant};
+
+    drop(env_logger::try_init());
+    let tmp_dir = TempDir::new("connect_timeout")?;
+    let file_path = tmp_dir.path().join("sock");
+    let listener = StdListener::bind(&file_path)?;
+
+    // connecting to a listener with a full backlog doesn't connect at all
+    let mut backlog = vec![];
+    loop {
+        let stream = executor::block_on(UnixStream::connect(&file_path))?;
+        if stream.peer_addr().is_err() {
+            break;
+        }
+        backlog.push(stream);
+        assert!(backlog.len() < 10_000);
+    }
+
+    let start = Instant::now();
+    let dur = Duration::from_millis(200);
+    let e = executor::block_on(UnixStream::connect_timeout(&file_path, dur)).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+    assert!(start.elapsed() >= dur);
+    assert_eq!(e.path(), Some(file_path.as_path()));
+
+    // accepting makes room for one more
+    let start = Instant::now();
+    let accept = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        listener.accept().unwrap();
+        listener
+    });
+    let dur = Duration::from_secs(5);
+    let stream = executor::block_on(UnixStream::connect_timeout(&file_path, dur))?;
+    stream.peer_addr()?;
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    accept.join().unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn bind_clean_removes_stale_socket() -> Result<(), Error> {
+    drop(env_logger::try_init());
+    let tmp_dir = TempDir::new("bind_clean")?;
+    let file_path = tmp_dir.path().join("sock");
+
+    // the file of a dropped listener stays around
+    drop(UnixListener::bind(&file_path)?);
+    let err = UnixListener::bind(&file_path).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+
+    let listener = UnixListener::bind_clean(&file_path)?;
+    drop(StdStream::connect(&file_path)?);
+
+    // a socket somebody listens on is left alone
+    let err = UnixListener::bind_clean(&file_path).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+    StdStream::connect(&file_path)?;
+    drop(listener);
+
+    // as is a file which isn't a socket
+    let other_path = tmp_dir.path().join("file");
+    fs::write(&other_path, b"data")?;
+    let err = UnixListener::bind_clean(&other_path).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+    assert_eq!(fs::read(&other_path)?, b"data");
+
+    Ok(())
+}
+
+#[test]
+fn unlink_on_drop() -> Result<(), Error> {
+    drop(env_logger::try_init());
+    let tmp_dir = TempDir::new("unlink_on_drop")?;
+    let file_path = tmp_dir.path().join("sock");
+
+    let listener = UnixListener::bind_unlink_on_drop(&file_path)?;
+    assert!(listener.unlink_on_drop());
+    assert!(file_path.exists());
+    drop(listener);
+    assert!(!file_path.exists());
+
+    // a file removed before the drop doesn't make it fail, and a file which
+    // replaced the socket is left alone
+    let listener = UnixListener::bind_unlink_on_drop(&file_path)?;
+    fs::remove_file(&file_path)?;
+    drop(listener);
+    let listener = UnixListener::bind_unlink_on_drop(&file_path)?;
+    fs::remove_file(&file_path)?;
+    fs::write(&file_path, b"data")?;
+    drop(listener);
+    assert_eq!(fs::read(&file_path)?, b"data");
+    fs::remove_file(&file_path)?;
+
+    // by default, the file stays around
+    let mut listener = UnixListener::bind(&file_path)?;
+    assert!(!listener.unlink_on_drop());
+    listener.set_unlink_on_drop(true)?;
+    listener.set_unlink_on_drop(false)?;
+    drop(listener);
+    assert!(file_path.exists());
+
+    Ok(())
+}
+
+#[test]
+fn accepted_socket_is_not_inherited() -> Result<(), Error> {
+    use std::os::unix::io::AsRawFd;
+    use std::process::Command;
+
+    drop(env_logger::try_init());
+    let tmp_dir = TempDir::new("cloexec")?;
+    let file_path = tmp_dir.path().join("sock");
+    let listener = UnixListener::bind(&file_path)?;
+    let _client = StdStream::connect(&file_path)?;
+
+    let (stream, _) = executor::block_on(listener.incoming_with_addr().next()).unwrap()?;
+    let fd = stream.as_raw_fd();
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+    assert_eq!(flags & libc::FD_CLOEXEC, libc::FD_CLOEXEC);
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("test ! -e /dev/fd/{}", fd))
+        .status()?;
+    assert!(status.success());
+
+    // the stream is nonblocking
+    let mut buf = [0; 16];
+    let err = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+    assert_eq!(err, -1);
+    assert_eq!(io::Error::last_os_error().kind(), io::ErrorKind::WouldBlock);
+
+    Ok(())
+}
+
+#[test]
+fn send_and_sync() {
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    assert_send::<UnixStream>();
+    assert_sync::<UnixStream>();
+    assert_send::<UnixListener>();
+    assert_sync::<UnixListener>();
+    assert_send::<romio::uds::ConnectFuture>();
+}